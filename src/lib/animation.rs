@@ -0,0 +1,653 @@
+//! Animated pictures : GIF and APNG files holding several frames
+//! Each frame is kept as a picture of its own, along with what is needed to write the animation
+//! back as it was : frame position, timing, disposal and loop count
+//! APNG frames are read and written through the png crate, one standalone PNG file per frame
+extern crate gif;
+
+use self::gif::SetParameter;
+use super::picture::{read_chunks, Chunk, Picture, GIF_SIGNATURE, PNG_SIGNATURE};
+
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{BufWriter, Error, ErrorKind, Write},
+};
+
+/// NETSCAPE application extension identifier, the extension holding GIF loop count
+const NETSCAPE_EXTENSION: &[u8] = b"NETSCAPE2.0";
+
+/// APNG chunks rebuilt when writing, every other chunk being kept as it is
+const REBUILT_CHUNKS: [&[u8; 4]; 8] = [
+    b"IHDR", b"PLTE", b"tRNS", b"acTL", b"fcTL", b"IDAT", b"fdAT", b"IEND",
+];
+
+/// How a frame is laid out and played
+#[derive(Debug, Clone, PartialEq)]
+enum FrameControl {
+    /// GIF frame, delay being given in hundredths of a second
+    Gif {
+        left: u16,
+        top: u16,
+        delay: u16,
+        dispose: gif::DisposalMethod,
+        needs_user_input: bool,
+        local_palette: bool,
+    },
+    /// APNG frame : fcTL chunk data, sequence number excluded
+    /// None for a default picture which is not part of the animation
+    Png(Option<Vec<u8>>),
+}
+
+/// Animation file format, along with what is shared by all frames
+enum AnimationFormat {
+    /// Global palette, and loop count (None if the file has no loop extension)
+    Gif {
+        global_palette: Vec<u8>,
+        repeat: Option<u16>,
+    },
+    /// Number of plays (0 for infinite), and ancillary chunks found before and after PLTE
+    Png {
+        plays: u32,
+        chunks_before_palette: Vec<Chunk>,
+        chunks_after_palette: Vec<Chunk>,
+    },
+}
+
+/// Animation : canvas dimensions, format and frames
+pub struct Animation {
+    width: u32,
+    height: u32,
+    format: AnimationFormat,
+    frames: Vec<(Picture, FrameControl)>,
+}
+
+impl Animation {
+    /// Read animation from GIF or APNG file
+    pub fn open(filepath: &str) -> Result<Animation, Error> {
+        let bytes = fs::read(filepath)?;
+
+        if bytes.starts_with(&PNG_SIGNATURE) {
+            Animation::from_png_bytes(&bytes)
+        } else if bytes.starts_with(&GIF_SIGNATURE) {
+            Animation::from_gif_bytes(&bytes)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/animation : Only GIF and APNG files can be animated!",
+            ))
+        }
+    }
+
+    /// Read every frame of GIF file contents, keeping their palettes
+    fn from_gif_bytes(bytes: &[u8]) -> Result<Animation, Error> {
+        let to_error =
+            |err: gif::DecodingError| Error::new(ErrorKind::InvalidData, err.to_string());
+
+        let mut decoder = gif::Decoder::new(bytes);
+        decoder.set(gif::ColorOutput::Indexed);
+        let mut reader = decoder.read_info().map_err(to_error)?;
+
+        let global_palette = reader.global_palette().unwrap_or(&[]).to_vec();
+        let (width, height) = (reader.width() as u32, reader.height() as u32);
+        let mut frames = Vec::new();
+
+        while let Some(frame) = reader.read_next_frame().map_err(to_error)? {
+            let palette = frame
+                .palette
+                .as_ref()
+                .unwrap_or(&global_palette)
+                .chunks(3)
+                .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                .collect();
+
+            // Transparent entry is the only one having an alpha
+            let transparency = match frame.transparent {
+                Some(index) => {
+                    let mut transparency = vec![255; index as usize];
+                    transparency.push(0);
+                    transparency
+                }
+                None => Vec::new(),
+            };
+
+            let picture = Picture::new_indexed(
+                frame.width as u32,
+                frame.height as u32,
+                palette,
+                transparency,
+                frame.buffer.to_vec(),
+            )?;
+
+            frames.push((
+                picture,
+                FrameControl::Gif {
+                    left: frame.left,
+                    top: frame.top,
+                    delay: frame.delay,
+                    dispose: frame.dispose,
+                    needs_user_input: frame.needs_user_input,
+                    local_palette: frame.palette.is_some(),
+                },
+            ));
+        }
+
+        if frames.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/animation : No frame in GIF file!",
+            ));
+        }
+
+        // Loop count is not given by the decoder : look for the extension itself
+        let repeat = bytes
+            .windows(NETSCAPE_EXTENSION.len() + 4)
+            .find(|window| window.starts_with(NETSCAPE_EXTENSION))
+            .map(|window| {
+                let count = &window[NETSCAPE_EXTENSION.len() + 2..];
+                u16::from_le_bytes([count[0], count[1]])
+            });
+
+        Ok(Animation {
+            width,
+            height,
+            format: AnimationFormat::Gif {
+                global_palette,
+                repeat,
+            },
+            frames,
+        })
+    }
+
+    /// Read every frame of APNG file contents
+    /// Each frame data is wrapped into a standalone PNG file, read as a picture
+    fn from_png_bytes(bytes: &[u8]) -> Result<Animation, Error> {
+        let chunks = read_chunks(bytes)?;
+        let find_chunk = |chunk_type: &[u8; 4]| {
+            chunks
+                .iter()
+                .find(|(found_type, _)| found_type == chunk_type)
+                .map(|(_, data)| data.clone())
+        };
+
+        let ihdr = find_chunk(b"IHDR")
+            .filter(|ihdr| ihdr.len() == 13)
+            .ok_or_else(|| format_error("Missing PNG header!"))?;
+        let actl = find_chunk(b"acTL")
+            .filter(|actl| actl.len() == 8)
+            .ok_or_else(|| format_error("PNG file is not animated!"))?;
+
+        let mut chunks_before_palette = Vec::new();
+        let mut chunks_after_palette = Vec::new();
+        let mut frames_data: Vec<(Option<Vec<u8>>, Vec<u8>)> = Vec::new();
+        let mut control: Option<Vec<u8>> = None;
+        let mut is_palette_read = false;
+
+        for (chunk_type, data) in chunks.iter() {
+            match chunk_type {
+                b"fcTL" if data.len() == 26 => control = Some(data[4..].to_vec()),
+                b"IDAT" | b"fdAT" => {
+                    let data = if chunk_type == b"IDAT" {
+                        &data[..]
+                    } else {
+                        data.get(4..).unwrap_or(&[])
+                    };
+
+                    // A frame starts with its control, or with the default picture
+                    if control.is_some() || (chunk_type == b"IDAT" && frames_data.is_empty()) {
+                        frames_data.push((control.take(), data.to_vec()));
+                    } else if let Some((_, frame_data)) = frames_data.last_mut() {
+                        frame_data.extend_from_slice(data);
+                    }
+                }
+                b"PLTE" => is_palette_read = true,
+                _ if REBUILT_CHUNKS.contains(&chunk_type) => (),
+                _ if is_palette_read => chunks_after_palette.push((*chunk_type, data.clone())),
+                _ => chunks_before_palette.push((*chunk_type, data.clone())),
+            }
+        }
+
+        // Every frame shares the header, palette and transparency of the default picture
+        let mut frames = Vec::with_capacity(frames_data.len());
+        for (control, data) in frames_data {
+            let mut header = ihdr.clone();
+            if let Some(ref control) = control {
+                header[..8].copy_from_slice(&control[..8]);
+            }
+
+            let mut frame_chunks: Vec<Chunk> = vec![(*b"IHDR", header)];
+            for chunk_type in &[b"PLTE", b"tRNS"] {
+                if let Some(data) = find_chunk(chunk_type) {
+                    frame_chunks.push((**chunk_type, data));
+                }
+            }
+            frame_chunks.push((*b"IDAT", data));
+            frame_chunks.push((*b"IEND", Vec::new()));
+
+            let picture = Picture::from_png_bytes(&write_chunks(&frame_chunks))?;
+            frames.push((picture, FrameControl::Png(control)));
+        }
+
+        if frames.is_empty() {
+            return Err(format_error("No frame in PNG file!"));
+        }
+
+        Ok(Animation {
+            width: u32::from_be_bytes([ihdr[0], ihdr[1], ihdr[2], ihdr[3]]),
+            height: u32::from_be_bytes([ihdr[4], ihdr[5], ihdr[6], ihdr[7]]),
+            format: AnimationFormat::Png {
+                plays: u32::from_be_bytes([actl[4], actl[5], actl[6], actl[7]]),
+                chunks_before_palette,
+                chunks_after_palette,
+            },
+            frames,
+        })
+    }
+
+    /// Write animation into file, in its input format whatever the file extension
+    pub fn save(&self, filepath: &str) -> Result<(), Error> {
+        let bytes = match self.format {
+            AnimationFormat::Gif { .. } => self.to_gif_bytes()?,
+            AnimationFormat::Png { .. } => self.to_png_bytes()?,
+        };
+
+        BufWriter::new(File::create(filepath)?).write_all(&bytes)
+    }
+
+    /// Returns GIF file contents, frames keeping their palettes, positions, timing and disposal
+    fn to_gif_bytes(&self) -> Result<Vec<u8>, Error> {
+        let (global_palette, repeat) = match self.format {
+            AnimationFormat::Gif {
+                ref global_palette,
+                repeat,
+            } => (global_palette, repeat),
+            AnimationFormat::Png { .. } => return Err(format_error("Not a GIF animation!")),
+        };
+
+        let mut bytes = Vec::<u8>::new();
+        {
+            let mut encoder = gif::Encoder::new(
+                &mut bytes,
+                self.width as u16,
+                self.height as u16,
+                global_palette,
+            )?;
+
+            match repeat {
+                Some(0) => encoder.set(gif::Repeat::Infinite)?,
+                Some(count) => encoder.set(gif::Repeat::Finite(count))?,
+                None => (),
+            }
+
+            for (picture, control) in self.frames.iter() {
+                if let FrameControl::Gif {
+                    left,
+                    top,
+                    delay,
+                    dispose,
+                    needs_user_input,
+                    local_palette,
+                } = *control
+                {
+                    let (width, height) = picture.dimensions();
+                    let indexes: Vec<u8> = (0..picture.get_color_samples_count())
+                        .map(|index| picture.get_color_sample(index) as u8)
+                        .collect();
+
+                    encoder.write_frame(&gif::Frame {
+                        delay,
+                        dispose,
+                        transparent: picture
+                            .get_transparency()
+                            .iter()
+                            .position(|&alpha| alpha == 0)
+                            .map(|index| index as u8),
+                        needs_user_input,
+                        top,
+                        left,
+                        width: width as u16,
+                        height: height as u16,
+                        interlaced: false,
+                        palette: if local_palette {
+                            Some(picture.get_palette().concat())
+                        } else {
+                            None
+                        },
+                        buffer: Cow::Borrowed(&indexes),
+                    })?;
+                }
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Returns APNG file contents, frames keeping their positions, timing, disposal and blending
+    /// Header, palette and transparency are the ones of the re-encoded default picture
+    fn to_png_bytes(&self) -> Result<Vec<u8>, Error> {
+        let (plays, chunks_before_palette, chunks_after_palette) = match self.format {
+            AnimationFormat::Png {
+                plays,
+                ref chunks_before_palette,
+                ref chunks_after_palette,
+            } => (plays, chunks_before_palette, chunks_after_palette),
+            AnimationFormat::Gif { .. } => return Err(format_error("Not an APNG animation!")),
+        };
+
+        let mut chunks: Vec<Chunk> = Vec::new();
+        let mut sequence_number = 0u32;
+        let animated_count = self
+            .frames
+            .iter()
+            .filter(|(_, control)| *control != FrameControl::Png(None))
+            .count() as u32;
+
+        for (position, (picture, control)) in self.frames.iter().enumerate() {
+            let frame_chunks = read_chunks(&picture.to_png_bytes()?)?;
+            let get_chunk = |chunk_type: &[u8; 4]| {
+                frame_chunks
+                    .iter()
+                    .filter(|(found_type, _)| found_type == chunk_type)
+                    .cloned()
+                    .collect::<Vec<Chunk>>()
+            };
+
+            if position == 0 {
+                chunks.append(&mut get_chunk(b"IHDR"));
+                chunks.extend(chunks_before_palette.iter().cloned());
+
+                let mut actl = animated_count.to_be_bytes().to_vec();
+                actl.extend_from_slice(&plays.to_be_bytes());
+                chunks.push((*b"acTL", actl));
+
+                chunks.append(&mut get_chunk(b"PLTE"));
+                chunks.append(&mut get_chunk(b"tRNS"));
+                chunks.extend(chunks_after_palette.iter().cloned());
+            }
+
+            if let FrameControl::Png(Some(ref control)) = *control {
+                let mut fctl = sequence_number.to_be_bytes().to_vec();
+                fctl.extend_from_slice(control);
+                chunks.push((*b"fcTL", fctl));
+                sequence_number += 1;
+            }
+
+            for (_, data) in get_chunk(b"IDAT") {
+                if position == 0 {
+                    chunks.push((*b"IDAT", data));
+                } else {
+                    let mut fdat = sequence_number.to_be_bytes().to_vec();
+                    fdat.extend_from_slice(&data);
+                    chunks.push((*b"fdAT", fdat));
+                    sequence_number += 1;
+                }
+            }
+        }
+
+        chunks.push((*b"IEND", Vec::new()));
+        Ok(write_chunks(&chunks))
+    }
+
+    /// Returns canvas dimensions
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns number of frames, default APNG picture included
+    pub fn get_frames_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns picture of frame at given index
+    pub fn get_picture(&self, frame: usize) -> &Picture {
+        &self.frames[frame].0
+    }
+
+    /// Returns mutable picture of frame at given index
+    pub fn get_picture_mut(&mut self, frame: usize) -> &mut Picture {
+        &mut self.frames[frame].0
+    }
+
+    /// Returns delay of frame at given index, as a fraction of second (numerator, denominator)
+    pub fn get_delay(&self, frame: usize) -> (u16, u16) {
+        match self.frames[frame].1 {
+            FrameControl::Gif { delay, .. } => (delay, 100),
+            FrameControl::Png(Some(ref control)) => (
+                u16::from_be_bytes([control[16], control[17]]),
+                u16::from_be_bytes([control[18], control[19]]),
+            ),
+            FrameControl::Png(None) => (0, 0),
+        }
+    }
+
+    /// Returns disposal method of frame at given index, as written in the file
+    pub fn get_disposal(&self, frame: usize) -> u8 {
+        match self.frames[frame].1 {
+            FrameControl::Gif { dispose, .. } => dispose as u8,
+            FrameControl::Png(Some(ref control)) => control[20],
+            FrameControl::Png(None) => 0,
+        }
+    }
+
+    /// Returns number of times the animation is played again (0 for ever)
+    /// None if the GIF file has no loop extension, the animation being played once
+    pub fn get_loop_count(&self) -> Option<u32> {
+        match self.format {
+            AnimationFormat::Gif { repeat, .. } => repeat.map(u32::from),
+            AnimationFormat::Png { plays, .. } => Some(plays),
+        }
+    }
+}
+
+/// Returns a format error, with given message
+fn format_error(message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("stegano/animation : {}", message),
+    )
+}
+
+/// Write PNG file contents from chunks, signature included
+fn write_chunks(chunks: &[Chunk]) -> Vec<u8> {
+    let mut bytes = PNG_SIGNATURE.to_vec();
+
+    for (chunk_type, data) in chunks {
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(chunk_type);
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(&get_crc(chunk_type, data).to_be_bytes());
+    }
+
+    bytes
+}
+
+/// Returns CRC-32 of chunk type and data, as stored at the end of each PNG chunk
+fn get_crc(chunk_type: &[u8], data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for &byte in chunk_type.iter().chain(data.iter()) {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::dot_matrix::tests::get_output_filepath;
+    use super::super::picture::ColorType;
+    use super::*;
+    use std::process;
+
+    /// Returns a sample value varying with position, channel and frame
+    fn get_test_value(x: u32, y: u32, channel: u32, frame: u32) -> u32 {
+        (x * 7 + y * 13 + channel * 41 + frame * 29 + (x * y) % 11) % 256
+    }
+
+    /// Returns APNG frame control : dimensions, offsets, delay, disposal and blending
+    fn get_png_control(
+        dimensions: (u32, u32, u32, u32),
+        delay: (u16, u16),
+        ops: [u8; 2],
+    ) -> Vec<u8> {
+        let (width, height, left, top) = dimensions;
+        let mut control = Vec::new();
+        for value in &[width, height, left, top] {
+            control.extend_from_slice(&value.to_be_bytes());
+        }
+        control.extend_from_slice(&delay.0.to_be_bytes());
+        control.extend_from_slice(&delay.1.to_be_bytes());
+        control.extend_from_slice(&ops);
+        control
+    }
+
+    /// Write a 3 frames animation, the second one being smaller and offset, returning its path
+    /// GIF frames are palette ones, the second one having its own palette and a transparent entry
+    pub fn write_test_animation(filename: &str, is_png: bool) -> String {
+        let output_filepath = get_output_filepath(filename);
+        let cube: Vec<[u8; 3]> = (0..216)
+            .map(|index| [(index / 36) * 51, (index / 6 % 6) * 51, (index % 6) * 51])
+            .collect();
+        let sizes = [(64, 48, 0, 0), (32, 24, 8, 4), (64, 48, 0, 0)];
+        let mut frames = Vec::new();
+
+        for (frame, &(width, height, left, top)) in sizes.iter().enumerate() {
+            let frame = frame as u32;
+            let coordinates = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+
+            let picture = if is_png {
+                let samples = coordinates
+                    .flat_map(|(x, y)| (0..3).map(move |channel| (x, y, channel)))
+                    .map(|(x, y, channel)| get_test_value(x, y, channel, frame) as u16)
+                    .collect();
+                Picture::new(width, height, ColorType::Rgb, 8, samples)
+            } else {
+                let indexes = coordinates
+                    .map(|(x, y)| (get_test_value(x, y, 0, frame) % 216) as u8)
+                    .collect();
+                let (palette, transparency) = if frame == 1 {
+                    (cube.iter().rev().cloned().collect(), vec![255, 255, 0])
+                } else {
+                    (cube.clone(), Vec::new())
+                };
+                Picture::new_indexed(width, height, palette, transparency, indexes)
+            };
+
+            let control = if is_png {
+                let delay = (frame as u16 + 1, 10);
+                let ops = [frame as u8, (frame == 1) as u8];
+                FrameControl::Png(Some(get_png_control(
+                    (width, height, left, top),
+                    delay,
+                    ops,
+                )))
+            } else {
+                FrameControl::Gif {
+                    left: left as u16,
+                    top: top as u16,
+                    delay: 10 * (frame as u16 + 1),
+                    dispose: gif::DisposalMethod::from_u8(frame as u8 + 1)
+                        .unwrap_or(gif::DisposalMethod::Any),
+                    needs_user_input: false,
+                    local_palette: frame == 1,
+                }
+            };
+
+            frames.push((
+                picture.unwrap_or_else(|err| {
+                    eprintln!("Error in write_test_animation: {}", err);
+                    process::exit(1);
+                }),
+                control,
+            ));
+        }
+
+        let format = if is_png {
+            AnimationFormat::Png {
+                plays: 3,
+                chunks_before_palette: vec![(*b"tEXt", b"Comment\0animated".to_vec())],
+                chunks_after_palette: Vec::new(),
+            }
+        } else {
+            AnimationFormat::Gif {
+                global_palette: cube.concat(),
+                repeat: Some(3),
+            }
+        };
+
+        let animation = Animation {
+            width: 64,
+            height: 48,
+            format,
+            frames,
+        };
+        animation.save(&output_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in write_test_animation: {}", err);
+            process::exit(1);
+        });
+
+        output_filepath
+    }
+
+    #[test]
+    fn test_crc() {
+        assert_eq!(get_crc(b"IEND", &[]), 0xAE42_6082);
+    }
+
+    #[test]
+    fn test_save_open_keeps_animation() {
+        for &(input, output, is_png) in &[
+            ("test_animation.png", "test_animation_rewritten.png", true),
+            ("test_animation.gif", "test_animation_rewritten.gif", false),
+        ] {
+            let input_filepath = write_test_animation(input, is_png);
+            let output_filepath = get_output_filepath(output);
+
+            let open = |filepath: &str| {
+                Animation::open(filepath).unwrap_or_else(|err| {
+                    eprintln!("Error in test_save_open_keeps_animation: {}", err);
+                    process::exit(1);
+                })
+            };
+
+            let animation = open(&input_filepath);
+            animation.save(&output_filepath).unwrap_or_else(|err| {
+                eprintln!("Error in test_save_open_keeps_animation: {}", err);
+                process::exit(1);
+            });
+            let animation2 = open(&output_filepath);
+
+            assert_eq!(animation.get_frames_count(), 3);
+            assert_eq!(animation2.get_frames_count(), 3);
+            assert_eq!(animation2.dimensions(), (64, 48));
+            assert_eq!(animation2.get_loop_count(), Some(3));
+
+            for frame in 0..3 {
+                let (picture, picture2) =
+                    (animation.get_picture(frame), animation2.get_picture(frame));
+
+                assert_eq!(animation2.get_delay(frame), animation.get_delay(frame));
+                assert_eq!(
+                    animation2.get_disposal(frame),
+                    animation.get_disposal(frame)
+                );
+                assert_eq!(animation2.frames[frame].1, animation.frames[frame].1);
+                assert_eq!(picture2.dimensions(), picture.dimensions());
+                assert!(picture2.to_rgba8() == picture.to_rgba8());
+            }
+
+            assert_eq!(
+                animation.get_delay(1),
+                if is_png { (2, 10) } else { (20, 100) }
+            );
+            assert_eq!(animation.get_picture(1).dimensions(), (32, 24));
+        }
+    }
+}
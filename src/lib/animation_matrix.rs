@@ -0,0 +1,381 @@
+//! Module handling animated pictures (GIF and APNG)
+//! Every frame is part of the same embedding space : the message goes sample after sample,
+//! frame after frame, palette frames holding their bits in the parity of luminance-sorted entries
+use std::io::{Error, ErrorKind};
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+
+use super::animation::Animation;
+use super::binary::*;
+use super::carrier::{self, frame_message, Carrier, LENGTH_BITS};
+use super::dot_matrix::{check_message_and_password, WipeOptions};
+use super::palette::SortedPalette;
+use super::picture::ColorType;
+use super::sealing::{PasswordSealing, Sealing};
+
+/// Header size : magic number only
+const HEADER_BYTES: usize = 2;
+
+/// Magic number telling the header is valid for the given password
+const HEADER_MAGIC: u16 = 0x5354;
+
+/// Key derivation context of the header mask
+const HEADER_CONTEXT: &str = "stegano/animation/header";
+
+/// Basic structure : an animation, palettes of its frames, slots, and a filepath
+/// Frames whose palette cannot hold anything get no sorted palette
+/// Slots are (frame, color sample) couples, which embedding never adds nor removes
+pub struct AnimationMatrix {
+    animation: Result<Animation, Error>,
+    sorted_palettes: Vec<Option<SortedPalette>>,
+    slots: Vec<(usize, usize)>,
+    input_filepath: String,
+}
+
+impl AnimationMatrix {
+    /// Constructor
+    pub fn new(filepath: &str) -> AnimationMatrix {
+        let animation = Animation::open(filepath);
+        let sorted_palettes = match animation {
+            Ok(ref animation) => (0..animation.get_frames_count())
+                .map(|frame| {
+                    let picture = animation.get_picture(frame);
+                    SortedPalette::new_opaque(picture.get_palette(), picture.get_transparency())
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        let mut matrix = AnimationMatrix {
+            animation,
+            sorted_palettes,
+            slots: Vec::new(),
+            input_filepath: filepath.to_string(),
+        };
+        matrix.slots = matrix.get_slots();
+        matrix
+    }
+
+    /// Returns true if file at given path is an animation of several frames
+    pub fn is_animation_file(filepath: &str) -> bool {
+        Animation::open(filepath).is_ok_and(|animation| animation.get_frames_count() > 1)
+    }
+
+    /// Accessor returning canvas dimensions as a tuple pixel
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        if let Ok(ref animation) = self.animation {
+            animation.dimensions()
+        } else {
+            (0, 0)
+        }
+    }
+
+    /// Accessor returning animation's filepath
+    pub fn get_input_filepath(&self) -> String {
+        self.input_filepath.clone()
+    }
+
+    /// Function to write the animation into target file, in its input format
+    pub fn write_to_file(&self, filepath: &str) -> Result<(), Error> {
+        if let Ok(ref animation) = self.animation {
+            animation.save(filepath)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/write_to_file : Unable to open inner image!",
+            ))
+        }
+    }
+
+    /// Returns inner animation
+    fn get_animation(&self) -> Result<&Animation, Error> {
+        match self.animation {
+            Ok(ref animation) => Ok(animation),
+            Err(_) => Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/get_animation : Unable to open inner image!",
+            )),
+        }
+    }
+
+    /// Returns samples able to hold a bit, as (frame, color sample) couples, frame after frame
+    /// Palette frames only use pixels of opaque entries, other ones showing previous frames
+    fn get_slots(&self) -> Vec<(usize, usize)> {
+        let animation = match self.animation {
+            Ok(ref animation) => animation,
+            Err(_) => return Vec::new(),
+        };
+        let mut slots = Vec::new();
+
+        for frame in 0..animation.get_frames_count() {
+            let picture = animation.get_picture(frame);
+            let samples = 0..picture.get_color_samples_count();
+
+            if picture.get_color_type() != ColorType::Indexed {
+                slots.extend(samples.map(|index| (frame, index)));
+            } else if let Some(ref palette) = self.sorted_palettes[frame] {
+                slots.extend(
+                    samples
+                        .filter(|&index| palette.is_ranked(picture.get_color_sample(index) as u8))
+                        .map(|index| (frame, index)),
+                );
+            }
+        }
+
+        slots
+    }
+
+    /// Returns number of bytes of encrypted message the animation can hold, all frames combined
+    pub fn get_capacity(&self) -> usize {
+        self.slots
+            .len()
+            .saturating_sub(HEADER_BYTES * 8 + LENGTH_BITS)
+            / 8
+    }
+
+    /// Encode given file in self animation
+    pub fn encode_file(&mut self, filepath: &str, password: &str) -> Result<(), Error> {
+        let input_file = File::open(filepath)?;
+        let mut buf_reader = BufReader::new(input_file);
+        let mut contents = String::new();
+        buf_reader.read_to_string(&mut contents)?;
+        self.encode(contents.as_str(), password)
+    }
+
+    /// Encode given message in self animation
+    pub fn encode(&mut self, message: &str, password: &str) -> Result<(), Error> {
+        self.encode_sealed(message, &PasswordSealing::new(password))
+    }
+
+    /// Encode given message in self animation, sealed with given method
+    /// Header goes first, then message spread over frames
+    /// Remaining slots get random bits, to hide message length
+    pub fn encode_sealed(&mut self, message: &str, sealing: &dyn Sealing) -> Result<(), Error> {
+        let password = &sealing.get_carrier_key();
+        check_message_and_password(message, password)?;
+
+        let vector = frame_message(&sealing.seal(message)?);
+        let index = carrier::store_from(self, HEADER_BYTES * 8, 1, &vector)?;
+        carrier::store_random_from(self, index, 1)?;
+
+        carrier::store_header(self, &HEADER_MAGIC.to_be_bytes(), password, HEADER_CONTEXT)
+    }
+
+    /// Remove any hidden payload : bit held by every slot of every frame is overwritten
+    /// with a random one, frames being written back one by one, not flattened
+    pub fn wipe(&mut self, options: &WipeOptions) -> Result<(), Error> {
+        if options.low_bits.is_some_and(|low_bits| low_bits != 1) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/wipe : Number of low bits must be 1 for animations",
+            ));
+        }
+
+        self.get_animation()?;
+        carrier::store_random_from(self, 0, 1)
+    }
+
+    /// Decodes animation and write result file
+    pub fn decode_and_write(&self, filepath: &str, password: &str) -> Result<(), Error> {
+        let decoded_string = &self.decode(password)?;
+        let mut output_file = File::create(filepath)?;
+        output_file.write_all(decoded_string.as_bytes())
+    }
+
+    /// Decodes animation and return result string
+    pub fn decode(&self, password: &str) -> Result<String, Error> {
+        self.decode_sealed(&PasswordSealing::new(password))
+    }
+
+    /// Decodes animation sealed with given method and return result string
+    pub fn decode_sealed(&self, sealing: &dyn Sealing) -> Result<String, Error> {
+        let password = &sealing.get_carrier_key();
+        if !is_one_byte_chars_message(password) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Input password must be 1 byte chars",
+            ));
+        }
+
+        let is_header_valid = carrier::read_header(self, HEADER_BYTES, password, HEADER_CONTEXT)?
+            .is_some_and(|header| header == HEADER_MAGIC.to_be_bytes());
+        if !is_header_valid {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/decode : Nothing hidden in this file!",
+            ));
+        }
+
+        let encrypted_message = carrier::read_framed_from(self, HEADER_BYTES * 8, 1)?;
+
+        sealing
+            .open(&encrypted_message)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+/// Trait implementation : samples of every frame are the slots, one bit each
+impl Carrier for AnimationMatrix {
+    /// Returns number of samples able to hold a bit, all frames combined
+    fn get_slots_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns bit held by slot at given index, whatever the given count
+    /// Palette frames hold it in the parity of the sorted palette entry
+    fn read_slot(&self, index: usize, _count: u8) -> Result<Vec<bool>, Error> {
+        let (frame, sample) = self.slots[index];
+        let value = self
+            .get_animation()?
+            .get_picture(frame)
+            .get_color_sample(sample);
+
+        Ok(vec![match self.sorted_palettes[frame] {
+            Some(ref palette) => palette.get_bit(value as u8),
+            None => value & 1 == 1,
+        }])
+    }
+
+    /// Store first given bit within slot at given index
+    fn write_slot(&mut self, index: usize, _count: u8, bits: &[bool]) -> Result<(), Error> {
+        let (frame, sample) = self.slots[index];
+        let bit = match bits.first() {
+            Some(&bit) => bit,
+            None => return Ok(()),
+        };
+        let picture = match self.animation {
+            Ok(ref mut animation) => animation.get_picture_mut(frame),
+            Err(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "stegano/write_slot : Unable to open inner image!",
+                ))
+            }
+        };
+        let value = picture.get_color_sample(sample);
+
+        let value = match self.sorted_palettes[frame] {
+            Some(ref palette) => palette.store_bit(value as u8, bit) as u16,
+            None => (value & !1) | bit as u16,
+        };
+        picture.set_color_sample(sample, value);
+
+        Ok(())
+    }
+
+    /// Write animation into target file
+    fn save(&self, filepath: &str) -> Result<(), Error> {
+        self.write_to_file(filepath)
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::animation::tests::write_test_animation;
+    use super::super::dot_matrix::tests::get_output_filepath;
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn test_global_animation() {
+        for &(input, output, is_png, length) in &[
+            (
+                "test_animation_carrier.png",
+                "test_animation_stego.png",
+                true,
+                1500,
+            ),
+            (
+                "test_animation_carrier.gif",
+                "test_animation_stego.gif",
+                false,
+                600,
+            ),
+        ] {
+            let input_filepath = write_test_animation(input, is_png);
+            let output_filepath = get_output_filepath(output);
+            let animation = Animation::open(&input_filepath).expect("Input animation");
+
+            // Message is too long for the first frame alone
+            let message: String = (0..length)
+                .map(|i| (b'a' + (i % 26) as u8) as char)
+                .collect();
+            let mut matrix = AnimationMatrix::new(&input_filepath);
+            assert!(message.len() > animation.get_picture(0).get_color_samples_count() / 8);
+            assert!(matrix.get_capacity() > message.len());
+
+            matrix
+                .encode(&message, "Password")
+                .and_then(|_| matrix.write_to_file(&output_filepath))
+                .unwrap_or_else(|err| {
+                    eprintln!("Error in test_global_animation: {}", err);
+                    process::exit(1);
+                });
+
+            let matrix2 = AnimationMatrix::new(&output_filepath);
+            let res = matrix2.decode("Password").unwrap_or_else(|err| {
+                eprintln!("Error in test_global_animation: {}", err);
+                process::exit(1);
+            });
+
+            assert_eq!(res, message);
+            assert!(matrix2.decode("Wrong password").is_err());
+
+            // Timing, disposal and loop count are kept
+            let animation2 = Animation::open(&output_filepath).expect("Output animation");
+            assert_eq!(animation2.get_frames_count(), 3);
+            assert_eq!(animation2.get_loop_count(), animation.get_loop_count());
+            for frame in 0..3 {
+                assert_eq!(animation2.get_delay(frame), animation.get_delay(frame));
+                assert_eq!(
+                    animation2.get_disposal(frame),
+                    animation.get_disposal(frame)
+                );
+            }
+
+            // Transparent pixels of palette frames are left untouched
+            if !is_png {
+                let transparent = |animation: &Animation| {
+                    let picture = animation.get_picture(1);
+                    (0..picture.get_color_samples_count())
+                        .filter(|&index| picture.get_color_sample(index) == 2)
+                        .collect::<Vec<usize>>()
+                };
+                assert!(!transparent(&animation).is_empty());
+                assert_eq!(transparent(&animation2), transparent(&animation));
+            }
+        }
+    }
+
+    #[test]
+    fn test_global_wipe() {
+        for &(input, output, is_png) in &[
+            ("test_animation_wipe.png", "test_animation_wiped.png", true),
+            ("test_animation_wipe.gif", "test_animation_wiped.gif", false),
+        ] {
+            let input_filepath = write_test_animation(input, is_png);
+            let output_filepath = get_output_filepath(output);
+
+            let mut matrix = AnimationMatrix::new(&input_filepath);
+            matrix
+                .encode("Hello how is the weather today", "Password")
+                .and_then(|_| matrix.wipe(&WipeOptions::default()))
+                .and_then(|_| matrix.write_to_file(&output_filepath))
+                .unwrap_or_else(|err| {
+                    eprintln!("Error in test_global_wipe: {}", err);
+                    process::exit(1);
+                });
+
+            // Payload is gone, every frame being kept
+            assert!(AnimationMatrix::new(&output_filepath)
+                .decode("Password")
+                .is_err());
+            let animation = Animation::open(&output_filepath).expect("Output animation");
+            assert_eq!(animation.get_frames_count(), 3);
+            assert!(matrix.wipe(&WipeOptions { low_bits: Some(2) }).is_err());
+        }
+    }
+}
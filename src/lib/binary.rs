@@ -123,6 +123,33 @@ pub fn convert_byte_vec_to_bit_array(byte_array: &[Byte]) -> Vec<bool> {
     message_as_binary_vector
 }
 
+/// Convert boolean (~= binary) vector into u8 vector, 8 bits per value
+pub fn convert_bit_array_to_u8_vec(bit_array: &[bool]) -> Result<Vec<u8>, Error> {
+    bit_array
+        .chunks(8)
+        .map(|chunk| Byte::from_bool_array(chunk).map(|byte| byte.get_value()))
+        .collect()
+}
+
+/// Convert u32 into boolean (~= binary) vector, most significant bit first
+pub fn convert_u32_to_bit_array(value: u32) -> Vec<bool> {
+    convert_byte_vec_to_bit_array(&convert_u8_vec_to_byte_array(&value.to_be_bytes()))
+}
+
+/// Convert 32 bits boolean (~= binary) vector into u32
+pub fn convert_bit_array_to_u32(bit_array: &[bool]) -> Result<u32, Error> {
+    if bit_array.len() != 32 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Input bit_vec must be 32 bits long to be stored in one u32",
+        ));
+    }
+
+    let mut bytes: [u8; 4] = [0; 4];
+    bytes.copy_from_slice(&convert_bit_array_to_u8_vec(bit_array)?);
+    Ok(u32::from_be_bytes(bytes))
+}
+
 /// Convert u8 vector into Byte vector
 pub fn convert_u8_vec_to_byte_array(u8_array: &[u8]) -> Vec<Byte> {
     u8_array.iter().map(|&e| Byte::new(e)).collect()
@@ -276,6 +303,31 @@ pub mod tests {
         assert_eq!(convert_byte_vec_to_bit_array(&byte_vec), bit_vec);
     }
 
+    #[test]
+    fn test_convert_bit_vec_to_u8_vec() {
+        let bit_vec = vec![
+            false, false, false, true, true, true, true, true, true, false, false, false, false,
+            false, false, false,
+        ];
+
+        assert_eq!(
+            convert_bit_array_to_u8_vec(&bit_vec).expect("Unable to convert bitvec to u8 vec!"),
+            vec![31, 128]
+        );
+    }
+
+    #[test]
+    fn test_convert_u32_to_bit_vec_and_back() {
+        let bit_vec = convert_u32_to_bit_array(123_456_789);
+
+        assert_eq!(bit_vec.len(), 32);
+        assert_eq!(
+            convert_bit_array_to_u32(&bit_vec).expect("Unable to convert bitvec to u32!"),
+            123_456_789
+        );
+        assert!(convert_bit_array_to_u32(&bit_vec[1..]).is_err());
+    }
+
     #[test]
     fn test_convert_u8_vec_to_byte_vec() {
         let u8_vec = vec![128, 11, 135];
@@ -0,0 +1,331 @@
+//! Cover media abstraction : pictures, animations, audio files...
+//! A carrier is a sequence of numbered slots, each one holding bits in its low bits.
+//! Message framing, password-masked headers and random filling are written here once,
+//! for every carrier
+extern crate rand;
+
+use self::rand::Rng;
+
+use std::io::{Error, ErrorKind};
+
+use super::binary::*;
+use super::cypher::derive_key;
+
+/// Number of bits used to store the encrypted message length
+pub const LENGTH_BITS: usize = 32;
+
+/// Cover medium holding bits in numbered slots
+pub trait Carrier {
+    /// Returns number of slots
+    fn get_slots_count(&self) -> usize;
+
+    /// Returns the given number of low bits of slot at given index, most significant first
+    fn read_slot(&self, index: usize, count: u8) -> Result<Vec<bool>, Error>;
+
+    /// Store bits within the given number of low bits of slot at given index
+    /// If the end of the message is reached, bits may be fewer than count : store what is storable
+    fn write_slot(&mut self, index: usize, count: u8, bits: &[bool]) -> Result<(), Error>;
+
+    /// Write carrier into target file
+    fn save(&self, filepath: &str) -> Result<(), Error>;
+
+    /// Returns highest value a slot may hold
+    /// None if slots hold no plain value (palette entries...), bits only being available
+    fn get_slot_max_value(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns value of slot at given index
+    fn read_slot_value(&self, _index: usize) -> Result<u32, Error> {
+        Err(no_value_error())
+    }
+
+    /// Replace value of slot at given index
+    fn write_slot_value(&mut self, _index: usize, _value: u32) -> Result<(), Error> {
+        Err(no_value_error())
+    }
+}
+
+/// Returns error of carriers whose slots hold no plain value
+fn no_value_error() -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        "stegano/carrier : Slots of this file hold no plain value!",
+    )
+}
+
+/// Some slots of a carrier, in given order, seen as a carrier of their own
+/// Read-only views wrap a shared carrier reference, writable ones a mutable one
+pub struct SlotsView<C> {
+    carrier: C,
+    slots: Vec<usize>,
+}
+
+impl<C> SlotsView<C> {
+    /// Constructor, with indexes of the viewed slots within the carrier
+    pub fn new(carrier: C, slots: Vec<usize>) -> SlotsView<C> {
+        SlotsView { carrier, slots }
+    }
+}
+
+/// Trait implementation : read-only view
+impl Carrier for SlotsView<&dyn Carrier> {
+    fn get_slots_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn read_slot(&self, index: usize, count: u8) -> Result<Vec<bool>, Error> {
+        self.carrier.read_slot(self.slots[index], count)
+    }
+
+    fn write_slot(&mut self, _index: usize, _count: u8, _bits: &[bool]) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "stegano/write_slot : Read-only slots!",
+        ))
+    }
+
+    fn save(&self, filepath: &str) -> Result<(), Error> {
+        self.carrier.save(filepath)
+    }
+}
+
+/// Trait implementation : writable view
+impl Carrier for SlotsView<&mut dyn Carrier> {
+    fn get_slots_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn read_slot(&self, index: usize, count: u8) -> Result<Vec<bool>, Error> {
+        self.carrier.read_slot(self.slots[index], count)
+    }
+
+    fn write_slot(&mut self, index: usize, count: u8, bits: &[bool]) -> Result<(), Error> {
+        self.carrier.write_slot(self.slots[index], count, bits)
+    }
+
+    fn save(&self, filepath: &str) -> Result<(), Error> {
+        self.carrier.save(filepath)
+    }
+}
+
+/// Convert encrypted message into binary vector, preceded by its length
+pub fn frame_message(encrypted_message: &[u8]) -> Vec<bool> {
+    let mut vector = convert_u32_to_bit_array(encrypted_message.len() as u32);
+    vector.append(&mut convert_byte_vec_to_bit_array(
+        &convert_u8_vec_to_byte_array(encrypted_message),
+    ));
+    vector
+}
+
+/// Read a message preceded by its length from successive bit chunks
+/// Stops reading chunks as soon as the whole message is known
+pub fn read_framed_message<I>(chunks: I) -> Result<Vec<u8>, Error>
+where
+    I: Iterator<Item = Result<Vec<bool>, Error>>,
+{
+    let mut vector = Vec::<bool>::new();
+    let mut message_length = None;
+
+    for chunk in chunks {
+        vector.append(&mut chunk?);
+
+        if message_length.is_none() && vector.len() >= LENGTH_BITS {
+            message_length = Some(convert_bit_array_to_u32(&vector[..LENGTH_BITS])? as usize * 8);
+        }
+
+        if let Some(length) = message_length {
+            if vector.len() >= LENGTH_BITS + length {
+                return convert_bit_array_to_u8_vec(&vector[LENGTH_BITS..LENGTH_BITS + length]);
+            }
+        }
+    }
+
+    Err(Error::new(
+        ErrorKind::InvalidData,
+        "stegano/decode : Nothing hidden in this file!",
+    ))
+}
+
+/// Returns header bits mask, derived from password within given context
+/// Without the password, the header cannot be told apart from random bits
+pub fn get_header_mask(password: &str, context: &str, size: usize) -> Vec<bool> {
+    convert_byte_vec_to_bit_array(&convert_u8_vec_to_byte_array(
+        &derive_key(password, context)[..size],
+    ))
+}
+
+/// Store masked header in the first slots, one bit per slot
+pub fn store_header(
+    carrier: &mut dyn Carrier,
+    header: &[u8],
+    password: &str,
+    context: &str,
+) -> Result<(), Error> {
+    let bits = convert_byte_vec_to_bit_array(&convert_u8_vec_to_byte_array(header));
+
+    for (index, (bit, mask)) in bits
+        .iter()
+        .zip(get_header_mask(password, context, header.len()))
+        .enumerate()
+    {
+        carrier.write_slot(index, 1, &[bit ^ mask])?;
+    }
+
+    Ok(())
+}
+
+/// Read masked header of given size from the first slots
+/// Returns None if the carrier has no slot left after the header
+pub fn read_header(
+    carrier: &dyn Carrier,
+    size: usize,
+    password: &str,
+    context: &str,
+) -> Result<Option<Vec<u8>>, Error> {
+    if carrier.get_slots_count() <= size * 8 {
+        return Ok(None);
+    }
+
+    let mut bits = Vec::<bool>::with_capacity(size * 8);
+    for (index, mask) in get_header_mask(password, context, size)
+        .into_iter()
+        .enumerate()
+    {
+        bits.push(carrier.read_slot(index, 1)?[0] ^ mask);
+    }
+
+    convert_bit_array_to_u8_vec(&bits).map(Some)
+}
+
+/// Store bits slot after slot from given slot index, low_bits by low_bits
+/// Returns index of the first slot left untouched
+pub fn store_from(
+    carrier: &mut dyn Carrier,
+    index: usize,
+    low_bits: u8,
+    bits: &[bool],
+) -> Result<usize, Error> {
+    if carrier.get_slots_count().saturating_sub(index) * (low_bits as usize) < bits.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "stegano/encode : Input file not big enough to store message!",
+        ));
+    }
+
+    let mut index = index;
+    for chunk in bits.chunks(low_bits as usize) {
+        carrier.write_slot(index, low_bits, chunk)?;
+        index += 1;
+    }
+
+    Ok(index)
+}
+
+/// Read a framed message stored slot after slot from given slot index, low_bits by low_bits
+pub fn read_framed_from(
+    carrier: &dyn Carrier,
+    index: usize,
+    low_bits: u8,
+) -> Result<Vec<u8>, Error> {
+    read_framed_message(
+        (index..carrier.get_slots_count()).map(|index| carrier.read_slot(index, low_bits)),
+    )
+}
+
+/// Store random bits from given slot index, to hide encrypted message length
+pub fn store_random_from(
+    carrier: &mut dyn Carrier,
+    index: usize,
+    low_bits: u8,
+) -> Result<(), Error> {
+    let mut rng = rand::thread_rng();
+
+    for index in index..carrier.get_slots_count() {
+        let bits: Vec<bool> = (0..low_bits).map(|_| rng.gen()).collect();
+        carrier.write_slot(index, low_bits, &bits)?;
+    }
+
+    Ok(())
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Carrier made of bytes in memory
+    pub struct MemoryCarrier {
+        pub bytes: Vec<u8>,
+    }
+
+    impl Carrier for MemoryCarrier {
+        fn get_slots_count(&self) -> usize {
+            self.bytes.len()
+        }
+
+        fn read_slot(&self, index: usize, count: u8) -> Result<Vec<bool>, Error> {
+            Ok((0..count)
+                .rev()
+                .map(|shift| (self.bytes[index] >> shift) & 1 == 1)
+                .collect())
+        }
+
+        fn write_slot(&mut self, index: usize, count: u8, bits: &[bool]) -> Result<(), Error> {
+            for (position, &bit) in bits.iter().take(count as usize).enumerate() {
+                let shift = count as usize - 1 - position;
+                self.bytes[index] = (self.bytes[index] & !(1 << shift)) | ((bit as u8) << shift);
+            }
+            Ok(())
+        }
+
+        fn save(&self, _filepath: &str) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn get_slot_max_value(&self) -> Option<u32> {
+            Some(u8::MAX as u32)
+        }
+
+        fn read_slot_value(&self, index: usize) -> Result<u32, Error> {
+            Ok(self.bytes[index] as u32)
+        }
+
+        fn write_slot_value(&mut self, index: usize, value: u32) -> Result<(), Error> {
+            self.bytes[index] = value as u8;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_header_and_framed_message() {
+        let mut carrier = MemoryCarrier {
+            bytes: (0..200).map(|i| (i * 37) as u8).collect(),
+        };
+        let original = carrier.bytes.clone();
+
+        store_header(&mut carrier, &[0x53, 0x54], "Password", "test").expect("Header stored");
+        let index = store_from(&mut carrier, 16, 2, &frame_message(b"Hello")).expect("Stored");
+        assert_eq!(index, 16 + (LENGTH_BITS + 40) / 2);
+
+        assert_eq!(
+            read_header(&carrier, 2, "Password", "test").expect("Header read"),
+            Some(vec![0x53, 0x54])
+        );
+        assert_ne!(
+            read_header(&carrier, 2, "Wrong password", "test").expect("Header read"),
+            Some(vec![0x53, 0x54])
+        );
+        assert_eq!(
+            read_framed_from(&carrier, 16, 2).expect("Message read"),
+            b"Hello".to_vec()
+        );
+
+        // Only low bits change
+        for (before, after) in original.iter().zip(carrier.bytes.iter()) {
+            assert_eq!(before & !3, after & !3);
+        }
+
+        assert!(store_from(&mut carrier, 190, 1, &frame_message(b"Hello")).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! Slotted container : a carrier holding several messages, each one in its own slot,
+//! so that owners may append, list, replace or delete their messages with their own password
+//! without disturbing other ones
+//!
+//! A table in the first carrier slots tells which container slots are in use, not whose;
+//! each container slot starts with a password-masked header, then the framed message,
+//! then random bits up to its end
+use std::io::{Error, ErrorKind};
+
+use super::carrier::{self, frame_message, Carrier, SlotsView};
+
+/// Number of slots of a container
+pub const CONTAINER_SLOTS: usize = 8;
+
+/// Magic number of containers table and slot headers
+const CONTAINER_MAGIC: u16 = 0x5343;
+
+/// Container table size : magic number, then one bit per slot in use
+const TABLE_BYTES: usize = 3;
+
+/// Key of the table mask, which needs no password
+const TABLE_KEY: &str = "stegano/container";
+
+/// Key derivation context of the table mask
+const TABLE_CONTEXT: &str = "stegano/container/table";
+
+/// Slot header size : magic number only
+const SLOT_HEADER_BYTES: usize = 2;
+
+/// Container slot, as seen with a password
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainerEntry {
+    pub index: usize,
+    pub used: bool,
+    pub owned: bool,
+}
+
+/// Returns key derivation context of given slot header mask
+fn get_slot_context(index: usize) -> String {
+    format!("stegano/container/slot/{}", index)
+}
+
+/// Returns read-only view of given container slot
+fn get_slot_view(carrier: &dyn Carrier, index: usize) -> SlotsView<&dyn Carrier> {
+    let slots = get_slot_range(carrier, index);
+    SlotsView::new(carrier, slots)
+}
+
+/// Returns carrier slots of given container slot : what follows the table, split evenly
+fn get_slot_range(carrier: &dyn Carrier, index: usize) -> Vec<usize> {
+    let size = carrier.get_slots_count().saturating_sub(TABLE_BYTES * 8) / CONTAINER_SLOTS;
+    let start = TABLE_BYTES * 8 + index * size;
+    (start..start + size).collect()
+}
+
+/// Returns bitmap of container slots in use, None if carrier holds no container
+pub fn read_table(carrier: &dyn Carrier) -> Result<Option<u8>, Error> {
+    Ok(
+        carrier::read_header(carrier, TABLE_BYTES, TABLE_KEY, TABLE_CONTEXT)?
+            .filter(|table| table[..2] == CONTAINER_MAGIC.to_be_bytes())
+            .map(|table| table[2]),
+    )
+}
+
+/// Store bitmap of container slots in use
+fn store_table(carrier: &mut dyn Carrier, table: u8) -> Result<(), Error> {
+    let magic = CONTAINER_MAGIC.to_be_bytes();
+    carrier::store_header(
+        carrier,
+        &[magic[0], magic[1], table],
+        TABLE_KEY,
+        TABLE_CONTEXT,
+    )
+}
+
+/// Returns true if given container slot header matches password
+fn is_owner(carrier: &dyn Carrier, index: usize, password: &str) -> Result<bool, Error> {
+    Ok(carrier::read_header(
+        &get_slot_view(carrier, index),
+        SLOT_HEADER_BYTES,
+        password,
+        &get_slot_context(index),
+    )?
+    .is_some_and(|header| header == CONTAINER_MAGIC.to_be_bytes()))
+}
+
+/// Check that password owns given container slot, before it is read or touched
+fn check_owner(carrier: &dyn Carrier, index: usize, password: &str) -> Result<(), Error> {
+    match list(carrier, password)?.get(index) {
+        Some(entry) if entry.owned => Ok(()),
+        _ => Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "stegano/container : Slot {} is not in use with this password!",
+                index
+            ),
+        )),
+    }
+}
+
+/// Store encrypted message in given container slot, then random bits up to its end
+fn store_slot(
+    carrier: &mut dyn Carrier,
+    index: usize,
+    encrypted_message: Option<&[u8]>,
+    password: &str,
+) -> Result<(), Error> {
+    let slots = get_slot_range(carrier, index);
+    let mut view = SlotsView::new(carrier, slots);
+
+    match encrypted_message {
+        Some(encrypted_message) => {
+            let end = carrier::store_from(
+                &mut view,
+                SLOT_HEADER_BYTES * 8,
+                1,
+                &frame_message(encrypted_message),
+            )?;
+            carrier::store_random_from(&mut view, end, 1)?;
+            carrier::store_header(
+                &mut view,
+                &CONTAINER_MAGIC.to_be_bytes(),
+                password,
+                &get_slot_context(index),
+            )
+        }
+        None => carrier::store_random_from(&mut view, 0, 1),
+    }
+}
+
+/// Append encrypted message in the first free container slot, creating the container if needed
+/// Returns index of the slot
+pub fn append(
+    carrier: &mut dyn Carrier,
+    encrypted_message: &[u8],
+    password: &str,
+) -> Result<usize, Error> {
+    // Carriers without container get an empty one, whatever they held
+    let table = match read_table(carrier)? {
+        Some(table) => table,
+        None => {
+            carrier::store_random_from(carrier, TABLE_BYTES * 8, 1)?;
+            0
+        }
+    };
+
+    let index = (0..CONTAINER_SLOTS)
+        .find(|index| table & (1 << index) == 0)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/container : Every slot is in use!",
+            )
+        })?;
+
+    store_slot(carrier, index, Some(encrypted_message), password)?;
+    store_table(carrier, table | (1 << index))?;
+    Ok(index)
+}
+
+/// Returns container slots, telling which ones are in use and which ones password owns
+pub fn list(carrier: &dyn Carrier, password: &str) -> Result<Vec<ContainerEntry>, Error> {
+    let table = read_table(carrier)?.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            "stegano/container : No container in this file!",
+        )
+    })?;
+
+    (0..CONTAINER_SLOTS)
+        .map(|index| {
+            let used = table & (1 << index) != 0;
+            Ok(ContainerEntry {
+                index,
+                used,
+                owned: used && is_owner(carrier, index, password)?,
+            })
+        })
+        .collect()
+}
+
+/// Returns encrypted message of given container slot, if password owns it
+pub fn read(carrier: &dyn Carrier, index: usize, password: &str) -> Result<Vec<u8>, Error> {
+    check_owner(carrier, index, password)?;
+
+    carrier::read_framed_from(&get_slot_view(carrier, index), SLOT_HEADER_BYTES * 8, 1)
+}
+
+/// Returns encrypted message of the first container slot password owns, if any
+pub fn find(carrier: &dyn Carrier, password: &str) -> Result<Option<Vec<u8>>, Error> {
+    if read_table(carrier)?.is_none() {
+        return Ok(None);
+    }
+
+    match list(carrier, password)?.iter().find(|entry| entry.owned) {
+        Some(entry) => read(carrier, entry.index, password).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Replace encrypted message of given container slot, if password owns it
+pub fn replace(
+    carrier: &mut dyn Carrier,
+    index: usize,
+    encrypted_message: &[u8],
+    password: &str,
+) -> Result<(), Error> {
+    check_owner(carrier, index, password)?;
+
+    store_slot(carrier, index, Some(encrypted_message), password)
+}
+
+/// Delete message of given container slot, if password owns it : slot is filled with random bits
+pub fn delete(carrier: &mut dyn Carrier, index: usize, password: &str) -> Result<(), Error> {
+    let table = read_table(carrier)?.unwrap_or_default();
+    check_owner(carrier, index, password)?;
+
+    store_slot(carrier, index, None, password)?;
+    store_table(carrier, table & !(1 << index))
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::carrier::tests::MemoryCarrier;
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn test_container() {
+        let mut carrier = MemoryCarrier {
+            bytes: vec![0; 24 + CONTAINER_SLOTS * 400],
+        };
+        let fail = |err: Error| -> usize {
+            eprintln!("Error in test_container: {}", err);
+            process::exit(1);
+        };
+
+        let first = append(&mut carrier, b"first message", "Password").unwrap_or_else(fail);
+        let second = append(&mut carrier, b"second message", "Other").unwrap_or_else(fail);
+        let third = append(&mut carrier, b"third message", "Password").unwrap_or_else(fail);
+        assert_eq!((first, second, third), (0, 1, 2));
+
+        // Owners see their own slots only, and cannot touch other ones
+        let entries = list(&carrier, "Password").unwrap_or_default();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.used, entry.owned))
+                .take(4)
+                .collect::<Vec<(bool, bool)>>(),
+            vec![(true, true), (true, false), (true, true), (false, false)]
+        );
+        assert!(replace(&mut carrier, second, b"stolen", "Password").is_err());
+        assert!(delete(&mut carrier, second, "Password").is_err());
+
+        // Replacing or deleting a slot leaves other ones as they were
+        assert!(replace(&mut carrier, third, b"third message, again", "Password").is_ok());
+        assert!(delete(&mut carrier, first, "Password").is_ok());
+        assert_eq!(
+            read(&carrier, second, "Other").ok(),
+            Some(b"second message".to_vec())
+        );
+        assert_eq!(
+            find(&carrier, "Password").ok(),
+            Some(Some(b"third message, again".to_vec()))
+        );
+        assert_eq!(append(&mut carrier, b"fourth", "Another").ok(), Some(first));
+    }
+}
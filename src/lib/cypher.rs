@@ -6,15 +6,23 @@ use self::crypto::{
     bcrypt_pbkdf::bcrypt_pbkdf,
     blockmodes, buffer,
     buffer::{BufferResult, ReadBuffer, WriteBuffer},
+    chacha20::ChaCha20,
+    digest::Digest,
+    hmac::Hmac,
+    mac::{Mac, MacResult},
+    sha2::Sha256,
     symmetriccipher,
+    symmetriccipher::SynchronousStreamCipher,
 };
 
 use std::{
-    cmp,
+    cmp, fs,
     io::{Error, ErrorKind},
     str,
 };
 
+use super::binary::is_one_byte_chars_message;
+
 // Encrypt a buffer with the given key and iv using
 // AES-256/CBC/Pkcs encryption.
 fn encrypt(
@@ -188,6 +196,133 @@ pub fn simple_decrypt(vector: &[u8], password: &str) -> Result<String, Error> {
     }
 }
 
+/// Check that password is made of 1 byte chars, and long enough
+pub fn check_password(password: &str) -> Result<(), Error> {
+    if !is_one_byte_chars_message(password) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Input password must be 1 byte chars",
+        ));
+    }
+    if password.len() < 8 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Input password must be at least 8 letters long",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Derive a 256 bits key from password, bound to the given usage context
+/// so that different steganographic steps never share the same key
+pub fn derive_key(password: &str, context: &str) -> [u8; 32] {
+    derive_key_with_keyfiles(password, &[], context)
+}
+
+/// Size of authentication tags, in bytes
+pub const TAG_BYTES: usize = 16;
+
+/// Returns HMAC-SHA256 of data under given key, truncated to TAG_BYTES
+pub fn get_tag(key: &[u8], data: &[u8]) -> [u8; TAG_BYTES] {
+    let mut hmac = Hmac::new(Sha256::new(), key);
+    hmac.input(data);
+
+    let mut tag = [0u8; TAG_BYTES];
+    tag.copy_from_slice(&hmac.result().code()[..TAG_BYTES]);
+    tag
+}
+
+/// Check in constant time that tag authenticates data under given key
+pub fn check_tag(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    tag.len() == TAG_BYTES && MacResult::new(&get_tag(key, data)) == MacResult::new(tag)
+}
+
+/// Derive a 256 bits key from password and keyfile digests, bound to the given usage context
+/// Digests are appended to the password, no keyfile leaving the key derive_key gives
+pub fn derive_key_with_keyfiles(password: &str, keyfiles: &[[u8; 32]], context: &str) -> [u8; 32] {
+    let mut secret = password.as_bytes().to_vec();
+    for digest in keyfiles {
+        secret.extend_from_slice(digest);
+    }
+
+    let mut key: [u8; 32] = [0; 32];
+    bcrypt_pbkdf(&secret, context.as_bytes(), 2, &mut key);
+    key
+}
+
+/// Returns SHA-256 digests of keyfiles, sorted so that their order does not matter
+pub fn read_keyfiles(keyfiles: &[String]) -> Result<Vec<[u8; 32]>, Error> {
+    let mut digests = Vec::new();
+    for keyfile in keyfiles {
+        let contents = fs::read(keyfile).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!(
+                    "stegano/read_keyfiles : Unable to read keyfile {} : {}",
+                    keyfile, err
+                ),
+            )
+        })?;
+
+        let mut digest = [0u8; 32];
+        let mut hasher = Sha256::new();
+        hasher.input(&contents);
+        hasher.result(&mut digest);
+        digests.push(digest);
+    }
+    digests.sort_unstable();
+
+    Ok(digests)
+}
+
+/// Deterministic pseudo-random stream, seeded with a derived key
+/// Encoder and decoder sharing the same key get the very same stream
+pub struct KeyStream {
+    cipher: ChaCha20,
+}
+
+impl KeyStream {
+    /// Constructor
+    pub fn new(key: &[u8; 32]) -> KeyStream {
+        KeyStream {
+            cipher: ChaCha20::new(key, &[0; 8]),
+        }
+    }
+
+    /// Constructor, deriving the seed from password and context
+    pub fn from_password(password: &str, context: &str) -> KeyStream {
+        KeyStream::new(&derive_key(password, context))
+    }
+
+    /// Returns next 32 bits of the stream
+    pub fn next_u32(&mut self) -> u32 {
+        let mut output: [u8; 4] = [0; 4];
+        self.cipher.process(&[0; 4], &mut output);
+        u32::from_be_bytes(output)
+    }
+
+    /// Returns a uniformly distributed value within [0, bound[
+    pub fn next_below(&mut self, bound: u32) -> u32 {
+        // Reject values from the incomplete last range to avoid modulo bias
+        let zone = u32::MAX - u32::MAX % bound;
+        loop {
+            let value = self.next_u32();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Shuffle given slice in place (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.next_below(i as u32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
 // Tests
 #[cfg(test)]
 pub mod tests {
@@ -210,4 +345,74 @@ pub mod tests {
             "Hello, how is the weather today ?".to_string()
         );
     }
+
+    #[test]
+    fn test_derive_key_with_keyfiles() {
+        let keyfiles = ["test_files/test.png".to_string(), "Cargo.toml".to_string()];
+        let read = |keyfiles: &[String]| {
+            read_keyfiles(keyfiles).unwrap_or_else(|err| {
+                eprintln!("Error in test_derive_key_with_keyfiles: {}", err);
+                process::exit(1);
+            })
+        };
+
+        let digests = read(&keyfiles);
+        let key = derive_key_with_keyfiles("Password", &digests, "stegano/test");
+        assert_eq!(digests, read(&[keyfiles[1].clone(), keyfiles[0].clone()]));
+        assert_ne!(
+            key,
+            derive_key_with_keyfiles("Password", &digests[..1], "stegano/test")
+        );
+        assert_ne!(
+            key,
+            derive_key_with_keyfiles("Passwore", &digests, "stegano/test")
+        );
+        assert_ne!(key, derive_key("Password", "stegano/test"));
+        assert_eq!(
+            derive_key_with_keyfiles("Password", &[], "stegano/test"),
+            derive_key("Password", "stegano/test")
+        );
+        assert!(read_keyfiles(&["test_files/missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_tag() {
+        let key = derive_key("Password", "stegano/test");
+        let tag = get_tag(&key, b"Hello");
+
+        assert!(check_tag(&key, b"Hello", &tag));
+        assert!(!check_tag(&key, b"Hellp", &tag));
+        assert!(!check_tag(
+            &derive_key("Passwore", "stegano/test"),
+            b"Hello",
+            &tag
+        ));
+        assert!(!check_tag(&key, b"Hello", &tag[1..]));
+    }
+
+    #[test]
+    fn test_key_stream_is_deterministic() {
+        let mut stream1 = KeyStream::from_password("Password", "stegano/test");
+        let mut stream2 = KeyStream::from_password("Password", "stegano/test");
+        let mut stream3 = KeyStream::from_password("Password", "stegano/other");
+
+        let values1: Vec<u32> = (0..16).map(|_| stream1.next_u32()).collect();
+        let values2: Vec<u32> = (0..16).map(|_| stream2.next_u32()).collect();
+        let values3: Vec<u32> = (0..16).map(|_| stream3.next_u32()).collect();
+
+        assert_eq!(values1, values2);
+        assert_ne!(values1, values3);
+    }
+
+    #[test]
+    fn test_key_stream_shuffle() {
+        let mut items: Vec<u32> = (0..100).collect();
+        KeyStream::from_password("Password", "stegano/test").shuffle(&mut items);
+
+        let mut sorted = items.clone();
+        sorted.sort();
+
+        assert_ne!(items, sorted);
+        assert_eq!(sorted, (0..100).collect::<Vec<u32>>());
+    }
 }
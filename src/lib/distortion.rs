@@ -0,0 +1,201 @@
+//! Content-adaptive distortion costs, telling how detectable a change of each pixel would be
+//! Costs are low in noisy / textured areas and high in smooth ones
+
+/// Kernel-based high-pass filter used by HILL
+const HIGH_PASS: [[f64; 3]; 3] = [[-1.0, 2.0, -1.0], [2.0, -4.0, 2.0], [-1.0, 2.0, -1.0]];
+
+/// Size of the averaging window applied to residuals
+const RESIDUAL_WINDOW: usize = 3;
+
+/// Size of the averaging window spreading costs around
+const SPREAD_WINDOW: usize = 15;
+
+/// Avoid divisions by zero on perfectly flat areas
+const EPSILON: f64 = 1e-10;
+
+/// Returns the value at (x, y), mirroring coordinates falling outside the plane
+fn get_mirrored(plane: &[f64], width: usize, height: usize, x: isize, y: isize) -> f64 {
+    let mirror = |value: isize, max: usize| -> usize {
+        let max = max as isize;
+        let mut value = value;
+        if value < 0 {
+            value = -value - 1;
+        }
+        if value >= max {
+            value = 2 * max - value - 1;
+        }
+        value.max(0).min(max - 1) as usize
+    };
+
+    plane[mirror(y, height) * width + mirror(x, width)]
+}
+
+/// Convolve plane with the 3x3 high-pass kernel, returning absolute residuals
+fn high_pass(plane: &[f64], width: usize, height: usize) -> Vec<f64> {
+    let mut output = vec![0.0; plane.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = 0.0;
+            for (dy, row) in HIGH_PASS.iter().enumerate() {
+                for (dx, coefficient) in row.iter().enumerate() {
+                    sum += coefficient
+                        * get_mirrored(
+                            plane,
+                            width,
+                            height,
+                            x as isize + dx as isize - 1,
+                            y as isize + dy as isize - 1,
+                        );
+                }
+            }
+            output[y * width + x] = sum.abs();
+        }
+    }
+
+    output
+}
+
+/// Average plane over a size x size window, using an integral image
+fn box_filter(plane: &[f64], width: usize, height: usize, size: usize) -> Vec<f64> {
+    let radius = (size / 2) as isize;
+
+    // Integral image of the mirrored-padded plane
+    let padded_width = width + 2 * radius as usize;
+    let padded_height = height + 2 * radius as usize;
+    let mut integral = vec![0.0; (padded_width + 1) * (padded_height + 1)];
+
+    for y in 0..padded_height {
+        let mut row_sum = 0.0;
+        for x in 0..padded_width {
+            row_sum += get_mirrored(
+                plane,
+                width,
+                height,
+                x as isize - radius,
+                y as isize - radius,
+            );
+            integral[(y + 1) * (padded_width + 1) + x + 1] =
+                integral[y * (padded_width + 1) + x + 1] + row_sum;
+        }
+    }
+
+    let area = (size * size) as f64;
+    let mut output = vec![0.0; plane.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            let top = y * (padded_width + 1);
+            let bottom = (y + size) * (padded_width + 1);
+            output[y * width + x] =
+                (integral[bottom + x + size] - integral[bottom + x] - integral[top + x + size]
+                    + integral[top + x])
+                    / area;
+        }
+    }
+
+    output
+}
+
+/// Compute HILL costs for a single channel plane (row-major, width x height values)
+pub fn hill_costs(plane: &[f64], width: usize, height: usize) -> Vec<f64> {
+    let residuals = box_filter(
+        &high_pass(plane, width, height),
+        width,
+        height,
+        RESIDUAL_WINDOW,
+    );
+
+    let inverted: Vec<f64> = residuals.iter().map(|r| 1.0 / (r + EPSILON)).collect();
+
+    box_filter(&inverted, width, height, SPREAD_WINDOW)
+}
+
+/// Compute a texture score for every pixel of a single channel plane (row-major, width x height values)
+/// Score is the 3x3 local variance (times 81) of the values without their low_bits least
+/// significant bits, so that rewriting those bits never changes the score
+pub fn texture_scores(plane: &[u16], width: usize, height: usize, low_bits: u8) -> Vec<u64> {
+    let high_bits: Vec<f64> = plane.iter().map(|&v| (v >> low_bits) as f64).collect();
+    let mut scores = vec![0; plane.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum: u64 = 0;
+            let mut square_sum: u64 = 0;
+
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    let value = get_mirrored(
+                        &high_bits,
+                        width,
+                        height,
+                        x as isize + dx - 1,
+                        y as isize + dy - 1,
+                    ) as u64;
+                    sum += value;
+                    square_sum += value * value;
+                }
+            }
+
+            scores[y * width + x] = 9 * square_sum - sum * sum;
+        }
+    }
+
+    scores
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_filter_keeps_constant_plane() {
+        let plane = vec![3.0; 20 * 10];
+
+        assert!(box_filter(&plane, 20, 10, 5)
+            .iter()
+            .all(|v| (v - 3.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_hill_costs_prefer_texture() {
+        // Left half is flat, right half is a checkerboard
+        let (width, height) = (64, 32);
+        let plane: Vec<f64> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                if x < width / 2 {
+                    128.0
+                } else {
+                    128.0 + 20.0 * ((x + y) % 2) as f64
+                }
+            })
+            .collect();
+
+        let costs = hill_costs(&plane, width, height);
+
+        assert!(costs[16 * width + 4] > 1000.0 * costs[16 * width + 60]);
+    }
+
+    #[test]
+    fn test_texture_scores_ignore_least_significant_bits() {
+        let (width, height) = (16, 16);
+        let plane: Vec<u16> = (0..width * height).map(|i| (i * 37 % 256) as u16).collect();
+        let flipped: Vec<u16> = plane.iter().map(|v| v ^ 1).collect();
+        let flipped3: Vec<u16> = plane.iter().map(|v| v ^ 7).collect();
+        let flat = vec![200; width * height];
+
+        assert_eq!(
+            texture_scores(&plane, width, height, 1),
+            texture_scores(&flipped, width, height, 1)
+        );
+        assert_eq!(
+            texture_scores(&plane, width, height, 3),
+            texture_scores(&flipped3, width, height, 3)
+        );
+        assert!(texture_scores(&flat, width, height, 1)
+            .iter()
+            .all(|&s| s == 0));
+    }
+}
@@ -1,34 +1,168 @@
 //! Module handling dot-matrix inner content manipulations
-extern crate image;
+extern crate rand;
 
-use self::image::{DynamicImage, GenericImage, GenericImageView, ImageError, Pixel};
+use self::rand::Rng;
 
 use std::{fmt, str};
 
 use std::io::{Error, ErrorKind};
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::BufReader;
 
 use super::binary::*;
+use super::carrier::{self, frame_message, read_framed_message, Carrier, SlotsView, LENGTH_BITS};
+use super::container::{self, ContainerEntry};
 use super::cypher::*;
-
-// TODO : put this in external file, or as input parameter
+use super::distortion::{hill_costs, texture_scores};
+use super::palette::SortedPalette;
+use super::picture::{ColorType, Picture};
+use super::pvd;
+use super::quality::get_psnr;
+use super::reversible::Bins;
+use super::sanitize::{get_png_extras, SanitizeReport};
+use super::sealing::{PasswordSealing, Sealing};
+use super::stc;
+use super::strategy::{EmbeddingStrategy, StrategyRegistry};
+
+// Ending character of messages written before headers were introduced
 const ENDING_CHAR: char = '~';
 
-/// Basic structure : a DynamicImage and a filepath
+/// Number of color samples holding the header in their least significant bit,
+/// at the very beginning of the picture
+const HEADER_SAMPLES: usize = 24;
+
+/// Header size : magic number, then embedding mode
+const HEADER_BYTES: usize = 3;
+
+/// Magic number telling the header is valid for the given password
+const HEADER_MAGIC: u16 = 0x5354;
+
+/// Key derivation context of the header mask
+const HEADER_CONTEXT: &str = "stegano/header";
+
+/// Mode identifier telling the header goes on with an embedding strategy and its parameter
+const STRATEGY_MODE: u8 = 0x0F;
+
+/// Header size of strategies : magic number, strategy mode, strategy identifier and parameter
+const STRATEGY_HEADER_BYTES: usize = 5;
+
+/// Number of color samples holding the header of strategies
+const STRATEGY_HEADER_SAMPLES: usize = STRATEGY_HEADER_BYTES * 8;
+
+/// Header size of deniable payloads : magic number only
+const DENIABLE_HEADER_BYTES: usize = 2;
+
+/// Key derivation context of deniable payloads header mask
+const DENIABLE_HEADER_CONTEXT: &str = "stegano/deniable/header";
+
+/// Key derivation context of deniable payloads authentication tag
+const DENIABLE_TAG_CONTEXT: &str = "stegano/deniable/tag";
+
+/// Key derivation context of deniable payloads sample order
+const DENIABLE_WALK_CONTEXT: &str = "stegano/deniable/walk";
+
+/// Key derivation context of the adaptive (syndrome-trellis) embedding
+const ADAPTIVE_CONTEXT: &str = "stegano/adaptive";
+
+/// Highest number of low bits used in each sample of 16 bits pictures
+const MAX_LOW_BITS: u8 = 8;
+
+/// Percentage of the most textured pixels textured mode uses, unless told otherwise
+const DEFAULT_TEXTURE_PERCENTAGE: u8 = 50;
+
+/// Embedding algorithms, the one used being written in the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingMode {
+    /// Least significant bits, pixel after pixel
+    Lsb,
+    /// Syndrome-trellis codes, driven by HILL distortion costs
+    Adaptive,
+    /// Least significant bits of the most textured pixels only
+    Textured,
+    /// Pixel-value differencing, between adjacent pixels
+    Pvd,
+    /// Histogram shifting, the original picture can be restored bit for bit
+    Reversible,
+    /// Parity of luminance-sorted palette entries (EzStego), for palette pictures only
+    Palette,
+}
+
+impl EmbeddingMode {
+    /// Returns mode identifier, as stored in the header
+    fn to_u8(self) -> u8 {
+        match self {
+            EmbeddingMode::Lsb => 0,
+            EmbeddingMode::Adaptive => 1,
+            EmbeddingMode::Textured => 2,
+            EmbeddingMode::Pvd => 3,
+            EmbeddingMode::Reversible => 4,
+            EmbeddingMode::Palette => 5,
+        }
+    }
+
+    /// Returns mode matching identifier stored in the header
+    fn from_u8(value: u8) -> Option<EmbeddingMode> {
+        match value {
+            0 => Some(EmbeddingMode::Lsb),
+            1 => Some(EmbeddingMode::Adaptive),
+            2 => Some(EmbeddingMode::Textured),
+            3 => Some(EmbeddingMode::Pvd),
+            4 => Some(EmbeddingMode::Reversible),
+            5 => Some(EmbeddingMode::Palette),
+            _ => None,
+        }
+    }
+}
+
+impl str::FromStr for EmbeddingMode {
+    type Err = Error;
+    /// Convert mode name, as typed by user, into mode
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode.to_uppercase().as_str() {
+            "LSB" => Ok(EmbeddingMode::Lsb),
+            "ADAPTIVE" => Ok(EmbeddingMode::Adaptive),
+            "TEXTURED" => Ok(EmbeddingMode::Textured),
+            "PVD" => Ok(EmbeddingMode::Pvd),
+            "REVERSIBLE" => Ok(EmbeddingMode::Reversible),
+            "PALETTE" => Ok(EmbeddingMode::Palette),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("stegano/embedding_mode : Unknown embedding mode {}", mode),
+            )),
+        }
+    }
+}
+
+/// Options of payload wiping
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WipeOptions {
+    /// Number of low bits overwritten in each sample,
+    /// None meaning every low bit the carrier may hide a payload in
+    pub low_bits: Option<u8>,
+}
+
+/// Basic structure : a Picture, a filepath, and the number of low bits used per sample
+/// Palette pictures also get their palette sorted by luminance
 pub struct DotMatrix {
-    image: Result<DynamicImage, ImageError>,
+    image: Result<Picture, Error>,
     input_filepath: String,
+    low_bits: u8,
+    texture_percentage: u8,
+    sorted_palette: Option<SortedPalette>,
 }
 
 impl DotMatrix {
     /// Constructor
     pub fn new(filepath: &str) -> DotMatrix {
+        let image = Picture::open(filepath);
         DotMatrix {
             input_filepath: filepath.to_string(),
-            image: image::open(filepath),
+            sorted_palette: get_sorted_palette(&image),
+            image,
+            low_bits: 1,
+            texture_percentage: DEFAULT_TEXTURE_PERCENTAGE,
         }
     }
 
@@ -46,14 +180,67 @@ impl DotMatrix {
         self.input_filepath.clone()
     }
 
+    /// Accessor returning number of low bits used in each sample by LSB and textured modes
+    pub fn get_low_bits(&self) -> u8 {
+        self.low_bits
+    }
+
+    /// Setter for the number of low bits used in each sample by LSB and textured modes
+    /// Only 16 bits pictures may use more than one bit, up to 8
+    pub fn set_low_bits(&mut self, low_bits: u8) -> Result<(), Error> {
+        let max_low_bits = self.get_max_low_bits();
+
+        if low_bits == 0 || low_bits > max_low_bits {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "stegano/set_low_bits : Number of low bits must range from 1 to {} for this picture",
+                    max_low_bits
+                ),
+            ));
+        }
+
+        self.low_bits = low_bits;
+        Ok(())
+    }
+
+    /// Accessor returning percentage of the most textured pixels used by textured mode
+    pub fn get_texture_percentage(&self) -> u8 {
+        self.texture_percentage
+    }
+
+    /// Setter for the percentage of the most textured pixels used by textured mode
+    /// Decoding needs no percentage, as smaller ones only keep the start of the same ranking
+    pub fn set_texture_percentage(&mut self, texture_percentage: u8) -> Result<(), Error> {
+        if texture_percentage == 0 || texture_percentage > 100 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/set_texture_percentage : Percentage of textured pixels must range from 1 to 100",
+            ));
+        }
+
+        self.texture_percentage = texture_percentage;
+        Ok(())
+    }
+
+    /// Returns highest number of low bits which may be used in each sample
+    fn get_max_low_bits(&self) -> u8 {
+        match self.image {
+            Ok(ref image) if image.get_bit_depth() == 16 => MAX_LOW_BITS,
+            _ => 1,
+        }
+    }
+
     /// Setter allowing the user to change the source picture
     pub fn read_from_file(&mut self, filepath: &str) {
         self.input_filepath = filepath.to_string();
-        self.image = image::open(filepath);
+        self.image = Picture::open(filepath);
+        self.low_bits = 1;
+        self.sorted_palette = get_sorted_palette(&self.image);
     }
 
     /// Function to write the picture into target file
-    /// TODO : refactor
+    /// Color type and bit depth of the input picture are kept
     pub fn write_to_file(&self, filepath: &str) -> Result<(), Error> {
         if let Ok(ref image_inner) = &self.image {
             if let Ok(()) = image_inner.save(filepath) {
@@ -72,520 +259,2202 @@ impl DotMatrix {
         }
     }
 
-    /// Function to store 3 bits, hidden into pixel at input coordinates
-    fn store_3bits_at(&mut self, x: u32, y: u32, bits: &[bool]) -> Result<(), Error> {
-        // Get the pixel at input coordinates
-        let ref mut image_unwrapped;
-        let pixel;
-
-        if let Ok(ref mut image_unwrapped_temp) = self.image {
-            image_unwrapped = image_unwrapped_temp
+    /// Returns inner picture
+    fn get_picture(&self) -> Result<&Picture, Error> {
+        if let Ok(ref image_unwrapped) = self.image {
+            Ok(image_unwrapped)
         } else {
-            return Err(Error::new(
+            Err(Error::new(
                 ErrorKind::InvalidData,
-                "stegano/store_3bits_at : Unable to open inner image!",
-            ));
+                "stegano/get_picture : Unable to open inner image!",
+            ))
         }
+    }
 
-        pixel = image_unwrapped.get_pixel(x, y);
-
-        // Retrieve pixel components
-        let mut red = Byte::new(pixel.to_rgb()[0]);
-        let mut green = Byte::new(pixel.to_rgb()[1]);
-        let mut blue = Byte::new(pixel.to_rgb()[2]);
-
-        // Then binary-OR it with the boolean input values
-        // If the end of the encryption message is reached,
-        // then the bit array may have less than 3 elements, so, store what is storable
-        if !bits.is_empty() {
-            red.store_bit(bits[0]);
+    /// Returns inner picture, mutable
+    fn get_picture_mut(&mut self) -> Result<&mut Picture, Error> {
+        if let Ok(ref mut image_unwrapped) = self.image {
+            Ok(image_unwrapped)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/get_picture_mut : Unable to open inner image!",
+            ))
         }
-        if bits.len() >= 2 {
-            green.store_bit(bits[1]);
+    }
+
+    /// Returns number of color channels : 1 for grayscale pictures, 3 otherwise
+    fn get_channels(&self) -> usize {
+        match self.image {
+            Ok(ref image) => image.get_color_type().get_color_channels(),
+            Err(_) => 3,
         }
-        if bits.len() == 3 {
-            blue.store_bit(bits[2]);
+    }
+
+    /// Returns number of color samples, alpha excluded
+    fn get_samples_count(&self) -> usize {
+        match self.image {
+            Ok(ref image) => image.get_color_samples_count(),
+            Err(_) => 0,
         }
+    }
 
-        // Create new pixel from altered RGB values and put it in image
-        // Alpha is not altered
-        image_unwrapped.put_pixel(
-            x,
-            y,
-            image::Rgba([
-                red.get_value(),
-                green.get_value(),
-                blue.get_value(),
-                pixel[3],
-            ]),
-        );
+    /// Returns number of pixels whose samples hold the header
+    fn get_header_pixels_count(&self) -> usize {
+        let channels = self.get_channels();
+        HEADER_SAMPLES.div_ceil(channels)
+    }
 
-        Ok(())
+    /// Returns true if inner picture is a palette one
+    fn is_indexed(&self) -> bool {
+        match self.image {
+            Ok(ref image) => image.get_color_type() == ColorType::Indexed,
+            Err(_) => false,
+        }
     }
 
-    /// Returns bits stored in pixel, at given position
-    fn get_3bits_at(&self, x: u32, y: u32) -> Result<[bool; 3], Error> {
-        let pixel;
+    /// Returns number of samples available after the header
+    fn get_payload_samples_count(&self) -> usize {
+        self.get_samples_count().saturating_sub(HEADER_SAMPLES)
+    }
 
-        if let Ok(ref image_unwrapped) = &self.image {
-            pixel = image_unwrapped.get_pixel(x, y);
-        } else {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!(
-                    "stegano/get_3bits_at : Unable to get pixel at coordinates ({},{})",
-                    x, y
-                ),
-            ));
-        }
+    /// Store header, telling which embedding mode was used, in the first samples
+    /// Number of low bits minus one goes in the upper half of the mode byte
+    fn store_header(&mut self, mode: EmbeddingMode, password: &str) -> Result<(), Error> {
+        let magic = HEADER_MAGIC.to_be_bytes();
+        let header = [
+            magic[0],
+            magic[1],
+            mode.to_u8() | ((self.low_bits - 1) << 4),
+        ];
+
+        carrier::store_header(self, &header, password, HEADER_CONTEXT)
+    }
 
-        // Use modulo to know whether value is odd or not
-        Ok([
-            pixel.to_rgb()[0] % 2 != 0,
-            pixel.to_rgb()[1] % 2 != 0,
-            pixel.to_rgb()[2] % 2 != 0,
-        ])
+    /// Read header from the first samples
+    /// Returns embedding mode and number of low bits, or None if no header matching password is found
+    fn read_header(&self, password: &str) -> Result<Option<(EmbeddingMode, u8)>, Error> {
+        match carrier::read_header(self, HEADER_BYTES, password, HEADER_CONTEXT)? {
+            Some(ref header) if u16::from_be_bytes([header[0], header[1]]) == HEADER_MAGIC => {
+                Ok(EmbeddingMode::from_u8(header[2] & 0x0F)
+                    .map(|mode| (mode, (header[2] >> 4) + 1)))
+            }
+            _ => Ok(None),
+        }
     }
 
-    /// Returns true if given x is within picture boundaries
-    fn x_in_dimensions(&self, x: u32) -> bool {
-        x < self.get_dimensions().0
+    /// Store header of embedding strategies, telling which strategy and parameter were used
+    fn store_strategy_header(
+        &mut self,
+        strategy: &dyn EmbeddingStrategy,
+        password: &str,
+    ) -> Result<(), Error> {
+        let magic = HEADER_MAGIC.to_be_bytes();
+        let header = [
+            magic[0],
+            magic[1],
+            STRATEGY_MODE,
+            strategy.get_id(),
+            strategy.get_parameter(),
+        ];
+
+        carrier::store_header(self, &header, password, HEADER_CONTEXT)
     }
 
-    /// Returns true if given y is within picture boundaries
-    fn y_in_dimensions(&self, y: u32) -> bool {
-        y < self.get_dimensions().1
+    /// Read header of embedding strategies from the first samples
+    /// Returns strategy identifier and parameter, or None if no such header matches password
+    fn read_strategy_header(&self, password: &str) -> Result<Option<(u8, u8)>, Error> {
+        match carrier::read_header(self, STRATEGY_HEADER_BYTES, password, HEADER_CONTEXT)? {
+            Some(ref header)
+                if u16::from_be_bytes([header[0], header[1]]) == HEADER_MAGIC
+                    && header[2] == STRATEGY_MODE =>
+            {
+                Ok(Some((header[3], header[4])))
+            }
+            _ => Ok(None),
+        }
     }
 
-    /// Returns true if image is big enough to store given message
-    fn is_big_enough_to_store_message(&self, message_len: u32) -> bool {
-        self.get_dimensions().0 * self.get_dimensions().1 * 3 + 1 /* ENDING_CHAR */ > message_len
+    /// Encode given message in self image, using given embedding strategy
+    /// Strategy identifier and parameter are written in the header, so that decode finds it back
+    pub fn encode_with_strategy(
+        &mut self,
+        message: &str,
+        password: &str,
+        strategy: &dyn EmbeddingStrategy,
+    ) -> Result<(), Error> {
+        self.encode_sealed_with_strategy(message, &PasswordSealing::new(password), strategy)
     }
 
-    /// Store random bits from input x and y coordinates, to hide encrypted message length
-    fn store_random_from(&mut self, mut x: u32, mut y: u32) -> Result<(), Error> {
-        let image_unwrapped;
-        let (max_x, max_y) = self.get_dimensions();
+    /// Encode given message in self image, sealed with given method, using given embedding strategy
+    pub fn encode_sealed_with_strategy(
+        &mut self,
+        message: &str,
+        sealing: &dyn Sealing,
+        strategy: &dyn EmbeddingStrategy,
+    ) -> Result<(), Error> {
+        let password = &sealing.get_carrier_key();
+        check_message_and_password(message, password)?;
+
+        if self.is_indexed() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/encode : Palette mode is the only one for palette pictures!",
+            ));
+        }
 
-        if let Ok(ref mut image_unwrapped_temp) = self.image {
-            image_unwrapped = image_unwrapped_temp
-        } else {
+        if self.get_samples_count() <= STRATEGY_HEADER_SAMPLES {
             return Err(Error::new(
                 ErrorKind::InvalidData,
-                "stegano/store_random_from : Unable to open inner image!",
+                "stegano/encode : Input file not big enough to store message!",
             ));
         }
 
-        // Ensure to stays within picture boundaries
-        while {
-            let pixel_unwrapped = image_unwrapped.get_pixel(x, y);
-
-            let mut red = Byte::new(pixel_unwrapped.to_rgb()[0]);
-            let mut green = Byte::new(pixel_unwrapped.to_rgb()[1]);
-            let mut blue = Byte::new(pixel_unwrapped.to_rgb()[2]);
-
-            // Store random bits in least significant bit of each color RGB component
-            red.store_random_bit();
-            green.store_random_bit();
-            blue.store_random_bit();
-            // Create new pixel from altered RGB values and put it in image
-            // Alpha is not altered
-            image_unwrapped.put_pixel(
-                x,
-                y,
-                image::Rgba([
-                    red.get_value(),
-                    green.get_value(),
-                    blue.get_value(),
-                    pixel_unwrapped[3],
-                ]),
-            );
+        let vector = frame_message(&sealing.seal(message)?);
+        strategy.embed(self, STRATEGY_HEADER_SAMPLES, &vector, password)?;
 
-            move_cursor_to_next_pixel(&mut x, &mut y, (max_x, max_y)).is_ok()
-        } {}
+        self.store_strategy_header(strategy, password)
+    }
 
-        Ok(())
+    /// Encode given file in self image, using given embedding strategy
+    pub fn encode_file_with_strategy(
+        &mut self,
+        filepath: &str,
+        password: &str,
+        strategy: &dyn EmbeddingStrategy,
+    ) -> Result<(), Error> {
+        let input_file = File::open(filepath)?;
+        let mut buf_reader = BufReader::new(input_file);
+        let mut contents = String::new();
+        buf_reader.read_to_string(&mut contents)?;
+        self.encode_with_strategy(contents.as_str(), password, strategy)
     }
 
     /// Encode given file in self image
     pub fn encode_file(&mut self, filepath: &str, password: &str) -> Result<(), Error> {
+        self.encode_file_with_mode(filepath, password, EmbeddingMode::Lsb)
+    }
+
+    /// Encode given file in self image, using given embedding mode
+    pub fn encode_file_with_mode(
+        &mut self,
+        filepath: &str,
+        password: &str,
+        mode: EmbeddingMode,
+    ) -> Result<(), Error> {
         let input_file = File::open(filepath)?;
         let mut buf_reader = BufReader::new(input_file);
         let mut contents = String::new();
         buf_reader.read_to_string(&mut contents)?;
-        self.encode(contents.as_str(), password)
+        self.encode_with_mode(contents.as_str(), password, mode)
     }
 
     /// Encode given message in self image
     pub fn encode(&mut self, message: &str, password: &str) -> Result<(), Error> {
-        if !is_one_byte_chars_message(message) {
+        self.encode_with_mode(message, password, EmbeddingMode::Lsb)
+    }
+
+    /// Encode given message in self image, using given embedding mode
+    /// Mode is written in the header, so that decode knows how to extract the message
+    pub fn encode_with_mode(
+        &mut self,
+        message: &str,
+        password: &str,
+        mode: EmbeddingMode,
+    ) -> Result<(), Error> {
+        self.encode_sealed_with_mode(message, &PasswordSealing::new(password), mode)
+    }
+
+    /// Encode given message in self image, sealed with given method, using given embedding mode
+    /// Carrier key of the sealing method masks the header, as passwords do
+    pub fn encode_sealed_with_mode(
+        &mut self,
+        message: &str,
+        sealing: &dyn Sealing,
+        mode: EmbeddingMode,
+    ) -> Result<(), Error> {
+        let password = &sealing.get_carrier_key();
+        check_message_and_password(message, password)?;
+
+        if self.get_payload_samples_count() == 0 {
             return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Input message must be 1 byte chars",
+                ErrorKind::InvalidData,
+                "stegano/encode : Input file not big enough to store message!",
             ));
         }
-        if !is_one_byte_chars_message(password) {
+
+        if self.low_bits > 1 && mode != EmbeddingMode::Lsb && mode != EmbeddingMode::Textured {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
-                "Input password must be 1 byte chars",
+                "stegano/encode : Several low bits can only be used by LSB and textured modes!",
             ));
         }
-        if password.len() < 8 {
+
+        // Palette pictures can only be written through their palette
+        let mode = match (self.is_indexed(), mode) {
+            (true, EmbeddingMode::Lsb) | (true, EmbeddingMode::Palette) => EmbeddingMode::Palette,
+            (false, EmbeddingMode::Palette) | (true, _) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "stegano/encode : Palette mode is the only one for palette pictures!",
+                ))
+            }
+            (false, mode) => mode,
+        };
+
+        if mode == EmbeddingMode::Palette && self.sorted_palette.is_none() {
             return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Input password must be at least 8 letters long",
+                ErrorKind::InvalidData,
+                "stegano/encode : Palette must hold at least 2 colors!",
             ));
         }
 
-        // Add ending character to input message
-        let mut encrypted_message = simple_encrypt(message, password)?;
+        let encrypted_message = sealing.seal(message)?;
 
-        add_ending_char(&mut encrypted_message);
+        match mode {
+            EmbeddingMode::Lsb => self.encode_lsb(&encrypted_message)?,
+            EmbeddingMode::Adaptive => self.embed_adaptive(&encrypted_message, password)?,
+            EmbeddingMode::Textured => self.embed_textured(&encrypted_message)?,
+            EmbeddingMode::Pvd => self.encode_pvd(&encrypted_message)?,
+            EmbeddingMode::Reversible => self.encode_reversible(&encrypted_message)?,
+            EmbeddingMode::Palette => self.encode_lsb(&encrypted_message)?,
+        }
 
-        // Convert message to binary vector
-        let vector =
-            convert_byte_vec_to_bit_array(&convert_u8_vec_to_byte_array(&encrypted_message));
+        self.store_header(mode, password)
+    }
 
-        // Get binary vector length
-        let message_length = vector.len();
+    /// Encode given message along with a decoy one, each password decoding its own message only
+    /// Samples are split into two disjoint sets, even and odd ones, each payload taking one set
+    /// at random, in an order derived from its password, with its own header
+    /// Both sets are filled with random bits after their message, as any set without message is
+    pub fn encode_with_decoy(
+        &mut self,
+        message: &str,
+        password: &str,
+        decoy_message: &str,
+        decoy_password: &str,
+    ) -> Result<(), Error> {
+        self.encode_sealed_with_decoy(
+            message,
+            &PasswordSealing::new(password),
+            decoy_message,
+            &PasswordSealing::new(decoy_password),
+        )
+    }
 
-        // Check if picture is big enough to store binary vector
-        if !self.is_big_enough_to_store_message(message_length as u32) {
+    /// Encode given message along with a decoy one, each being sealed with its own method
+    pub fn encode_sealed_with_decoy(
+        &mut self,
+        message: &str,
+        sealing: &dyn Sealing,
+        decoy_message: &str,
+        decoy_sealing: &dyn Sealing,
+    ) -> Result<(), Error> {
+        let (password, decoy_password) =
+            (sealing.get_carrier_key(), decoy_sealing.get_carrier_key());
+        check_message_and_password(message, &password)?;
+        check_message_and_password(decoy_message, &decoy_password)?;
+
+        if password == decoy_password {
             return Err(Error::new(
-                ErrorKind::InvalidData,
-                "stegano/encode : Input file not big enough to store message!",
+                ErrorKind::InvalidInput,
+                "stegano/encode : Decoy password must differ from the real one!",
             ));
         }
 
-        // Position indexes
-        let mut x = 0;
-        let mut y = 0;
+        let set = rand::thread_rng().gen_range(0, 2);
+        self.store_deniable(set, &sealing.seal(message)?, &password)?;
+        self.store_deniable(
+            1 - set,
+            &decoy_sealing.seal(decoy_message)?,
+            &decoy_password,
+        )
+    }
 
-        let (max_x, max_y) = self.get_dimensions();
+    /// Returns samples of given deniable set, in the order derived from password
+    fn get_deniable_walk(&self, set: usize, password: &str) -> Vec<usize> {
+        let mut walk: Vec<usize> = (set..self.get_samples_count()).step_by(2).collect();
+        KeyStream::from_password(password, DENIABLE_WALK_CONTEXT).shuffle(&mut walk);
+        walk
+    }
 
-        // Parsing cursor : as we can store 3 bits in a pixel
-        // this index ensures that we progress 3 bits by 3 bits in the message converted as vector
-        // which we want to encore
-        let mut parsing_cursor = 0;
+    /// Store header, encrypted message and its tag in given deniable set, then random bits
+    fn store_deniable(
+        &mut self,
+        set: usize,
+        encrypted_message: &[u8],
+        password: &str,
+    ) -> Result<(), Error> {
+        let walk = self.get_deniable_walk(set, password);
+        let mut view = SlotsView::new(self as &mut dyn Carrier, walk);
+
+        let mut payload = encrypted_message.to_vec();
+        payload.extend_from_slice(&get_tag(
+            &derive_key(password, DENIABLE_TAG_CONTEXT),
+            encrypted_message,
+        ));
+
+        let index = carrier::store_from(
+            &mut view,
+            DENIABLE_HEADER_BYTES * 8,
+            1,
+            &frame_message(&payload),
+        )?;
+        carrier::store_random_from(&mut view, index, 1)?;
+        carrier::store_header(
+            &mut view,
+            &HEADER_MAGIC.to_be_bytes(),
+            password,
+            DENIABLE_HEADER_CONTEXT,
+        )
+    }
 
-        // Ensure to stays within picture boundaries
-        while {
-            // Check if message end is reached and if there is less than 3 bits to write into picture
-            if parsing_cursor + 2 >= message_length {
-                // If so, dump remaining vector bits into last pixel
-                self.store_3bits_at(x, y, &vector[parsing_cursor..])?;
-
-                // Store random things to hide picture alteration from picture analysers
-                if self.x_in_dimensions(x + 1) {
-                    self.store_random_from(x + 1, y)?;
-                } else if self.y_in_dimensions(y + 1) {
-                    self.store_random_from(0, y + 1)?;
-                } else {
+    /// Decode encrypted message of the deniable set whose header matches password, if any
+    /// Fails when a header matches but the tag does not, rather than trying other layouts
+    fn decode_deniable(&self, password: &str) -> Result<Option<Vec<u8>>, Error> {
+        for set in 0..2 {
+            let view = SlotsView::new(self as &dyn Carrier, self.get_deniable_walk(set, password));
+            let header = carrier::read_header(
+                &view,
+                DENIABLE_HEADER_BYTES,
+                password,
+                DENIABLE_HEADER_CONTEXT,
+            )?;
+
+            if header.is_some_and(|header| header == HEADER_MAGIC.to_be_bytes()) {
+                let mut encrypted_message =
+                    carrier::read_framed_from(&view, DENIABLE_HEADER_BYTES * 8, 1)?;
+                let tag =
+                    encrypted_message.split_off(encrypted_message.len().saturating_sub(TAG_BYTES));
+
+                if !check_tag(
+                    &derive_key(password, DENIABLE_TAG_CONTEXT),
+                    &encrypted_message,
+                    &tag,
+                ) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "stegano/decode : Deniable payload failed authentication!",
+                    ));
                 }
 
-                return Ok(()); // Hell yeah, it's finished !
-            } else {
-                // If not, store 3 bits in current pixel
-                self.store_3bits_at(x, y, &vector[parsing_cursor..parsing_cursor + 3])?;
+                return Ok(Some(encrypted_message));
             }
+        }
 
-            parsing_cursor += 3;
+        Ok(None)
+    }
 
-            move_cursor_to_next_pixel(&mut x, &mut y, (max_x, max_y)).is_ok() // Error seems to be here !
-        } {}
+    /// Append message in a slotted container, created if picture holds none
+    /// Returns index of the container slot holding message
+    pub fn append_message(&mut self, message: &str, password: &str) -> Result<usize, Error> {
+        self.append_sealed_message(message, &PasswordSealing::new(password))
+    }
 
-        Err(Error::new(
-            ErrorKind::Other,
-            "stegano/encode : Input file not big enough to store message",
-        )) // Should not happen
+    /// Append message in a slotted container, message being sealed with given method
+    pub fn append_sealed_message(
+        &mut self,
+        message: &str,
+        sealing: &dyn Sealing,
+    ) -> Result<usize, Error> {
+        let password = &sealing.get_carrier_key();
+        check_message_and_password(message, password)?;
+        container::append(self, &sealing.seal(message)?, password)
     }
 
-    /// Decodes image and write result file
-    pub fn decode_and_write(&self, filepath: &str, password: &str) -> Result<(), Error> {
-        let decoded_string = &self.decode(password)?;
-        let mut output_file = File::create(filepath)?;
-        output_file.write_all(decoded_string.as_bytes())
+    /// Replace message of given container slot, if password owns it
+    pub fn replace_message(
+        &mut self,
+        index: usize,
+        message: &str,
+        password: &str,
+    ) -> Result<(), Error> {
+        self.replace_sealed_message(index, message, &PasswordSealing::new(password))
     }
 
-    /// Decodes image and return result string
-    pub fn decode(&self, password: &str) -> Result<String, Error> {
-        if !is_one_byte_chars_message(password) {
+    /// Replace message of given container slot, message being sealed with given method
+    pub fn replace_sealed_message(
+        &mut self,
+        index: usize,
+        message: &str,
+        sealing: &dyn Sealing,
+    ) -> Result<(), Error> {
+        let password = &sealing.get_carrier_key();
+        check_message_and_password(message, password)?;
+        container::replace(self, index, &sealing.seal(message)?, password)
+    }
+
+    /// Delete message of given container slot, if password owns it
+    pub fn delete_message(&mut self, index: usize, password: &str) -> Result<(), Error> {
+        container::delete(self, index, password)
+    }
+
+    /// Returns container slots, telling which ones are in use and which ones password owns
+    pub fn list_messages(&self, password: &str) -> Result<Vec<ContainerEntry>, Error> {
+        container::list(self, password)
+    }
+
+    /// Remove any hidden payload : low bits of every sample, not only up to the message end,
+    /// are overwritten with fresh random bits, as random filling after messages is
+    pub fn wipe(&mut self, options: &WipeOptions) -> Result<(), Error> {
+        let max_low_bits = self.get_max_low_bits();
+        let low_bits = options.low_bits.unwrap_or(max_low_bits);
+
+        if low_bits == 0 || low_bits > max_low_bits {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
-                "Input password must be 1 byte chars",
+                format!(
+                    "stegano/wipe : Number of low bits must range from 1 to {} for this picture",
+                    max_low_bits
+                ),
             ));
         }
 
-        // Position indexes
-        let mut x = 0;
-        let mut y = 0;
+        self.get_picture()?;
+        carrier::store_random_from(self, 0, low_bits)
+    }
 
-        let (max_x, max_y) = self.get_dimensions();
+    /// Destroy any potential payload of an incoming picture : samples are dithered with
+    /// +/-1 noise, then their low bits re-randomized as by wipe
+    /// Metadata chunks are stripped, and appended data is lost as soon as the picture is written back
+    pub fn sanitize(&mut self) -> Result<SanitizeReport, Error> {
+        let original = self.get_picture()?.clone();
+        self.get_picture_mut()?.strip_chunks();
+
+        // Palette pictures only get the parity of their sorted palette entries re-randomized
+        if !self.is_indexed() {
+            let mut rng = rand::thread_rng();
+            let picture = self.get_picture_mut()?;
+            let max_value = picture.get_max_value() as i32;
+
+            for index in 0..picture.get_color_samples_count() {
+                let value = picture.get_color_sample(index) as i32 + rng.gen_range(-1, 2);
+                picture.set_color_sample(index, value.clamp(0, max_value) as u16);
+            }
+        }
+        self.wipe(&WipeOptions::default())?;
+
+        let (stripped_chunks, appended_bytes) = fs::read(&self.input_filepath)
+            .map(|bytes| get_png_extras(&bytes))
+            .unwrap_or_default();
+        Ok(SanitizeReport {
+            psnr: get_psnr(&original, self.get_picture()?)?,
+            stripped_chunks,
+            appended_bytes,
+        })
+    }
 
-        let mut bool_triplet_unwrapped;
+    /// Encode encrypted message sample after sample, right after the header
+    /// Remaining samples get random bits, to hide picture alteration from picture analysers
+    fn encode_lsb(&mut self, encrypted_message: &[u8]) -> Result<(), Error> {
+        let low_bits = self.low_bits;
+        let index = carrier::store_from(
+            self,
+            HEADER_SAMPLES,
+            low_bits,
+            &frame_message(encrypted_message),
+        )?;
+
+        carrier::store_random_from(self, index, low_bits)
+    }
 
-        // Vector containing consolidated bits, before conversion in byte, then in char
-        let mut boolean_byte_vector = Vec::<bool>::new();
+    /// Decode encrypted message stored sample after sample, right after the header
+    fn decode_lsb(&self, low_bits: u8) -> Result<Vec<u8>, Error> {
+        carrier::read_framed_from(self, HEADER_SAMPLES, low_bits)
+    }
 
-        // Remains of previous bit vector, belonging to the next character
-        let mut remains_of_previous_bit_vector = Vec::<bool>::new();
+    /// Returns the least significant bit of every color sample, pixel after pixel
+    fn get_all_bits(&self) -> Result<Vec<bool>, Error> {
+        let picture = self.get_picture()?;
 
-        // The decoded character
-        let mut charac;
+        Ok((0..picture.get_color_samples_count())
+            .map(|index| picture.get_color_sample(index) % 2 != 0)
+            .collect())
+    }
 
-        // The message hidden in picture
-        let mut message = Vec::<u8>::new();
+    /// Store given bits in the least significant bit of every color sample, pixel after pixel
+    fn store_all_bits(&mut self, bits: &[bool]) -> Result<(), Error> {
+        for (index, &bit) in bits.iter().enumerate() {
+            self.write_slot(index, 1, &[bit])?;
+        }
 
-        // Keep cursor within picture boundaries
-        while {
-            bool_triplet_unwrapped = self.get_3bits_at(x, y)?;
+        Ok(())
+    }
 
-            if boolean_byte_vector.len() == 6 {
-                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
-                    &mut boolean_byte_vector,
-                    bool_triplet_unwrapped,
-                    0,
-                    1,
-                );
-                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
-                    &mut remains_of_previous_bit_vector,
-                    bool_triplet_unwrapped,
-                    2,
-                    2,
-                );
-            } else if boolean_byte_vector.len() == 7 {
-                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
-                    &mut boolean_byte_vector,
-                    bool_triplet_unwrapped,
-                    0,
-                    0,
-                );
-                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
-                    &mut remains_of_previous_bit_vector,
-                    bool_triplet_unwrapped,
-                    1,
-                    2,
-                );
-            } else if boolean_byte_vector.len() == 8 {
-                // Complete byte formed!
-                // Convert byte to character
-                charac = Byte::from_bool_array(&boolean_byte_vector)?.get_value();
+    /// Returns HILL distortion cost of every color sample, pixel after pixel
+    fn get_all_costs(&self) -> Result<Vec<f64>, Error> {
+        let picture = self.get_picture()?;
+        let (width, height) = picture.dimensions();
+        let (width, height) = (width as usize, height as usize);
+        let channels = self.get_channels();
+
+        // Compute costs channel by channel, then interleave them back
+        let mut costs = vec![0.0; picture.get_color_samples_count()];
+
+        for channel in 0..channels {
+            let plane: Vec<f64> = picture
+                .get_color_plane(channel)
+                .iter()
+                .map(|&v| v as f64)
+                .collect();
+
+            for (index, cost) in hill_costs(&plane, width, height).iter().enumerate() {
+                costs[index * channels + channel] = *cost;
+            }
+        }
 
-                // Check if read character is the ending one
-                if charac == ENDING_CHAR as u8 {
-                    match simple_decrypt(&message, password) {
-                        Ok(str_str) => return Ok(str_str.to_owned()),
-                        Err(err) => {
-                            return Err(Error::new(ErrorKind::InvalidData, err.to_string()))
-                        }
-                    };
-                } else {
-                    // Continue fetching pixels to retrieve the missing characters
-                    message.push(charac);
+        Ok(costs)
+    }
 
-                    boolean_byte_vector = Vec::<bool>::new();
-                    boolean_byte_vector.append(&mut remains_of_previous_bit_vector);
+    /// Returns the order in which adaptive mode walks through color samples
+    /// Header samples are left out
+    fn get_adaptive_order(&self, stream: &mut KeyStream) -> Vec<usize> {
+        let samples_count = self.get_samples_count();
+        let mut order: Vec<usize> = (HEADER_SAMPLES.min(samples_count)..samples_count).collect();
+        stream.shuffle(&mut order);
+        order
+    }
+    /// Encode given message in self image, using syndrome-trellis codes
+    /// Changes are driven towards textured areas, where they are the hardest to detect
+    pub fn encode_adaptive(&mut self, message: &str, password: &str) -> Result<(), Error> {
+        self.encode_with_mode(message, password, EmbeddingMode::Adaptive)
+    }
 
-                    remains_of_previous_bit_vector = Vec::<bool>::new();
+    /// Decodes image encoded with syndrome-trellis codes and return result string
+    pub fn decode_adaptive(&self, password: &str) -> Result<String, Error> {
+        self.decode_with_mode(password, EmbeddingMode::Adaptive)
+    }
 
-                    push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
-                        &mut boolean_byte_vector,
-                        bool_triplet_unwrapped,
-                        0,
-                        2,
-                    );
-                }
-            } else {
-                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
-                    &mut boolean_byte_vector,
+    /// Encode encrypted message using syndrome-trellis codes
+    /// Changes are driven towards textured areas, where they are the hardest to detect
+    fn embed_adaptive(&mut self, encrypted_message: &[u8], password: &str) -> Result<(), Error> {
+        // Message length goes first, then the message itself
+        let length_vector = convert_u32_to_bit_array(encrypted_message.len() as u32);
+        let vector =
+            convert_byte_vec_to_bit_array(&convert_u8_vec_to_byte_array(encrypted_message));
+
+        let mut stream = KeyStream::from_password(password, ADAPTIVE_CONTEXT);
+        let order = self.get_adaptive_order(&mut stream);
+
+        // Check if picture is big enough to store binary vector
+        let width = stc::get_width(order.len().saturating_sub(LENGTH_BITS), vector.len());
+        if width == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/encode_adaptive : Input file not big enough to store message!",
+            ));
+        }
+
+        let mut bits = self.get_all_bits()?;
+        let all_costs = self.get_all_costs()?;
+
+        // Length is stored as is, in the very first components of the walk
+        for (&index, &bit) in order.iter().zip(length_vector.iter()) {
+            bits[index] = bit;
+        }
+
+        // Message is stored as the syndrome of the following ones
+        let walk = &order[LENGTH_BITS..LENGTH_BITS + width * vector.len()];
+        let cover: Vec<bool> = walk.iter().map(|&index| bits[index]).collect();
+        let costs: Vec<f64> = walk.iter().map(|&index| all_costs[index]).collect();
+
+        let submatrix = stc::generate_submatrix(&mut stream, width);
+        let stego = stc::embed(&cover, &costs, &vector, &submatrix)?;
+
+        for (&index, &bit) in walk.iter().zip(stego.iter()) {
+            bits[index] = bit;
+        }
+
+        self.store_all_bits(&bits)
+    }
+
+    /// Decode encrypted message stored with syndrome-trellis codes
+    fn extract_adaptive(&self, password: &str) -> Result<Vec<u8>, Error> {
+        let mut stream = KeyStream::from_password(password, ADAPTIVE_CONTEXT);
+        let order = self.get_adaptive_order(&mut stream);
+        let bits = self.get_all_bits()?;
+
+        if order.len() <= LENGTH_BITS {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/decode_adaptive : Nothing hidden in this file!",
+            ));
+        }
+
+        // Read message length
+        let length_vector: Vec<bool> = order[..LENGTH_BITS].iter().map(|&i| bits[i]).collect();
+        let message_length = convert_bit_array_to_u32(&length_vector)? as usize * 8;
+
+        let width = stc::get_width(order.len() - LENGTH_BITS, message_length);
+        if width == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/decode_adaptive : Nothing hidden in this file!",
+            ));
+        }
+
+        // Then compute the syndrome of the walked components
+        let walk = &order[LENGTH_BITS..LENGTH_BITS + width * message_length];
+        let stego: Vec<bool> = walk.iter().map(|&index| bits[index]).collect();
+
+        let submatrix = stc::generate_submatrix(&mut stream, width);
+        convert_bit_array_to_u8_vec(&stc::extract(&stego, message_length, &submatrix))
+    }
+
+    /// Returns color samples indexes, from the most textured pixel to the smoothest one
+    /// Ranking only relies on bits above the low ones, so it survives encoding
+    fn get_texture_order(&self, low_bits: u8) -> Result<Vec<usize>, Error> {
+        let picture = self.get_picture()?;
+        let (width, height) = picture.dimensions();
+        let (width, height) = (width as usize, height as usize);
+        let channels = self.get_channels();
+
+        // Sum channels scores
+        let mut scores = vec![0; width * height];
+
+        for channel in 0..channels {
+            for (score, channel_score) in scores.iter_mut().zip(texture_scores(
+                &picture.get_color_plane(channel),
+                width,
+                height,
+                low_bits,
+            )) {
+                *score += channel_score;
+            }
+        }
+
+        // Highest scores first, raster order among equal scores, header pixels excluded
+        let mut order: Vec<usize> =
+            (self.get_header_pixels_count().min(width * height)..width * height).collect();
+        order.sort_by(|a, b| scores[*b].cmp(&scores[*a]));
+
+        Ok(order
+            .iter()
+            .flat_map(|pixel| (0..channels).map(move |channel| pixel * channels + channel))
+            .collect())
+    }
+
+    /// Encode given message in self image, only within its most textured pixels
+    /// Smooth areas, where changes are the most visible, are left untouched
+    pub fn encode_textured(&mut self, message: &str, password: &str) -> Result<(), Error> {
+        self.encode_with_mode(message, password, EmbeddingMode::Textured)
+    }
+
+    /// Decodes image encoded within its most textured pixels and return result string
+    pub fn decode_textured(&self, password: &str) -> Result<String, Error> {
+        self.decode_with_mode(password, EmbeddingMode::Textured)
+    }
+
+    /// Encode encrypted message only within the most textured pixels
+    /// Smooth areas, where changes are the most visible, are left untouched
+    fn embed_textured(&mut self, encrypted_message: &[u8]) -> Result<(), Error> {
+        let vector = frame_message(encrypted_message);
+        let low_bits = self.low_bits;
+        let mut order = self.get_texture_order(low_bits)?;
+
+        // Only the top-N textured pixels may be used
+        let channels = self.get_channels();
+        let pixels = order.len() / channels * self.texture_percentage as usize / 100;
+        order.truncate(pixels * channels);
+
+        // Check if the top-N textured pixels are enough to store binary vector
+        if order.len() * (low_bits as usize) < vector.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "stegano/encode_textured : The {} % most textured pixels are not enough to store message!",
+                    self.texture_percentage
+                ),
+            ));
+        }
+
+        // Fill top-N textured samples, low_bits by low_bits
+        for (&index, chunk) in order.iter().zip(vector.chunks(low_bits as usize)) {
+            self.write_slot(index, low_bits, chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Decode encrypted message stored within the most textured pixels
+    fn extract_textured(&self, low_bits: u8) -> Result<Vec<u8>, Error> {
+        read_framed_message(
+            self.get_texture_order(low_bits)?
+                .into_iter()
+                .map(|index| self.read_slot(index, low_bits)),
+        )
+    }
+
+    /// Returns sample pairs used by pixel-value differencing, as sample indexes
+    /// Adjacent pixels are paired, then each color channel of the pair holds bits
+    fn get_pvd_pairs(&self) -> Vec<(usize, usize)> {
+        let channels = self.get_channels();
+        let header_pixels = self.get_header_pixels_count();
+        let pixels_count = self.get_samples_count() / channels;
+
+        (0..pixels_count.saturating_sub(header_pixels) / 2)
+            .flat_map(|pair| {
+                let first = (header_pixels + 2 * pair) * channels;
+                (0..channels).map(move |channel| (first + channel, first + channels + channel))
+            })
+            .collect()
+    }
+
+    /// Encode encrypted message in the differences between adjacent pixels, channel by channel
+    fn encode_pvd(&mut self, encrypted_message: &[u8]) -> Result<(), Error> {
+        let vector = frame_message(encrypted_message);
+        let pairs = self.get_pvd_pairs();
+        let picture = self.get_picture_mut()?;
+        let max = picture.get_max_value();
+
+        // Check if picture is big enough to store binary vector
+        let capacity: usize = pairs
+            .iter()
+            .filter_map(|&(first, second)| {
+                pvd::get_capacity(
+                    picture.get_color_sample(first),
+                    picture.get_color_sample(second),
+                    max,
+                )
+            })
+            .sum();
+
+        if capacity < vector.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/encode_pvd : Input file not big enough to store message!",
+            ));
+        }
+
+        let mut cursor = 0;
+
+        for (first, second) in pairs {
+            if cursor >= vector.len() {
+                break;
+            }
+
+            if let Some((new_first, new_second, used)) = pvd::embed_pair(
+                picture.get_color_sample(first),
+                picture.get_color_sample(second),
+                max,
+                &vector[cursor..],
+            ) {
+                picture.set_color_sample(first, new_first);
+                picture.set_color_sample(second, new_second);
+                cursor += used;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decode encrypted message stored in the differences between adjacent pixels
+    fn decode_pvd(&self) -> Result<Vec<u8>, Error> {
+        let picture = self.get_picture()?;
+        let max = picture.get_max_value();
+
+        read_framed_message(self.get_pvd_pairs().into_iter().map(|(first, second)| {
+            Ok(pvd::extract_pair(
+                picture.get_color_sample(first),
+                picture.get_color_sample(second),
+                max,
+            )
+            .unwrap_or_default())
+        }))
+    }
+
+    /// Returns number of samples holding histogram shifting parameters, right after the header
+    /// Peak and empty bins of each channel are stored, using the picture bit depth
+    fn get_parameters_samples_count(&self) -> usize {
+        match self.image {
+            Ok(ref image) => self.get_channels() * 2 * image.get_bit_depth() as usize,
+            Err(_) => 0,
+        }
+    }
+
+    /// Returns indexes of the samples used by histogram shifting
+    fn get_reversible_samples(&self) -> std::ops::Range<usize> {
+        let samples_count = self.get_samples_count();
+        let reserved_samples = HEADER_SAMPLES + self.get_parameters_samples_count();
+        reserved_samples.min(samples_count)..samples_count
+    }
+
+    /// Returns least significant bits of header and parameters samples
+    fn get_reserved_bits(&self) -> Result<Vec<bool>, Error> {
+        let mut bits = Vec::<bool>::new();
+        for index in 0..self.get_reversible_samples().start {
+            bits.append(&mut self.read_slot(index, 1)?);
+        }
+        Ok(bits)
+    }
+
+    /// Read histogram shifting parameters of each color channel
+    fn get_reversible_parameters(&self) -> Result<Vec<Option<Bins>>, Error> {
+        let bit_depth = self.get_picture()?.get_bit_depth() as usize;
+        let mut bits = Vec::<bool>::new();
+        for index in HEADER_SAMPLES..self.get_reversible_samples().start {
+            bits.append(&mut self.read_slot(index, 1)?);
+        }
+
+        if bits.len() < self.get_parameters_samples_count() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/get_reversible_parameters : Nothing hidden in this file!",
+            ));
+        }
+
+        let values: Vec<u16> = bits
+            .chunks(bit_depth)
+            .map(|chunk| chunk.iter().fold(0, |acc, &bit| (acc << 1) | bit as u16))
+            .collect();
+
+        // Equal peak and zero bins mean the channel is not used
+        Ok(values
+            .chunks(2)
+            .map(|bins| {
+                if bins[0] != bins[1] {
+                    Some(Bins {
+                        peak: bins[0],
+                        zero: bins[1],
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Encode encrypted message by histogram shifting, channel by channel
+    /// Original least significant bits of the header are hidden along with the message
+    fn encode_reversible(&mut self, encrypted_message: &[u8]) -> Result<(), Error> {
+        let samples = self.get_reversible_samples();
+        let channels = self.get_channels();
+
+        if samples.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/encode_reversible : Input file not big enough to store message!",
+            ));
+        }
+
+        let mut vector = self.get_reserved_bits()?;
+        vector.append(&mut frame_message(encrypted_message));
+
+        let picture = self.get_picture_mut()?;
+        let (max, bit_depth) = (picture.get_max_value(), picture.get_bit_depth());
+
+        let parameters: Vec<Option<Bins>> = (0..channels)
+            .map(|channel| {
+                Bins::from_values(
+                    samples
+                        .clone()
+                        .filter(|index| index % channels == channel)
+                        .map(|index| picture.get_color_sample(index)),
+                    max,
+                )
+            })
+            .collect();
+
+        // Check if picture is big enough to store binary vector
+        let capacity = samples
+            .clone()
+            .filter(|&index| {
+                parameters[index % channels].map(|bins| bins.peak)
+                    == Some(picture.get_color_sample(index))
+            })
+            .count();
+
+        if capacity < vector.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/encode_reversible : Input file not big enough to store message!",
+            ));
+        }
+
+        let mut cursor = 0;
+
+        for index in samples.clone() {
+            if let Some(bins) = parameters[index % channels] {
+                let bit = *vector.get(cursor).unwrap_or(&false);
+                let (value, consumed) = bins.embed(picture.get_color_sample(index), bit);
+                picture.set_color_sample(index, value);
+                if consumed {
+                    cursor += 1;
+                }
+            }
+        }
+
+        // Parameters are written once histogram is shifted
+        let parameters_vector: Vec<bool> = parameters
+            .iter()
+            .flat_map(|parameter| match parameter {
+                Some(bins) => vec![bins.peak, bins.zero],
+                None => vec![0, 0],
+            })
+            .flat_map(|value| {
+                (0..bit_depth)
+                    .rev()
+                    .map(move |shift| (value >> shift) & 1 == 1)
+            })
+            .collect();
+
+        for (index, &bit) in (HEADER_SAMPLES..samples.start).zip(parameters_vector.iter()) {
+            self.write_slot(index, 1, &[bit])?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns every bit hidden by histogram shifting
+    fn get_reversible_bits(&self) -> Result<Vec<bool>, Error> {
+        let parameters = self.get_reversible_parameters()?;
+        let picture = self.get_picture()?;
+        let channels = self.get_channels();
+
+        Ok(self
+            .get_reversible_samples()
+            .filter_map(|index| {
+                parameters[index % channels]
+                    .and_then(|bins| bins.extract(picture.get_color_sample(index)))
+            })
+            .collect())
+    }
+
+    /// Decode encrypted message stored by histogram shifting
+    fn decode_reversible(&self) -> Result<Vec<u8>, Error> {
+        let reserved_bits_count = self.get_reversible_samples().start;
+        let bits = self.get_reversible_bits()?;
+
+        if bits.len() < reserved_bits_count {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/decode_reversible : Nothing hidden in this file!",
+            ));
+        }
+
+        read_framed_message(std::iter::once(Ok(bits[reserved_bits_count..].to_vec())))
+    }
+
+    /// Decodes image encoded in reversible mode, return result string
+    /// and restore self image, bit for bit, as it was before encoding
+    pub fn extract_and_restore(&mut self, password: &str) -> Result<String, Error> {
+        self.extract_and_restore_sealed(&PasswordSealing::new(password))
+    }
+
+    /// Decodes image encoded in reversible mode with given sealing method, return result string
+    /// and restore self image as it was before encoding
+    pub fn extract_and_restore_sealed(&mut self, sealing: &dyn Sealing) -> Result<String, Error> {
+        let password = &sealing.get_carrier_key();
+        if !is_one_byte_chars_message(password) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Input password must be 1 byte chars",
+            ));
+        }
+
+        if self.read_header(password)?.map(|(mode, _)| mode) != Some(EmbeddingMode::Reversible) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/extract_and_restore : No reversible message hidden in this file!",
+            ));
+        }
+
+        let message = sealing
+            .open(&self.decode_reversible()?)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+
+        // Shift histogram back
+        let parameters = self.get_reversible_parameters()?;
+        let samples = self.get_reversible_samples();
+        let reserved_bits = self.get_reversible_bits()?[..samples.start].to_vec();
+        let channels = self.get_channels();
+        let picture = self.get_picture_mut()?;
+
+        for index in samples {
+            if let Some(bins) = parameters[index % channels] {
+                let value = bins.restore(picture.get_color_sample(index));
+                picture.set_color_sample(index, value);
+            }
+        }
+
+        // Then put back header and parameters original least significant bits
+        for (index, &bit) in reserved_bits.iter().enumerate() {
+            self.write_slot(index, 1, &[bit])?;
+        }
+
+        Ok(message)
+    }
+
+    /// Decodes image encoded in reversible mode, write result file
+    /// and restore self image as it was before encoding
+    pub fn extract_and_restore_and_write(
+        &mut self,
+        filepath: &str,
+        password: &str,
+    ) -> Result<(), Error> {
+        let decoded_string = &self.extract_and_restore(password)?;
+        let mut output_file = File::create(filepath)?;
+        output_file.write_all(decoded_string.as_bytes())
+    }
+
+    /// Decodes image and write result file
+    pub fn decode_and_write(&self, filepath: &str, password: &str) -> Result<(), Error> {
+        let decoded_string = &self.decode(password)?;
+        let mut output_file = File::create(filepath)?;
+        output_file.write_all(decoded_string.as_bytes())
+    }
+
+    /// Decodes image and return result string
+    /// Embedding mode is read from the header, pictures without header are decoded the legacy way
+    pub fn decode(&self, password: &str) -> Result<String, Error> {
+        self.decode_with_registry(password, &StrategyRegistry::default())
+    }
+
+    /// Decodes image encoded in given mode and return result string
+    fn decode_with_mode(&self, password: &str, mode: EmbeddingMode) -> Result<String, Error> {
+        if self.read_header(password)?.map(|(found, _)| found) != Some(mode) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "stegano/decode : No message hidden in {:?} mode in this file!",
+                    mode
+                ),
+            ));
+        }
+
+        self.decode(password)
+    }
+
+    /// Decodes image and return result string, embedding strategies being looked for in given registry
+    pub fn decode_with_registry(
+        &self,
+        password: &str,
+        registry: &StrategyRegistry,
+    ) -> Result<String, Error> {
+        self.decode_sealed_with_registry(&PasswordSealing::new(password), registry)
+    }
+
+    /// Decodes image sealed with given method and return result string
+    pub fn decode_sealed(&self, sealing: &dyn Sealing) -> Result<String, Error> {
+        self.decode_sealed_with_registry(sealing, &StrategyRegistry::default())
+    }
+
+    /// Decodes image sealed with given method and return result string,
+    /// embedding strategies being looked for in given registry
+    pub fn decode_sealed_with_registry(
+        &self,
+        sealing: &dyn Sealing,
+        registry: &StrategyRegistry,
+    ) -> Result<String, Error> {
+        let password = &sealing.get_carrier_key();
+        if !is_one_byte_chars_message(password) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Input password must be 1 byte chars",
+            ));
+        }
+
+        // Strategy header is read first, its mode identifier being unknown to the regular header
+        if let Some((id, parameter)) = self.read_strategy_header(password)? {
+            let encrypted_message = registry.from_header(id, parameter)?.extract(
+                self,
+                STRATEGY_HEADER_SAMPLES,
+                password,
+            )?;
+
+            return sealing
+                .open(&encrypted_message)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()));
+        }
+
+        let encrypted_message = match self.read_header(password)? {
+            Some((EmbeddingMode::Lsb, low_bits)) => self.decode_lsb(low_bits)?,
+            Some((EmbeddingMode::Adaptive, _)) => self.extract_adaptive(password)?,
+            Some((EmbeddingMode::Textured, low_bits)) => self.extract_textured(low_bits)?,
+            Some((EmbeddingMode::Pvd, _)) => self.decode_pvd()?,
+            Some((EmbeddingMode::Reversible, _)) => self.decode_reversible()?,
+            Some((EmbeddingMode::Palette, _)) => self.decode_lsb(1)?,
+            None => match self.decode_deniable(password)? {
+                Some(encrypted_message) => encrypted_message,
+                None => match container::find(self, password)? {
+                    Some(encrypted_message) => encrypted_message,
+                    None => return self.decode_legacy(password),
+                },
+            },
+        };
+
+        sealing
+            .open(&encrypted_message)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+
+    /// Returns least significant bits of the RGB components of pixel at given position
+    /// Only used by legacy decoding, which handled RGB pictures only
+    fn get_3bits_at(&self, x: u32, y: u32) -> Result<[bool; 3], Error> {
+        let (max_x, _) = self.get_dimensions();
+        let index = (y * max_x + x) as usize * 3;
+
+        Ok([
+            self.read_slot(index, 1)?[0],
+            self.read_slot(index + 1, 1)?[0],
+            self.read_slot(index + 2, 1)?[0],
+        ])
+    }
+
+    /// Returns given bit plane of given color channel, as a black and white picture
+    /// Bits are read the way get_3bits_at does, palette pictures only having plane 0
+    pub fn get_bit_plane(&self, plane: u8, channel: usize) -> Result<Picture, Error> {
+        let max_plane = match self.sorted_palette {
+            Some(_) => 0,
+            None => self.get_picture()?.get_bit_depth() - 1,
+        };
+        if plane > max_plane || channel >= self.get_channels() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "stegano/get_bit_plane : Planes range from 0 to {}, channels from 0 to {}",
+                    max_plane,
+                    self.get_channels() - 1
+                ),
+            ));
+        }
+
+        let (width, height) = self.get_dimensions();
+        let samples = (0..(width * height) as usize)
+            .map(|pixel| {
+                let bits = self.read_slot(pixel * self.get_channels() + channel, plane + 1)?;
+                Ok(if bits[0] { u8::MAX as u16 } else { 0 })
+            })
+            .collect::<Result<Vec<u16>, Error>>()?;
+
+        Picture::new(width, height, ColorType::Gray, 8, samples)
+    }
+
+    /// Returns amplified difference between this picture and another one, sample by sample
+    /// Palette pictures are compared index by index
+    pub fn get_difference(&self, other: &DotMatrix, amplification: u32) -> Result<Picture, Error> {
+        if self.get_dimensions() != other.get_dimensions()
+            || self.get_channels() != other.get_channels()
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/get_difference : Pictures dimensions differ!",
+            ));
+        }
+
+        let samples = (0..self.get_samples_count())
+            .map(|index| {
+                let difference = self
+                    .read_slot_value(index)?
+                    .abs_diff(other.read_slot_value(index)?);
+                Ok(difference.saturating_mul(amplification).min(u8::MAX as u32) as u16)
+            })
+            .collect::<Result<Vec<u16>, Error>>()?;
+
+        let color_type = match self.get_channels() {
+            3 => ColorType::Rgb,
+            _ => ColorType::Gray,
+        };
+        let (width, height) = self.get_dimensions();
+        Picture::new(width, height, color_type, 8, samples)
+    }
+
+    /// Decodes image written before headers were introduced : message ends with ENDING_CHAR
+    fn decode_legacy(&self, password: &str) -> Result<String, Error> {
+        if self.get_channels() != 3 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/decode : Nothing hidden in this file!",
+            ));
+        }
+
+        // Position indexes
+        let mut x = 0;
+        let mut y = 0;
+
+        let (max_x, max_y) = self.get_dimensions();
+
+        let mut bool_triplet_unwrapped;
+
+        // Vector containing consolidated bits, before conversion in byte, then in char
+        let mut boolean_byte_vector = Vec::<bool>::new();
+
+        // Remains of previous bit vector, belonging to the next character
+        let mut remains_of_previous_bit_vector = Vec::<bool>::new();
+
+        // The decoded character
+        let mut charac;
+
+        // The message hidden in picture
+        let mut message = Vec::<u8>::new();
+
+        // Keep cursor within picture boundaries
+        while {
+            bool_triplet_unwrapped = self.get_3bits_at(x, y)?;
+
+            if boolean_byte_vector.len() == 6 {
+                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
+                    &mut boolean_byte_vector,
+                    bool_triplet_unwrapped,
+                    0,
+                    1,
+                );
+                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
+                    &mut remains_of_previous_bit_vector,
+                    bool_triplet_unwrapped,
+                    2,
+                    2,
+                );
+            } else if boolean_byte_vector.len() == 7 {
+                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
+                    &mut boolean_byte_vector,
+                    bool_triplet_unwrapped,
+                    0,
+                    0,
+                );
+                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
+                    &mut remains_of_previous_bit_vector,
+                    bool_triplet_unwrapped,
+                    1,
+                    2,
+                );
+            } else if boolean_byte_vector.len() == 8 {
+                // Complete byte formed!
+                // Convert byte to character
+                charac = Byte::from_bool_array(&boolean_byte_vector)?.get_value();
+
+                // Check if read character is the ending one
+                // Legacy messages are whole AES blocks of 1 byte chars, anything else
+                // being random bits, such as the filling of deniable sets
+                if charac == ENDING_CHAR as u8 {
+                    return match simple_decrypt(&message, password) {
+                        Ok(str_str)
+                            if !message.is_empty()
+                                && message.len().is_multiple_of(16)
+                                && is_one_byte_chars_message(&str_str) =>
+                        {
+                            Ok(str_str)
+                        }
+                        Ok(_) => Err(Error::new(
+                            ErrorKind::InvalidData,
+                            "stegano/decode : Nothing hidden in this file!",
+                        )),
+                        Err(err) => Err(Error::new(ErrorKind::InvalidData, err.to_string())),
+                    };
+                } else {
+                    // Continue fetching pixels to retrieve the missing characters
+                    message.push(charac);
+
+                    boolean_byte_vector = Vec::<bool>::new();
+                    boolean_byte_vector.append(&mut remains_of_previous_bit_vector);
+
+                    remains_of_previous_bit_vector = Vec::<bool>::new();
+
+                    push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
+                        &mut boolean_byte_vector,
+                        bool_triplet_unwrapped,
+                        0,
+                        2,
+                    );
+                }
+            } else {
+                push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
+                    &mut boolean_byte_vector,
                     bool_triplet_unwrapped,
                     0,
                     2,
                 );
             }
 
-            move_cursor_to_next_pixel(&mut x, &mut y, (max_x, max_y)).is_ok()
-        } {}
+            move_cursor_to_next_pixel(&mut x, &mut y, (max_x, max_y)).is_ok()
+        } {}
+
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            "stegano/decode : Nothing hidden in this file!",
+        )) // Ending character never reached, return None
+    }
+}
+
+/// Returns luminance-sorted palette of palette pictures, None for other pictures
+fn get_sorted_palette(image: &Result<Picture, Error>) -> Option<SortedPalette> {
+    match image {
+        Ok(picture) if picture.get_color_type() == ColorType::Indexed => {
+            SortedPalette::new(picture.get_palette())
+        }
+        _ => None,
+    }
+}
+
+/// Check that message and password can be encoded
+pub fn check_message_and_password(message: &str, password: &str) -> Result<(), Error> {
+    if !is_one_byte_chars_message(message) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Input message must be 1 byte chars",
+        ));
+    }
+
+    check_password(password)
+}
+
+/// Move cursor to next pixel (And automatically take bounds into account)
+fn move_cursor_to_next_pixel(
+    x: &mut u32,
+    y: &mut u32,
+    dimensions: (u32, u32),
+) -> Result<(), Error> {
+    if *x + 1 == dimensions.0 {
+        if *y + 1 == dimensions.1 {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "No more pixels available in image!",
+            ))
+        } else {
+            *x = 0;
+            *y += 1;
+            Ok(())
+        }
+    } else {
+        *x += 1;
+        Ok(())
+    }
+}
+
+/// Write bits from vector to dot matrix
+fn push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
+    vector: &mut Vec<bool>,
+    bool_triplet: [bool; 3],
+    n: usize,
+    m: usize,
+) {
+    for i in n..=m {
+        vector.push(bool_triplet[i]);
+    }
+}
+
+/// Trait implementation : color samples are the slots, alpha excluded
+impl Carrier for DotMatrix {
+    /// Returns number of color samples
+    fn get_slots_count(&self) -> usize {
+        self.get_samples_count()
+    }
+
+    /// Returns the given number of low bits of sample at given index, most significant first
+    /// Palette pictures hold a single bit per pixel, in the parity of the sorted palette entry
+    fn read_slot(&self, index: usize, count: u8) -> Result<Vec<bool>, Error> {
+        let value = self.get_picture()?.get_color_sample(index);
+
+        if let Some(ref palette) = self.sorted_palette {
+            return Ok(vec![palette.get_bit(value as u8)]);
+        }
+
+        Ok((0..count)
+            .rev()
+            .map(|shift| (value >> shift) & 1 == 1)
+            .collect())
+    }
+
+    /// Store bits within the given number of low bits of sample at given index
+    /// If the end of the message is reached, bits may be fewer than count : store what is storable
+    fn write_slot(&mut self, index: usize, count: u8, bits: &[bool]) -> Result<(), Error> {
+        if let (Ok(ref mut picture), Some(ref palette)) = (&mut self.image, &self.sorted_palette) {
+            let value = picture.get_color_sample(index) as u8;
+            if let Some(&bit) = bits.first() {
+                picture.set_color_sample(index, palette.store_bit(value, bit) as u16);
+            }
+            return Ok(());
+        }
+
+        let picture = self.get_picture_mut()?;
+        let mut value = picture.get_color_sample(index);
+
+        for (position, &bit) in bits.iter().take(count as usize).enumerate() {
+            let shift = count as usize - 1 - position;
+            value = (value & !(1 << shift)) | ((bit as u16) << shift);
+        }
+
+        picture.set_color_sample(index, value);
+        Ok(())
+    }
+
+    /// Write picture into target file
+    fn save(&self, filepath: &str) -> Result<(), Error> {
+        self.write_to_file(filepath)
+    }
+
+    /// Returns highest sample value, None for palette pictures
+    fn get_slot_max_value(&self) -> Option<u32> {
+        match self.image {
+            Ok(ref image) if self.sorted_palette.is_none() && !self.is_indexed() => {
+                Some(image.get_max_value() as u32)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns value of sample at given index
+    fn read_slot_value(&self, index: usize) -> Result<u32, Error> {
+        Ok(self.get_picture()?.get_color_sample(index) as u32)
+    }
+
+    /// Replace value of sample at given index
+    fn write_slot_value(&mut self, index: usize, value: u32) -> Result<(), Error> {
+        let picture = self.get_picture_mut()?;
+        if value > picture.get_max_value() as u32 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/write_slot_value : Value out of sample range!",
+            ));
+        }
+
+        picture.set_color_sample(index, value as u16);
+        Ok(())
+    }
+}
+
+/// Trait implementation
+impl Clone for DotMatrix {
+    fn clone(&self) -> DotMatrix {
+        DotMatrix::new(self.get_input_filepath().as_str())
+    }
+}
+
+/// Trait implementation
+impl fmt::Display for DotMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Filepath: {} \n Contents: {} \n Dimensions : {:?}",
+            self.get_input_filepath(),
+            if let Ok(_) = self.image {
+                "Yes"
+            } else {
+                "Invalid content"
+            },
+            self.get_dimensions()
+        )
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::sealing::{generate_keypair, to_hex, RecipientSealing};
+    use super::*;
+    use std::{
+        env,
+        fs::{self, File},
+        io::{Read, Write},
+        process,
+    };
+
+    /// Path of the reference picture used as input by tests
+    pub const TEST_FILEPATH: &str = "test_files/test.png";
+
+    /// Returns a path in temporary directory, for files produced by tests
+    pub fn get_output_filepath(filename: &str) -> String {
+        env::temp_dir()
+            .join(format!("stegano_{}", filename))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    // TODO : unable to store special characters
+    // TODO : errors triggering in a very useless order
+    fn test_global() {
+        let output_filepath = get_output_filepath("test2.png");
+        let _ = fs::remove_file(&output_filepath);
+
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+
+        image
+            .encode("Hello how is the weather today", "Password")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global: {}", err);
+                process::exit(1);
+            });
+
+        image.write_to_file(&output_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in test_global: {}", err);
+            process::exit(1);
+        });
+
+        let image2 = DotMatrix::new(&output_filepath);
+        let res = image2.decode("Password").unwrap_or_else(|err| {
+            eprintln!("Error in test_global: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(res, "Hello how is the weather today".to_string());
+    }
+
+    #[test]
+    fn test_global_with_file_encoding() {
+        let output_filepath = get_output_filepath("test_global.png");
+        let input_text_filepath = get_output_filepath("test.txt");
+        let output_text_filepath = get_output_filepath("test2.txt");
+
+        let _ = fs::remove_file(&output_filepath);
+        let _ = fs::remove_file(&input_text_filepath);
+        let _ = fs::remove_file(&output_text_filepath);
+
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+
+        let mut file = File::create(&input_text_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in test_global: {}", err);
+            process::exit(1);
+        });
+        file.write_all(b"Test message within file")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global: {}", err);
+                process::exit(1);
+            });
+
+        image
+            .encode_file(&input_text_filepath, "Password")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global: {}", err);
+                process::exit(1);
+            });
+
+        image.write_to_file(&output_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in test_global: {}", err);
+            process::exit(1);
+        });
+
+        let image2 = DotMatrix::new(&output_filepath);
+        image2
+            .decode_and_write(&output_text_filepath, "Password")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global: {}", err);
+                process::exit(1);
+            });
+
+        let mut file = File::open(&output_text_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in test_global: {}", err);
+            process::exit(1);
+        });
+
+        let mut result_string = String::new();
+        let _ = file
+            .read_to_string(&mut result_string)
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global: {}", err);
+                process::exit(1);
+            });
+
+        assert_eq!("Test message within file".to_string(), result_string);
+    }
+
+    /// Encode, write, read back and decode message using given mode
+    fn check_mode(mode: EmbeddingMode, filename: &str) -> DotMatrix {
+        let output_filepath = get_output_filepath(filename);
+        let _ = fs::remove_file(&output_filepath);
+
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+
+        image
+            .encode_with_mode("Hello how is the weather today", "Password", mode)
+            .unwrap_or_else(|err| {
+                eprintln!("Error in check_mode: {}", err);
+                process::exit(1);
+            });
+
+        image.write_to_file(&output_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in check_mode: {}", err);
+            process::exit(1);
+        });
+
+        let image2 = DotMatrix::new(&output_filepath);
+        let res = image2.decode("Password").unwrap_or_else(|err| {
+            eprintln!("Error in check_mode: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(res, "Hello how is the weather today".to_string());
+        assert!(image2.decode("Wrong password").is_err());
+
+        image2
+    }
+
+    #[test]
+    fn test_global_adaptive() {
+        let output_filepath = get_output_filepath("test_adaptive.png");
+        let _ = fs::remove_file(&output_filepath);
+
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+
+        image
+            .encode_adaptive("Hello how is the weather today", "Password")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global_adaptive: {}", err);
+                process::exit(1);
+            });
+
+        image.write_to_file(&output_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in test_global_adaptive: {}", err);
+            process::exit(1);
+        });
 
-        Err(Error::new(
-            ErrorKind::InvalidData,
-            "stegano/decode : Nothing hidden in this file!",
-        )) // Ending character never reached, return None
+        let image2 = DotMatrix::new(&output_filepath);
+        let res = image2.decode_adaptive("Password").unwrap_or_else(|err| {
+            eprintln!("Error in test_global_adaptive: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(res, "Hello how is the weather today".to_string());
+        assert!(image2.decode_adaptive("Wrong password").is_err());
     }
-}
 
-/// Append ending character to message
-fn add_ending_char(message: &mut Vec<u8>) {
-    message.push(ENDING_CHAR as u8);
-}
+    #[test]
+    fn test_global_textured() {
+        let output_filepath = get_output_filepath("test_textured.png");
+        let _ = fs::remove_file(&output_filepath);
 
-/// Move cursor to next pixel (And automatically take bounds into account)
-fn move_cursor_to_next_pixel(
-    x: &mut u32,
-    y: &mut u32,
-    dimensions: (u32, u32),
-) -> Result<(), Error> {
-    if *x + 1 == dimensions.0 {
-        if *y + 1 == dimensions.1 {
-            Err(Error::new(
-                ErrorKind::InvalidData,
-                "No more pixels available in image!",
-            ))
-        } else {
-            *x = 0;
-            *y += 1;
-            Ok(())
-        }
-    } else {
-        *x += 1;
-        Ok(())
-    }
-}
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+        let order = image.get_texture_order(1).unwrap_or_else(|err| {
+            eprintln!("Error in test_global_textured: {}", err);
+            process::exit(1);
+        });
 
-/// Write bits from vector to dot matrix
-fn push_bits_in_vector_from_bool_triplet_from_n_up_to_m(
-    vector: &mut Vec<bool>,
-    bool_triplet: [bool; 3],
-    n: usize,
-    m: usize,
-) {
-    for i in n..=m {
-        vector.push(bool_triplet[i]);
-    }
-}
+        image
+            .encode_textured("Hello how is the weather today", "Password")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global_textured: {}", err);
+                process::exit(1);
+            });
 
-/// Trait implementation
-impl Clone for DotMatrix {
-    fn clone(&self) -> DotMatrix {
-        DotMatrix::new(self.get_input_filepath().as_str())
-    }
-}
+        image.write_to_file(&output_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in test_global_textured: {}", err);
+            process::exit(1);
+        });
 
-/// Trait implementation
-impl fmt::Display for DotMatrix {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "Filepath: {} \n Contents: {} \n Dimensions : {:?}",
-            self.get_input_filepath(),
-            if let Ok(_) = self.image { "Yes" } else { "Invalid content" },
-            self.get_dimensions()
-        )
+        let image2 = DotMatrix::new(&output_filepath);
+
+        // Ranking must not be altered by encoding
+        let order2 = image2.get_texture_order(1).unwrap_or_else(|err| {
+            eprintln!("Error in test_global_textured: {}", err);
+            process::exit(1);
+        });
+        assert!(order2 == order);
+
+        let res = image2.decode_textured("Password").unwrap_or_else(|err| {
+            eprintln!("Error in test_global_textured: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(res, "Hello how is the weather today".to_string());
+
+        // Messages too long for the top-N textured pixels are refused
+        let mut image3 = DotMatrix::new(TEST_FILEPATH);
+        assert!(image3.set_texture_percentage(0).is_err());
+        assert!(image3.set_texture_percentage(1).is_ok());
+        assert!(image3
+            .encode_textured(&"Hello ".repeat(1000), "Password")
+            .is_err());
     }
-}
 
-// Tests
-#[cfg(test)]
-pub mod tests {
-    use super::*;
-    use std::{
-        fs::{self, File},
-        io::{Read, Write},
-        process,
-    };
+    #[test]
+    fn test_global_pvd() {
+        check_mode(EmbeddingMode::Pvd, "test_pvd.png");
+    }
 
     #[test]
-    // TODO : unable to store special characters
-    // TODO : errors triggering in a very useless order
-    fn test_global() {
-        let _ = fs::remove_file("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test2.png");
+    fn test_global_reversible() {
+        let mut image = check_mode(EmbeddingMode::Reversible, "test_reversible.png");
+
+        let res = image.extract_and_restore("Password").unwrap_or_else(|err| {
+            eprintln!("Error in test_global_reversible: {}", err);
+            process::exit(1);
+        });
+        assert_eq!(res, "Hello how is the weather today".to_string());
 
-        // TODO : relative path
-        let mut image =
-            DotMatrix::new("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test.png");
+        // Restored picture must be the exact original one
+        let original = DotMatrix::new(TEST_FILEPATH);
+        let raw = |matrix: &DotMatrix| match matrix.image {
+            Ok(ref image) => image.to_rgba8(),
+            Err(_) => Vec::new(),
+        };
+
+        assert!(raw(&image) == raw(&original));
+        assert!(DotMatrix::new(TEST_FILEPATH)
+            .extract_and_restore("Password")
+            .is_err());
+    }
 
-        image
-            .encode("Hello how is the weather today", "Password")
+    /// Write a grayscale or 16 bits version of the reference picture, returning its path
+    fn write_converted_picture(color_type: ColorType, bit_depth: u8, filename: &str) -> String {
+        let output_filepath = get_output_filepath(filename);
+        let reference = Picture::open(TEST_FILEPATH).unwrap_or_else(|err| {
+            eprintln!("Error in write_converted_picture: {}", err);
+            process::exit(1);
+        });
+
+        let (width, height) = reference.dimensions();
+        let channels = color_type.get_color_channels();
+        let planes: Vec<Vec<u16>> = (0..channels)
+            .map(|channel| reference.get_color_plane(channel))
+            .collect();
+
+        // 16 bits samples get noisy low bits, as a real 16 bits capture would
+        let samples: Vec<u16> = (0..(width * height) as usize * channels)
+            .map(|index| {
+                let value = planes[index % channels][index / channels];
+                if bit_depth == 16 {
+                    value * 256 + (index * 97 % 256) as u16
+                } else {
+                    value
+                }
+            })
+            .collect();
+
+        Picture::new(width, height, color_type, bit_depth, samples)
+            .and_then(|picture| picture.save(&output_filepath))
             .unwrap_or_else(|err| {
-                eprintln!("Error in test_global: {}", err);
+                eprintln!("Error in write_converted_picture: {}", err);
                 process::exit(1);
             });
 
+        output_filepath
+    }
+
+    /// Encode, write, read back and decode message, checking picture format is kept
+    fn check_format(input_filepath: &str, mode: EmbeddingMode, low_bits: u8, filename: &str) {
+        let output_filepath = get_output_filepath(filename);
+        let _ = fs::remove_file(&output_filepath);
+
+        let mut image = DotMatrix::new(input_filepath);
+        image.set_low_bits(low_bits).unwrap_or_else(|err| {
+            eprintln!("Error in check_format: {}", err);
+            process::exit(1);
+        });
+
         image
-            .write_to_file("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test2.png")
+            .encode_with_mode("Hello how is the weather today", "Password", mode)
+            .and_then(|_| image.write_to_file(&output_filepath))
             .unwrap_or_else(|err| {
-                eprintln!("Error in test_global: {}", err);
+                eprintln!("Error in check_format: {}", err);
                 process::exit(1);
             });
 
-        let image2 =
-            DotMatrix::new("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test2.png");
-        let res = image2.decode("Password").unwrap_or_else(|err| {
-            eprintln!("Error in test_global: {}", err);
+        let input = Picture::open(input_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in check_format: {}", err);
             process::exit(1);
         });
+        let output = Picture::open(&output_filepath).unwrap_or_else(|err| {
+            eprintln!("Error in check_format: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(output.get_color_type(), input.get_color_type());
+        assert_eq!(output.get_bit_depth(), input.get_bit_depth());
+
+        let res = DotMatrix::new(&output_filepath)
+            .decode("Password")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in check_format: {}", err);
+                process::exit(1);
+            });
 
         assert_eq!(res, "Hello how is the weather today".to_string());
     }
 
     #[test]
-    fn test_global_with_file_encoding() {
-        let _ = fs::remove_file(
-            "/Users/mathias/Documents/Devs/Rust/stegano/test_files/test_global.png",
+    fn test_global_grayscale() {
+        let input_filepath = write_converted_picture(ColorType::Gray, 8, "test_gray.png");
+
+        check_format(&input_filepath, EmbeddingMode::Lsb, 1, "test_gray_lsb.png");
+        check_format(&input_filepath, EmbeddingMode::Pvd, 1, "test_gray_pvd.png");
+        check_format(
+            &input_filepath,
+            EmbeddingMode::Textured,
+            1,
+            "test_gray_textured.png",
         );
-        let _ = fs::remove_file("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test.txt");
-        let _ = fs::remove_file("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test2.txt");
+        assert!(DotMatrix::new(&input_filepath).set_low_bits(2).is_err());
+    }
+
+    #[test]
+    fn test_global_16_bits() {
+        let input_filepath = write_converted_picture(ColorType::Rgb, 16, "test_16.png");
+
+        check_format(&input_filepath, EmbeddingMode::Lsb, 4, "test_16_lsb.png");
+        check_format(
+            &input_filepath,
+            EmbeddingMode::Textured,
+            8,
+            "test_16_textured.png",
+        );
+        check_format(&input_filepath, EmbeddingMode::Pvd, 1, "test_16_pvd.png");
+        check_format(
+            &input_filepath,
+            EmbeddingMode::Reversible,
+            1,
+            "test_16_reversible.png",
+        );
+        assert!(DotMatrix::new(&input_filepath).set_low_bits(9).is_err());
+    }
+
+    /// Write a palette version of the reference picture, 216 colors cube, returning its path
+    fn write_indexed_picture(filename: &str) -> String {
+        let output_filepath = get_output_filepath(filename);
+        let reference = Picture::open(TEST_FILEPATH).unwrap_or_else(|err| {
+            eprintln!("Error in write_indexed_picture: {}", err);
+            process::exit(1);
+        });
+
+        let palette: Vec<[u8; 3]> = (0..216)
+            .map(|index| [(index / 36) * 51, (index / 6 % 6) * 51, (index % 6) * 51])
+            .collect();
+        let indexes: Vec<u8> = reference
+            .to_rgba8()
+            .chunks(4)
+            .map(|rgba| {
+                let level = |value: u8| (value as u16 + 25) / 51;
+                (level(rgba[0]) * 36 + level(rgba[1]) * 6 + level(rgba[2])) as u8
+            })
+            .collect();
+
+        let (width, height) = reference.dimensions();
+        Picture::new_indexed(width, height, palette, Vec::new(), indexes)
+            .and_then(|picture| picture.save(&output_filepath))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in write_indexed_picture: {}", err);
+                process::exit(1);
+            });
+
+        output_filepath
+    }
+
+    #[test]
+    fn test_global_palette() {
+        for &(input, output) in &[
+            ("test_indexed.png", "test_indexed_palette.png"),
+            ("test_indexed.gif", "test_indexed_palette.gif"),
+        ] {
+            let input_filepath = write_indexed_picture(input);
+            check_format(&input_filepath, EmbeddingMode::Palette, 1, output);
+
+            // Palette must be kept as is
+            let palette = |filepath: &str| match Picture::open(filepath) {
+                Ok(picture) => picture.get_palette().to_vec(),
+                Err(_) => Vec::new(),
+            };
+            assert!(palette(&get_output_filepath(output)) == palette(&input_filepath));
+
+            let mut image = DotMatrix::new(&input_filepath);
+            assert!(image
+                .encode_with_mode("Hello", "Password", EmbeddingMode::Pvd)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_embedding_mode_from_str() {
+        assert_eq!(
+            "pvd"
+                .parse::<EmbeddingMode>()
+                .expect("Unable to parse mode!"),
+            EmbeddingMode::Pvd
+        );
+        assert!("unknown".parse::<EmbeddingMode>().is_err());
+    }
+
+    #[test]
+    fn test_decode_legacy() {
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+
+        // Message written the way it was before headers were introduced
+        let mut encrypted_message =
+            simple_encrypt("Old message", "Password").unwrap_or_else(|err| {
+                eprintln!("Error in test_decode_legacy: {}", err);
+                process::exit(1);
+            });
+        encrypted_message.push(ENDING_CHAR as u8);
+        let vector =
+            convert_byte_vec_to_bit_array(&convert_u8_vec_to_byte_array(&encrypted_message));
+
+        for (index, &bit) in vector.iter().enumerate() {
+            image.write_slot(index, 1, &[bit]).unwrap_or_else(|err| {
+                eprintln!("Error in test_decode_legacy: {}", err);
+                process::exit(1);
+            });
+        }
+
+        let res = image.decode("Password").unwrap_or_else(|err| {
+            eprintln!("Error in test_decode_legacy: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(res, "Old message".to_string());
+
+        // Ending character alone, as random bits may start with, holds no message
+        let vector =
+            convert_byte_vec_to_bit_array(&convert_u8_vec_to_byte_array(&[ENDING_CHAR as u8]));
+        for (index, &bit) in vector.iter().enumerate() {
+            image.write_slot(index, 1, &[bit]).unwrap_or_else(|err| {
+                eprintln!("Error in test_decode_legacy: {}", err);
+                process::exit(1);
+            });
+        }
+        assert!(image.decode("Password").is_err());
+    }
+
+    /// Third party strategy : least significant bits, from the last sample backwards
+    struct BackwardLsb;
+
+    impl EmbeddingStrategy for BackwardLsb {
+        fn get_id(&self) -> u8 {
+            200
+        }
+
+        fn embed(
+            &self,
+            carrier: &mut dyn Carrier,
+            start: usize,
+            bits: &[bool],
+            _password: &str,
+        ) -> Result<(), Error> {
+            let last = carrier.get_slots_count() - 1;
+            for (position, &bit) in bits.iter().enumerate().take(last + 1 - start) {
+                carrier.write_slot(last - position, 1, &[bit])?;
+            }
+            Ok(())
+        }
+
+        fn extract(
+            &self,
+            carrier: &dyn Carrier,
+            start: usize,
+            _password: &str,
+        ) -> Result<Vec<u8>, Error> {
+            let last = carrier.get_slots_count() - 1;
+            read_framed_message(
+                (start..=last).map(|index| carrier.read_slot(last + start - index, 1)),
+            )
+        }
+    }
+
+    #[test]
+    fn test_global_strategies() {
+        let mut registry = StrategyRegistry::default();
+        registry
+            .register(200, "BACKWARD", |_| Ok(Box::new(BackwardLsb)))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global_strategies: {}", err);
+                process::exit(1);
+            });
 
-        // TODO : relative path
-        let mut image =
-            DotMatrix::new("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test.png");
+        for name in &["RANDOM_WALK:2", "MATCHING", "MATRIX", "BACKWARD"] {
+            let output_filepath = get_output_filepath("test_strategy.png");
+            let mut image = DotMatrix::new(TEST_FILEPATH);
+
+            registry
+                .from_name(name)
+                .and_then(|strategy| {
+                    image.encode_with_strategy(
+                        "Hello how is the weather today",
+                        "Password",
+                        strategy.as_ref(),
+                    )
+                })
+                .and_then(|_| image.write_to_file(&output_filepath))
+                .unwrap_or_else(|err| {
+                    eprintln!("Error in test_global_strategies: {}", err);
+                    process::exit(1);
+                });
 
-        let mut file =
-            File::create("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test.txt")
+            // Strategy is found back from the header
+            let image2 = DotMatrix::new(&output_filepath);
+            let res = image2
+                .decode_with_registry("Password", &registry)
                 .unwrap_or_else(|err| {
-                    eprintln!("Error in test_global: {}", err);
+                    eprintln!("Error in test_global_strategies: {}", err);
                     process::exit(1);
                 });
-        file.write_all(b"Test message within file")
+
+            assert_eq!(res, "Hello how is the weather today".to_string());
+            assert!(image2
+                .decode_with_registry("Wrong password", &registry)
+                .is_err());
+
+            // Default registry does not know third party strategies
+            assert_eq!(image2.decode("Password").is_ok(), *name != "BACKWARD");
+        }
+    }
+
+    #[test]
+    fn test_global_recipient() {
+        let output_filepath = get_output_filepath("test_recipient.png");
+        let (secret_key, public_key) = generate_keypair().unwrap_or_else(|err| {
+            eprintln!("Error in test_global_recipient: {}", err);
+            process::exit(1);
+        });
+        let sealing = |embedding_key: &str| {
+            RecipientSealing::to_recipient(public_key, embedding_key).unwrap_or_else(|err| {
+                eprintln!("Error in test_global_recipient: {}", err);
+                process::exit(1);
+            })
+        };
+
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+        image
+            .encode_sealed_with_mode(
+                "Hello how is the weather today",
+                &sealing("Embedding key"),
+                EmbeddingMode::Adaptive,
+            )
+            .and_then(|_| image.write_to_file(&output_filepath))
             .unwrap_or_else(|err| {
-                eprintln!("Error in test_global: {}", err);
+                eprintln!("Error in test_global_recipient: {}", err);
+                process::exit(1);
+            });
+
+        // Only the private key holder decodes, the public key with the embedding key
+        // finding the header only, the public key alone finding nothing
+        let image2 = DotMatrix::new(&output_filepath);
+        let res = RecipientSealing::from_identity(secret_key, "Embedding key")
+            .and_then(|identity| image2.decode_sealed(&identity))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global_recipient: {}", err);
                 process::exit(1);
             });
 
+        assert_eq!(res, "Hello how is the weather today".to_string());
+        assert!(image2.decode_sealed(&sealing("Embedding key")).is_err());
+        assert!(image2
+            .read_header(&sealing("Embedding key").get_carrier_key())
+            .is_ok_and(|header| header.is_some()));
+        assert!(
+            RecipientSealing::from_identity(secret_key, "Other embedding key")
+                .and_then(|identity| image2.decode_sealed(&identity))
+                .is_err()
+        );
+        assert!(image2.decode(&to_hex(&public_key)).is_err());
+    }
+
+    #[test]
+    fn test_global_decoy() {
+        let output_filepath = get_output_filepath("test_decoy.png");
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+
         image
-            .encode_file(
-                "/Users/mathias/Documents/Devs/Rust/stegano/test_files/test.txt",
+            .encode_with_decoy(
+                "Hello how is the weather today",
                 "Password",
+                "Shopping list : eggs, milk",
+                "Decoy password",
             )
+            .and_then(|_| image.write_to_file(&output_filepath))
             .unwrap_or_else(|err| {
-                eprintln!("Error in test_global: {}", err);
+                eprintln!("Error in test_global_decoy: {}", err);
                 process::exit(1);
             });
 
+        // Each password decodes its own message only
+        let image2 = DotMatrix::new(&output_filepath);
+        for &(password, message) in &[
+            ("Password", "Hello how is the weather today"),
+            ("Decoy password", "Shopping list : eggs, milk"),
+        ] {
+            let res = image2.decode(password).unwrap_or_else(|err| {
+                eprintln!("Error in test_global_decoy: {}", err);
+                process::exit(1);
+            });
+            assert_eq!(res, message.to_string());
+        }
+        assert!(image2.decode("Wrong password").is_err());
+
+        assert!(DotMatrix::new(TEST_FILEPATH)
+            .encode_with_decoy("Hello", "Password", "Hello", "Password")
+            .is_err());
+
+        // Tampered payload fails authentication instead of decoding anything else
+        let mut image3 = DotMatrix::new(TEST_FILEPATH);
+        let index = image3
+            .store_deniable(0, b"Encrypted message", "Password")
+            .map(|_| {
+                image3.get_deniable_walk(0, "Password")[DENIABLE_HEADER_BYTES * 8 + LENGTH_BITS]
+            })
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global_decoy: {}", err);
+                process::exit(1);
+            });
+        let bit = image3.read_slot(index, 1).unwrap_or_else(|err| {
+            eprintln!("Error in test_global_decoy: {}", err);
+            process::exit(1);
+        })[0];
+        image3.write_slot(index, 1, &[!bit]).unwrap_or_else(|err| {
+            eprintln!("Error in test_global_decoy: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(
+            image3.decode_deniable("Password").unwrap_err().to_string(),
+            "stegano/decode : Deniable payload failed authentication!"
+        );
+    }
+
+    #[test]
+    fn test_global_wipe() {
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+
         image
-            .write_to_file("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test_global.png")
+            .encode("Hello how is the weather today", "Password")
+            .and_then(|_| image.wipe(&WipeOptions::default()))
             .unwrap_or_else(|err| {
-                eprintln!("Error in test_global: {}", err);
+                eprintln!("Error in test_global_wipe: {}", err);
                 process::exit(1);
             });
 
-        let image2 =
-            DotMatrix::new("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test_global.png");
-        image2
-            .decode_and_write(
-                "/Users/mathias/Documents/Devs/Rust/stegano/test_files/test2.txt",
-                "Password",
-            )
+        assert!(image.decode("Password").is_err());
+        assert!(image.wipe(&WipeOptions { low_bits: Some(2) }).is_err());
+    }
+
+    #[test]
+    fn test_global_sanitize() {
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+
+        let report = image
+            .encode("Hello how is the weather today", "Password")
+            .and_then(|_| image.sanitize())
             .unwrap_or_else(|err| {
-                eprintln!("Error in test_global: {}", err);
+                eprintln!("Error in test_global_sanitize: {}", err);
                 process::exit(1);
             });
 
-        let mut file =
-            File::open("/Users/mathias/Documents/Devs/Rust/stegano/test_files/test2.txt")
-                .unwrap_or_else(|err| {
-                    eprintln!("Error in test_global: {}", err);
-                    process::exit(1);
-                });
+        assert!(image.decode("Password").is_err());
+        assert!(report.psnr > 40.0);
+    }
 
-        let mut result_string = String::new();
-        let _ = file
-            .read_to_string(&mut result_string)
+    #[test]
+    fn test_global_visualize() {
+        let mut image = DotMatrix::new(TEST_FILEPATH);
+        let cover = DotMatrix::new(TEST_FILEPATH);
+
+        let (plane, difference) = image
+            .encode("Hello how is the weather today", "Password")
+            .and_then(|_| {
+                Ok((
+                    image.get_bit_plane(0, 1)?,
+                    image.get_difference(&cover, 255)?,
+                ))
+            })
             .unwrap_or_else(|err| {
-                eprintln!("Error in test_global: {}", err);
+                eprintln!("Error in test_global_visualize: {}", err);
                 process::exit(1);
             });
 
-        assert_eq!("Test message within file".to_string(), result_string);
+        // Plane tells the LSB of the green samples, difference the samples the payload changed
+        assert_eq!(plane.dimensions(), image.get_dimensions());
+        assert_eq!(
+            plane.get_color_sample(0) == 255,
+            image.read_slot(1, 1).unwrap_or_default()[0]
+        );
+        assert!((0..difference.get_color_samples_count())
+            .all(|index| [0, 255].contains(&difference.get_color_sample(index))));
+        assert!(image.get_bit_plane(8, 0).is_err());
     }
 }
@@ -0,0 +1,143 @@
+//! F5 : hide bits in non-zero quantized DCT coefficients, with matrix encoding
+//! k bits are hidden in n = 2^k - 1 coefficients by changing at most one of them, whose
+//! absolute value is decreased by one. A coefficient falling to zero (shrinkage) no longer
+//! holds anything : the same bits are then embedded again in the following coefficients
+extern crate rand;
+
+use self::rand::Rng;
+
+/// Returns bit held by a non-zero coefficient
+fn get_bit(coefficient: i32) -> bool {
+    (coefficient.abs() % 2 == 1) ^ (coefficient < 0)
+}
+
+/// Returns indexes of the next n non-zero coefficients from cursor, None if too few are left
+fn get_group(coefficients: &[i32], cursor: usize, n: usize) -> Option<Vec<usize>> {
+    let group: Vec<usize> = (cursor..coefficients.len())
+        .filter(|&index| coefficients[index] != 0)
+        .take(n)
+        .collect();
+
+    if group.len() == n {
+        Some(group)
+    } else {
+        None
+    }
+}
+
+/// Returns the k bits value held by a group of coefficients
+fn get_hash(coefficients: &[i32], group: &[usize]) -> usize {
+    group
+        .iter()
+        .enumerate()
+        .filter(|&(_, &index)| get_bit(coefficients[index]))
+        .fold(0, |hash, (position, _)| hash ^ (position + 1))
+}
+
+/// Embed bits from cursor, k bits per group, the last group being padded with false bits
+/// Returns cursor after the last group, or None if coefficients are exhausted
+pub fn embed(coefficients: &mut [i32], cursor: usize, bits: &[bool], k: usize) -> Option<usize> {
+    let n = (1 << k) - 1;
+    let mut cursor = cursor;
+
+    for chunk in bits.chunks(k) {
+        let value = (0..k).fold(0, |acc, i| {
+            (acc << 1) | *chunk.get(i).unwrap_or(&false) as usize
+        });
+
+        loop {
+            let group = get_group(coefficients, cursor, n)?;
+            let target = get_hash(coefficients, &group) ^ value;
+
+            if target != 0 {
+                let index = group[target - 1];
+                coefficients[index] -= coefficients[index].signum();
+
+                // Shrinkage : the group is taken again, without the zeroed coefficient
+                if coefficients[index] == 0 {
+                    continue;
+                }
+            }
+
+            cursor = group[n - 1] + 1;
+            break;
+        }
+    }
+
+    Some(cursor)
+}
+
+/// Extract count bits from cursor, k bits per group
+/// Returns bits and cursor after the last group, or None if coefficients are exhausted
+pub fn extract(
+    coefficients: &[i32],
+    cursor: usize,
+    count: usize,
+    k: usize,
+) -> Option<(Vec<bool>, usize)> {
+    let n = (1 << k) - 1;
+    let mut cursor = cursor;
+    let mut bits = Vec::<bool>::with_capacity(count + k);
+
+    while bits.len() < count {
+        let group = get_group(coefficients, cursor, n)?;
+        let hash = get_hash(coefficients, &group);
+
+        bits.extend((0..k).rev().map(|shift| (hash >> shift) & 1 == 1));
+        cursor = group[n - 1] + 1;
+    }
+
+    bits.truncate(count);
+    Some((bits, cursor))
+}
+
+/// Overwrite bit held by every non-zero coefficient with a random one, the way embed changes them
+pub fn randomize(coefficients: &mut [i32]) {
+    let mut rng = rand::thread_rng();
+
+    for coefficient in coefficients
+        .iter_mut()
+        .filter(|coefficient| **coefficient != 0)
+    {
+        if get_bit(*coefficient) != rng.gen::<bool>() {
+            *coefficient -= coefficient.signum();
+        }
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed_extract() {
+        let original: Vec<i32> = (0..3000)
+            .map(|i: i32| ((i * 7919) % 13 - 6) * ((i % 5 != 0) as i32))
+            .collect();
+        let bits: Vec<bool> = (0..100).map(|i| (i * 31) % 7 < 3).collect();
+
+        for k in 1..=4 {
+            let mut coefficients = original.clone();
+            let cursor = embed(&mut coefficients, 10, &bits, k).expect("Enough coefficients");
+            let (extracted, extracted_cursor) =
+                extract(&coefficients, 10, bits.len(), k).expect("Enough coefficients");
+
+            assert_eq!(extracted, bits);
+            assert_eq!(extracted_cursor, cursor);
+
+            // Changes only decrease absolute values, by one at most
+            for (before, after) in original.iter().zip(coefficients.iter()) {
+                assert!(after.abs() <= before.abs() && before.abs() - after.abs() <= 1);
+                assert!(*after == 0 || after.signum() == before.signum());
+            }
+        }
+    }
+
+    #[test]
+    fn test_embed_runs_out() {
+        let mut coefficients = vec![1, -1, 0, 1];
+
+        assert_eq!(embed(&mut coefficients, 0, &[true; 20], 1), None);
+    }
+}
@@ -0,0 +1,938 @@
+//! Baseline JPEG files, read and written in the quantized DCT coefficients domain
+//! Coefficients are never dequantized : quantization tables and every segment but
+//! Huffman tables are written back as they were, so that re-writing is lossless
+//! Huffman tables are rebuilt from the coefficients statistics when writing
+
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Read, Write},
+};
+
+/// Markers handled
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOF0: u8 = 0xC0;
+const SOF1: u8 = 0xC1;
+const DHT: u8 = 0xC4;
+const SOS: u8 = 0xDA;
+const DRI: u8 = 0xDD;
+const RST0: u8 = 0xD0;
+
+/// Number of coefficients of a 8x8 block
+pub const BLOCK_SIZE: usize = 64;
+
+/// Longest Huffman code allowed by JPEG
+const MAX_CODE_LENGTH: usize = 16;
+
+/// Huffman symbol, followed by magnitude bits and their count
+type Symbol = (u8, u32, u8);
+
+/// Returns a JPEG format error
+fn format_error(message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("stegano/jpeg : {}", message),
+    )
+}
+
+/// Huffman table, as stored in DHT segments
+#[derive(Clone)]
+struct HuffmanTable {
+    counts: [u8; MAX_CODE_LENGTH],
+    symbols: Vec<u8>,
+}
+
+impl HuffmanTable {
+    /// Returns (code, length) of every symbol, canonical codes being assigned by length
+    fn get_codes(&self) -> Vec<Option<(u16, u8)>> {
+        let mut codes = vec![None; 256];
+        let mut code: u16 = 0;
+        let mut symbols = self.symbols.iter();
+
+        for (length, &count) in self.counts.iter().enumerate() {
+            for _ in 0..count {
+                if let Some(&symbol) = symbols.next() {
+                    codes[symbol as usize] = Some((code, length as u8 + 1));
+                }
+                code += 1;
+            }
+            code <<= 1;
+        }
+
+        codes
+    }
+
+    /// Build optimal table from symbols frequencies, codes being at most 16 bits long
+    /// See ITU T.81 annex K.2
+    fn from_frequencies(frequencies: &[usize; 256]) -> HuffmanTable {
+        // An extra symbol reserves the all-ones code
+        let mut frequencies: Vec<usize> = frequencies.to_vec();
+        frequencies.push(1);
+
+        let mut code_sizes = vec![0usize; 257];
+        let mut others: Vec<Option<usize>> = vec![None; 257];
+
+        loop {
+            // Two least frequent symbols, the highest value one being chosen among equal ones
+            let mut first: Option<usize> = None;
+            for (symbol, &frequency) in frequencies.iter().enumerate() {
+                if frequency > 0 && first.is_none_or(|best| frequency <= frequencies[best]) {
+                    first = Some(symbol);
+                }
+            }
+            let mut second: Option<usize> = None;
+            for (symbol, &frequency) in frequencies.iter().enumerate() {
+                if frequency > 0
+                    && Some(symbol) != first
+                    && second.is_none_or(|best| frequency <= frequencies[best])
+                {
+                    second = Some(symbol);
+                }
+            }
+
+            let (mut first, mut second) = match (first, second) {
+                (Some(first), Some(second)) => (first, second),
+                _ => break,
+            };
+
+            frequencies[first] += frequencies[second];
+            frequencies[second] = 0;
+
+            code_sizes[first] += 1;
+            while let Some(other) = others[first] {
+                first = other;
+                code_sizes[first] += 1;
+            }
+            others[first] = Some(second);
+
+            code_sizes[second] += 1;
+            while let Some(other) = others[second] {
+                second = other;
+                code_sizes[second] += 1;
+            }
+        }
+
+        // Count codes of each length, then limit lengths to 16 bits
+        let mut counts = vec![0usize; 33];
+        for &size in &code_sizes {
+            if size > 0 {
+                counts[size.min(32)] += 1;
+            }
+        }
+
+        let mut length = 32;
+        while length > MAX_CODE_LENGTH {
+            while counts[length] > 0 {
+                let mut shorter = length - 2;
+                while counts[shorter] == 0 {
+                    shorter -= 1;
+                }
+                counts[length] -= 2;
+                counts[length - 1] += 1;
+                counts[shorter + 1] += 2;
+                counts[shorter] -= 1;
+            }
+            length -= 1;
+        }
+
+        // Remove the reserved code
+        while counts[length] == 0 {
+            length -= 1;
+        }
+        counts[length] -= 1;
+
+        // Symbols sorted by code size, then by value
+        let mut symbols = Vec::<u8>::new();
+        for size in 1..=32 {
+            for (symbol, &code_size) in code_sizes.iter().enumerate().take(256) {
+                if code_size == size {
+                    symbols.push(symbol as u8);
+                }
+            }
+        }
+
+        let mut table_counts = [0u8; MAX_CODE_LENGTH];
+        for (length, count) in table_counts.iter_mut().enumerate() {
+            *count = counts[length + 1] as u8;
+        }
+
+        HuffmanTable {
+            counts: table_counts,
+            symbols,
+        }
+    }
+}
+
+/// Huffman decoder, using per-length code ranges (ITU T.81 F.2.2.3)
+struct HuffmanDecoder {
+    min_codes: [i32; MAX_CODE_LENGTH],
+    max_codes: [i32; MAX_CODE_LENGTH],
+    offsets: [usize; MAX_CODE_LENGTH],
+    symbols: Vec<u8>,
+}
+
+impl HuffmanDecoder {
+    /// Constructor
+    fn new(table: &HuffmanTable) -> HuffmanDecoder {
+        let mut decoder = HuffmanDecoder {
+            min_codes: [0; MAX_CODE_LENGTH],
+            max_codes: [-1; MAX_CODE_LENGTH],
+            offsets: [0; MAX_CODE_LENGTH],
+            symbols: table.symbols.clone(),
+        };
+
+        let mut code = 0;
+        let mut offset = 0;
+        for (length, &count) in table.counts.iter().enumerate() {
+            if count > 0 {
+                decoder.offsets[length] = offset;
+                decoder.min_codes[length] = code;
+                code += count as i32;
+                offset += count as usize;
+                decoder.max_codes[length] = code - 1;
+            }
+            code <<= 1;
+        }
+
+        decoder
+    }
+
+    /// Read next symbol
+    fn decode(&self, reader: &mut BitReader) -> Result<u8, Error> {
+        let mut code = 0;
+        for length in 0..MAX_CODE_LENGTH {
+            code = (code << 1) | reader.read_bit() as i32;
+            if code <= self.max_codes[length] {
+                let position = self.offsets[length] + (code - self.min_codes[length]) as usize;
+                return self
+                    .symbols
+                    .get(position)
+                    .cloned()
+                    .ok_or_else(|| format_error("Invalid Huffman code!"));
+            }
+        }
+
+        Err(format_error("Invalid Huffman code!"))
+    }
+}
+
+/// Reads entropy-coded data bit after bit, removing stuffed bytes
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    bits_count: u8,
+}
+
+impl<'a> BitReader<'a> {
+    /// Constructor
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            position: 0,
+            buffer: 0,
+            bits_count: 0,
+        }
+    }
+
+    /// Read next bit, zeros being returned once a marker is reached
+    fn read_bit(&mut self) -> u8 {
+        if self.bits_count == 0 {
+            let byte = match self.data.get(self.position) {
+                Some(0xFF) if self.data.get(self.position + 1) == Some(&0x00) => {
+                    self.position += 2;
+                    0xFF
+                }
+                Some(0xFF) | None => 0x00,
+                Some(&byte) => {
+                    self.position += 1;
+                    byte
+                }
+            };
+            self.buffer = byte as u32;
+            self.bits_count = 8;
+        }
+
+        self.bits_count -= 1;
+        ((self.buffer >> self.bits_count) & 1) as u8
+    }
+
+    /// Read given number of bits, most significant first
+    fn read_bits(&mut self, count: u8) -> u32 {
+        (0..count).fold(0, |acc, _| (acc << 1) | self.read_bit() as u32)
+    }
+
+    /// Skip restart marker, dropping remaining bits of the current byte
+    fn restart(&mut self) -> Result<(), Error> {
+        self.bits_count = 0;
+        match (
+            self.data.get(self.position),
+            self.data.get(self.position + 1),
+        ) {
+            (Some(0xFF), Some(marker)) if (RST0..RST0 + 8).contains(marker) => {
+                self.position += 2;
+                Ok(())
+            }
+            _ => Err(format_error("Missing restart marker!")),
+        }
+    }
+}
+
+/// Writes entropy-coded data, stuffing bytes after 0xFF
+struct BitWriter {
+    data: Vec<u8>,
+    buffer: u32,
+    bits_count: u8,
+}
+
+impl BitWriter {
+    /// Constructor
+    fn new() -> BitWriter {
+        BitWriter {
+            data: Vec::new(),
+            buffer: 0,
+            bits_count: 0,
+        }
+    }
+
+    /// Write given number of bits, most significant first
+    fn write_bits(&mut self, value: u32, count: u8) {
+        for shift in (0..count).rev() {
+            self.buffer = (self.buffer << 1) | ((value >> shift) & 1);
+            self.bits_count += 1;
+
+            if self.bits_count == 8 {
+                self.data.push(self.buffer as u8);
+                if self.buffer as u8 == 0xFF {
+                    self.data.push(0x00);
+                }
+                self.buffer = 0;
+                self.bits_count = 0;
+            }
+        }
+    }
+
+    /// Pad current byte with ones
+    fn flush(&mut self) {
+        if self.bits_count > 0 {
+            self.write_bits(0xFF, 8 - self.bits_count);
+        }
+    }
+}
+
+/// Returns number of bits needed to write coefficient magnitude
+fn get_category(value: i32) -> u8 {
+    (32 - value.unsigned_abs().leading_zeros()) as u8
+}
+
+/// Rebuild coefficient from its magnitude bits (ITU T.81 F.2.2.1)
+fn extend(bits: u32, category: u8) -> i32 {
+    if category == 0 {
+        0
+    } else if bits < 1 << (category - 1) {
+        bits as i32 - (1 << category) + 1
+    } else {
+        bits as i32
+    }
+}
+
+/// Returns magnitude bits of coefficient, negative ones being written minus one
+fn get_magnitude_bits(value: i32, category: u8) -> u32 {
+    if value < 0 {
+        (value - 1) as u32 & ((1 << category) - 1)
+    } else {
+        value as u32
+    }
+}
+
+/// Frame component, with its quantized coefficients block after block, in zigzag order
+pub struct Component {
+    id: u8,
+    horizontal: usize,
+    vertical: usize,
+    dc_table: u8,
+    ac_table: u8,
+    blocks_per_line: usize,
+    blocks_per_column: usize,
+    coefficients: Vec<i32>,
+}
+
+/// Baseline JPEG file
+pub struct Jpeg {
+    width: usize,
+    height: usize,
+    segments: Vec<(u8, Vec<u8>)>,
+    scan_header: Vec<u8>,
+    restart_interval: usize,
+    components: Vec<Component>,
+    scan_components: Vec<usize>,
+}
+
+impl Jpeg {
+    /// Read JPEG file
+    pub fn open(filepath: &str) -> Result<Jpeg, Error> {
+        let mut data = Vec::<u8>::new();
+        File::open(filepath)?.read_to_end(&mut data)?;
+        Jpeg::from_bytes(&data)
+    }
+
+    /// Returns true if data starts like a JPEG file
+    pub fn is_jpeg(data: &[u8]) -> bool {
+        data.len() > 2 && data[0] == 0xFF && data[1] == SOI
+    }
+
+    /// Parse JPEG file contents, down to quantized coefficients
+    pub fn from_bytes(data: &[u8]) -> Result<Jpeg, Error> {
+        if !Jpeg::is_jpeg(data) {
+            return Err(format_error("Not a JPEG file!"));
+        }
+
+        let mut jpeg = Jpeg {
+            width: 0,
+            height: 0,
+            segments: Vec::new(),
+            scan_header: Vec::new(),
+            restart_interval: 0,
+            components: Vec::new(),
+            scan_components: Vec::new(),
+        };
+        let mut tables: Vec<Option<HuffmanTable>> = vec![None; 8];
+        let mut position = 2;
+
+        loop {
+            // Skip fill bytes
+            while data.get(position) == Some(&0xFF) && data.get(position + 1) == Some(&0xFF) {
+                position += 1;
+            }
+
+            let marker = match (data.get(position), data.get(position + 1)) {
+                (Some(0xFF), Some(&marker)) => marker,
+                _ => return Err(format_error("Invalid marker!")),
+            };
+
+            if marker == EOI {
+                return Err(format_error("No scan in this file!"));
+            }
+
+            let length = match (data.get(position + 2), data.get(position + 3)) {
+                (Some(&high), Some(&low)) => u16::from_be_bytes([high, low]) as usize,
+                _ => return Err(format_error("Truncated segment!")),
+            };
+            let segment = data
+                .get(position + 4..position + 2 + length)
+                .ok_or_else(|| format_error("Truncated segment!"))?;
+            position += 2 + length;
+
+            match marker {
+                SOF0 | SOF1 => jpeg.read_frame(segment)?,
+                0xC2..=0xCF if marker != DHT && marker != 0xC8 && marker != 0xCC => {
+                    return Err(format_error("Only baseline JPEG files are handled!"))
+                }
+                DHT => read_huffman_tables(segment, &mut tables)?,
+                DRI if segment.len() >= 2 => {
+                    jpeg.restart_interval = u16::from_be_bytes([segment[0], segment[1]]) as usize
+                }
+                SOS => {
+                    jpeg.read_scan_header(segment)?;
+                    jpeg.decode_scan(&data[position..], &tables)?;
+                    return Ok(jpeg);
+                }
+                _ => {}
+            }
+
+            // Huffman tables are rebuilt when writing
+            if marker != DHT {
+                jpeg.segments.push((marker, segment.to_vec()));
+            }
+        }
+    }
+
+    /// Read frame header
+    fn read_frame(&mut self, segment: &[u8]) -> Result<(), Error> {
+        if segment.len() < 6 || segment[0] != 8 {
+            return Err(format_error("Only 8 bits JPEG files are handled!"));
+        }
+
+        self.height = u16::from_be_bytes([segment[1], segment[2]]) as usize;
+        self.width = u16::from_be_bytes([segment[3], segment[4]]) as usize;
+        let count = segment[5] as usize;
+
+        if segment.len() < 6 + 3 * count || self.width == 0 || self.height == 0 {
+            return Err(format_error("Invalid frame header!"));
+        }
+
+        for component in segment[6..6 + 3 * count].chunks(3) {
+            let (horizontal, vertical) =
+                ((component[1] >> 4) as usize, (component[1] & 15) as usize);
+            if horizontal == 0 || vertical == 0 {
+                return Err(format_error("Invalid sampling factors!"));
+            }
+
+            self.components.push(Component {
+                id: component[0],
+                horizontal,
+                vertical,
+                dc_table: 0,
+                ac_table: 0,
+                blocks_per_line: 0,
+                blocks_per_column: 0,
+                coefficients: Vec::new(),
+            });
+        }
+
+        // Blocks are allocated up to the MCU boundaries
+        let (mcus_per_line, mcus_per_column) = self.get_mcus_count();
+        for component in self.components.iter_mut() {
+            component.blocks_per_line = mcus_per_line * component.horizontal;
+            component.blocks_per_column = mcus_per_column * component.vertical;
+            component.coefficients =
+                vec![0; component.blocks_per_line * component.blocks_per_column * BLOCK_SIZE];
+        }
+
+        Ok(())
+    }
+
+    /// Returns highest sampling factors
+    fn get_max_sampling(&self) -> (usize, usize) {
+        (
+            self.components
+                .iter()
+                .map(|c| c.horizontal)
+                .max()
+                .unwrap_or(1),
+            self.components
+                .iter()
+                .map(|c| c.vertical)
+                .max()
+                .unwrap_or(1),
+        )
+    }
+
+    /// Returns number of MCUs per line and per column, for interleaved scans
+    fn get_mcus_count(&self) -> (usize, usize) {
+        let (max_horizontal, max_vertical) = self.get_max_sampling();
+        (
+            self.width.div_ceil(8 * max_horizontal),
+            self.height.div_ceil(8 * max_vertical),
+        )
+    }
+
+    /// Read scan header, the scan being required to hold every component
+    fn read_scan_header(&mut self, segment: &[u8]) -> Result<(), Error> {
+        if self.components.is_empty() {
+            return Err(format_error("Scan found before frame header!"));
+        }
+
+        let count = *segment.first().unwrap_or(&0) as usize;
+        if count != self.components.len() || segment.len() < 1 + 2 * count + 3 {
+            return Err(format_error("Only single-scan JPEG files are handled!"));
+        }
+
+        for selector in segment[1..1 + 2 * count].chunks(2) {
+            let index = self
+                .components
+                .iter()
+                .position(|component| component.id == selector[0])
+                .ok_or_else(|| format_error("Unknown scan component!"))?;
+            self.components[index].dc_table = selector[1] >> 4;
+            self.components[index].ac_table = selector[1] & 15;
+            self.scan_components.push(index);
+        }
+
+        self.scan_header = segment.to_vec();
+        Ok(())
+    }
+
+    /// Returns blocks of the scan, MCU after MCU, as (component, block) indexes
+    fn get_scan_blocks(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut mcus = Vec::new();
+
+        if self.scan_components.len() == 1 {
+            // Non-interleaved scan : blocks covering the component only, one per MCU
+            let index = self.scan_components[0];
+            let component = &self.components[index];
+            let (max_horizontal, max_vertical) = self.get_max_sampling();
+            let width = (self.width * component.horizontal).div_ceil(max_horizontal);
+            let height = (self.height * component.vertical).div_ceil(max_vertical);
+
+            for y in 0..height.div_ceil(8) {
+                for x in 0..width.div_ceil(8) {
+                    mcus.push(vec![(index, y * component.blocks_per_line + x)]);
+                }
+            }
+        } else {
+            let (mcus_per_line, mcus_per_column) = self.get_mcus_count();
+
+            for mcu_y in 0..mcus_per_column {
+                for mcu_x in 0..mcus_per_line {
+                    let mut mcu = Vec::new();
+                    for &index in &self.scan_components {
+                        let component = &self.components[index];
+                        for y in 0..component.vertical {
+                            for x in 0..component.horizontal {
+                                let block_y = mcu_y * component.vertical + y;
+                                let block_x = mcu_x * component.horizontal + x;
+                                mcu.push((index, block_y * component.blocks_per_line + block_x));
+                            }
+                        }
+                    }
+                    mcus.push(mcu);
+                }
+            }
+        }
+
+        mcus
+    }
+
+    /// Decode entropy-coded data into coefficients
+    fn decode_scan(&mut self, data: &[u8], tables: &[Option<HuffmanTable>]) -> Result<(), Error> {
+        let get_decoder = |class: usize, id: u8| -> Result<HuffmanDecoder, Error> {
+            tables
+                .get(class * 4 + id as usize)
+                .and_then(|table| table.as_ref())
+                .map(HuffmanDecoder::new)
+                .ok_or_else(|| format_error("Missing Huffman table!"))
+        };
+
+        let mut decoders = Vec::new();
+        for component in &self.components {
+            decoders.push((
+                get_decoder(0, component.dc_table)?,
+                get_decoder(1, component.ac_table)?,
+            ));
+        }
+
+        let mut reader = BitReader::new(data);
+        let mut predictions = vec![0; self.components.len()];
+
+        for (mcu_index, mcu) in self.get_scan_blocks().iter().enumerate() {
+            if self.restart_interval > 0 && mcu_index > 0 && mcu_index % self.restart_interval == 0
+            {
+                reader.restart()?;
+                predictions
+                    .iter_mut()
+                    .for_each(|prediction| *prediction = 0);
+            }
+
+            for &(index, block) in mcu {
+                let (ref dc_decoder, ref ac_decoder) = decoders[index];
+                let coefficients = &mut self.components[index].coefficients
+                    [block * BLOCK_SIZE..(block + 1) * BLOCK_SIZE];
+
+                let category = dc_decoder.decode(&mut reader)?;
+                predictions[index] += extend(reader.read_bits(category), category);
+                coefficients[0] = predictions[index];
+
+                let mut position = 1;
+                while position < BLOCK_SIZE {
+                    let symbol = ac_decoder.decode(&mut reader)?;
+                    let (run, category) = ((symbol >> 4) as usize, symbol & 15);
+
+                    if category == 0 {
+                        if run != 15 {
+                            break;
+                        }
+                        position += 16;
+                        continue;
+                    }
+
+                    position += run;
+                    if position >= BLOCK_SIZE {
+                        return Err(format_error("Invalid coefficients run!"));
+                    }
+                    coefficients[position] = extend(reader.read_bits(category), category);
+                    position += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns symbols of a block, as (symbol, magnitude bits, magnitude bits count) triplets
+    fn get_block_symbols(coefficients: &[i32], prediction: i32) -> Vec<Symbol> {
+        let difference = coefficients[0] - prediction;
+        let category = get_category(difference);
+        let mut symbols = vec![(category, get_magnitude_bits(difference, category), category)];
+
+        let mut run = 0;
+        for &coefficient in &coefficients[1..] {
+            if coefficient == 0 {
+                run += 1;
+                continue;
+            }
+
+            while run > 15 {
+                symbols.push((0xF0, 0, 0));
+                run -= 16;
+            }
+
+            let category = get_category(coefficient);
+            symbols.push((
+                (run << 4) | category,
+                get_magnitude_bits(coefficient, category),
+                category,
+            ));
+            run = 0;
+        }
+
+        if run > 0 {
+            symbols.push((0x00, 0, 0));
+        }
+
+        symbols
+    }
+
+    /// Returns symbols of the whole scan, MCU after MCU, tagged with their component index
+    fn get_scan_symbols(&self) -> Vec<Vec<(usize, Vec<Symbol>)>> {
+        let mut predictions = vec![0; self.components.len()];
+
+        self.get_scan_blocks()
+            .iter()
+            .enumerate()
+            .map(|(mcu_index, mcu)| {
+                if self.restart_interval > 0
+                    && mcu_index > 0
+                    && mcu_index % self.restart_interval == 0
+                {
+                    predictions
+                        .iter_mut()
+                        .for_each(|prediction| *prediction = 0);
+                }
+
+                mcu.iter()
+                    .map(|&(index, block)| {
+                        let coefficients = &self.components[index].coefficients
+                            [block * BLOCK_SIZE..(block + 1) * BLOCK_SIZE];
+                        let symbols = Jpeg::get_block_symbols(coefficients, predictions[index]);
+                        predictions[index] = coefficients[0];
+                        (index, symbols)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Write JPEG file contents, with Huffman tables fitted to the coefficients
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let symbols = self.get_scan_symbols();
+
+        // Count symbols of each table
+        let mut frequencies = vec![[0usize; 256]; 8];
+        for mcu in &symbols {
+            for (index, block) in mcu {
+                let component = &self.components[*index];
+                for (position, &(symbol, _, _)) in block.iter().enumerate() {
+                    let table = if position == 0 {
+                        component.dc_table as usize
+                    } else {
+                        4 + component.ac_table as usize
+                    };
+                    frequencies[table][symbol as usize] += 1;
+                }
+            }
+        }
+
+        let tables: Vec<Option<HuffmanTable>> = frequencies
+            .iter()
+            .map(|frequencies| {
+                if frequencies.iter().any(|&frequency| frequency > 0) {
+                    Some(HuffmanTable::from_frequencies(frequencies))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let mut data = vec![0xFF, SOI];
+        let push_segment = |data: &mut Vec<u8>, marker: u8, segment: &[u8]| {
+            data.extend_from_slice(&[0xFF, marker]);
+            data.extend_from_slice(&(segment.len() as u16 + 2).to_be_bytes());
+            data.extend_from_slice(segment);
+        };
+
+        for (marker, segment) in &self.segments {
+            push_segment(&mut data, *marker, segment);
+        }
+
+        for (slot, table) in tables.iter().enumerate() {
+            if let Some(table) = table {
+                let mut segment = vec![(((slot / 4) << 4) | (slot % 4)) as u8];
+                segment.extend_from_slice(&table.counts);
+                segment.extend_from_slice(&table.symbols);
+                push_segment(&mut data, DHT, &segment);
+            }
+        }
+
+        push_segment(&mut data, SOS, &self.scan_header);
+
+        // Entropy-coded data
+        let codes: Vec<Vec<Option<(u16, u8)>>> = tables
+            .iter()
+            .map(|table| {
+                table
+                    .as_ref()
+                    .map(|table| table.get_codes())
+                    .unwrap_or_default()
+            })
+            .collect();
+        let mut writer = BitWriter::new();
+
+        for (mcu_index, mcu) in symbols.iter().enumerate() {
+            if self.restart_interval > 0 && mcu_index > 0 && mcu_index % self.restart_interval == 0
+            {
+                writer.flush();
+                let marker = RST0 + ((mcu_index / self.restart_interval - 1) % 8) as u8;
+                writer.data.extend_from_slice(&[0xFF, marker]);
+            }
+
+            for (index, block) in mcu {
+                let component = &self.components[*index];
+                for (position, &(symbol, bits, count)) in block.iter().enumerate() {
+                    let table = if position == 0 {
+                        component.dc_table as usize
+                    } else {
+                        4 + component.ac_table as usize
+                    };
+                    // Every symbol was counted, so it has a code
+                    if let Some((code, length)) = codes[table][symbol as usize] {
+                        writer.write_bits(code as u32, length);
+                    }
+                    writer.write_bits(bits, count);
+                }
+            }
+        }
+
+        writer.flush();
+        data.append(&mut writer.data);
+        data.extend_from_slice(&[0xFF, EOI]);
+
+        data
+    }
+
+    /// Write JPEG file
+    pub fn save(&self, filepath: &str) -> Result<(), Error> {
+        File::create(filepath)?.write_all(&self.to_bytes())
+    }
+
+    /// Returns picture dimensions
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width as u32, self.height as u32)
+    }
+
+    /// Returns number of coefficients, components after components, blocks after blocks
+    pub fn get_coefficients_count(&self) -> usize {
+        self.components
+            .iter()
+            .map(|component| component.coefficients.len())
+            .sum()
+    }
+
+    /// Returns component and position of coefficient at given index
+    fn get_position(&self, mut index: usize) -> (usize, usize) {
+        for (component_index, component) in self.components.iter().enumerate() {
+            if index < component.coefficients.len() {
+                return (component_index, index);
+            }
+            index -= component.coefficients.len();
+        }
+        (self.components.len(), 0)
+    }
+
+    /// Returns true if coefficient at given index is an AC one
+    pub fn is_ac(&self, index: usize) -> bool {
+        !self.get_position(index).1.is_multiple_of(BLOCK_SIZE)
+    }
+
+    /// Returns quantized coefficient at given index
+    pub fn get_coefficient(&self, index: usize) -> i32 {
+        let (component, position) = self.get_position(index);
+        self.components[component].coefficients[position]
+    }
+
+    /// Replace quantized coefficient at given index
+    pub fn set_coefficient(&mut self, index: usize, value: i32) {
+        let (component, position) = self.get_position(index);
+        self.components[component].coefficients[position] = value;
+    }
+}
+
+/// Read Huffman tables of a DHT segment, table slots being class * 4 + id
+fn read_huffman_tables(segment: &[u8], tables: &mut [Option<HuffmanTable>]) -> Result<(), Error> {
+    let mut position = 0;
+
+    while position < segment.len() {
+        let slot = match segment[position] {
+            info if info >> 4 < 2 && info & 15 < 4 => ((info >> 4) * 4 + (info & 15)) as usize,
+            _ => return Err(format_error("Invalid Huffman table!")),
+        };
+
+        let mut counts = [0u8; MAX_CODE_LENGTH];
+        counts.copy_from_slice(
+            segment
+                .get(position + 1..position + 1 + MAX_CODE_LENGTH)
+                .ok_or_else(|| format_error("Truncated Huffman table!"))?,
+        );
+        let total: usize = counts.iter().map(|&count| count as usize).sum();
+        position += 1 + MAX_CODE_LENGTH;
+
+        let symbols = segment
+            .get(position..position + total)
+            .ok_or_else(|| format_error("Truncated Huffman table!"))?
+            .to_vec();
+        position += total;
+
+        tables[slot] = Some(HuffmanTable { counts, symbols });
+    }
+
+    Ok(())
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extend_and_magnitude_bits() {
+        for value in -300..300 {
+            let category = get_category(value);
+            assert_eq!(extend(get_magnitude_bits(value, category), category), value);
+        }
+    }
+
+    #[test]
+    fn test_huffman_table_from_frequencies() {
+        let mut frequencies = [0usize; 256];
+        for (symbol, frequency) in frequencies.iter_mut().enumerate() {
+            *frequency = (symbol * 7919) % 1000 + 1;
+        }
+        frequencies[3] = 1_000_000;
+
+        let table = HuffmanTable::from_frequencies(&frequencies);
+        let codes = table.get_codes();
+
+        assert_eq!(table.symbols.len(), 256);
+        assert!(codes
+            .iter()
+            .all(|code| code.is_some_and(|(_, length)| length <= 16)));
+        assert_eq!(table.symbols[0], 3);
+
+        // No code may be the prefix of another one, nor be all ones
+        let mut decoder_input = Vec::<u8>::new();
+        let mut writer = BitWriter::new();
+        for code in codes.iter() {
+            let (code, length) = code.unwrap_or((0, 0));
+            assert_ne!(code as u32, (1 << length) - 1);
+            writer.write_bits(code as u32, length);
+        }
+        writer.flush();
+        decoder_input.append(&mut writer.data);
+
+        let decoder = HuffmanDecoder::new(&table);
+        let mut reader = BitReader::new(&decoder_input);
+        for symbol in 0..256 {
+            assert_eq!(decoder.decode(&mut reader).ok(), Some(symbol as u8));
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Module handling JPEG pictures, in the DCT domain
+//! Messages are hidden in non-zero AC coefficients with F5, walked in a password-derived order,
+//! so that they survive the file being written back
+use std::io::{Error, ErrorKind};
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+
+use super::binary::*;
+use super::carrier::frame_message;
+use super::cypher::*;
+use super::dot_matrix::{check_message_and_password, WipeOptions};
+use super::f5;
+use super::jpeg::Jpeg;
+use super::sealing::{PasswordSealing, Sealing};
+
+/// Header size : magic number, then matrix encoding parameter k
+const HEADER_BYTES: usize = 3;
+
+/// Magic number telling the header is valid for the given password
+const HEADER_MAGIC: u16 = 0x5354;
+
+/// Key derivation context of the header mask
+const HEADER_CONTEXT: &str = "stegano/jpeg/header";
+
+/// Key derivation context of the coefficients walk
+const WALK_CONTEXT: &str = "stegano/jpeg/walk";
+
+/// Number of bits used to store the encrypted message length
+const LENGTH_BITS: usize = 32;
+
+/// Highest matrix encoding parameter : 7 bits in 127 coefficients
+const MAX_K: usize = 7;
+
+/// Basic structure : a JPEG file and a filepath
+pub struct JpegMatrix {
+    jpeg: Result<Jpeg, Error>,
+    input_filepath: String,
+}
+
+impl JpegMatrix {
+    /// Constructor
+    pub fn new(filepath: &str) -> JpegMatrix {
+        JpegMatrix {
+            input_filepath: filepath.to_string(),
+            jpeg: Jpeg::open(filepath),
+        }
+    }
+
+    /// Returns true if file at given path is a JPEG one
+    pub fn is_jpeg_file(filepath: &str) -> bool {
+        let mut signature = [0u8; 3];
+        File::open(filepath)
+            .and_then(|mut file| file.read_exact(&mut signature))
+            .is_ok()
+            && Jpeg::is_jpeg(&signature)
+    }
+
+    /// Accessor returning picture dimensions as a tuple pixel
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        if let Ok(ref jpeg) = self.jpeg {
+            jpeg.dimensions()
+        } else {
+            (0, 0)
+        }
+    }
+
+    /// Accessor returning picture's filepath
+    pub fn get_input_filepath(&self) -> String {
+        self.input_filepath.clone()
+    }
+
+    /// Function to write the picture into target file
+    /// Quantization tables and coefficients are written as they are, without any re-compression
+    pub fn write_to_file(&self, filepath: &str) -> Result<(), Error> {
+        if let Ok(ref jpeg) = self.jpeg {
+            jpeg.save(filepath)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/write_to_file : Unable to open inner image!",
+            ))
+        }
+    }
+
+    /// Returns inner JPEG file
+    fn get_jpeg(&self) -> Result<&Jpeg, Error> {
+        match self.jpeg {
+            Ok(ref jpeg) => Ok(jpeg),
+            Err(_) => Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/get_jpeg : Unable to open inner image!",
+            )),
+        }
+    }
+
+    /// Returns AC coefficients indexes, in the password-derived walk order
+    fn get_walk(&self, password: &str) -> Result<Vec<usize>, Error> {
+        let jpeg = self.get_jpeg()?;
+        let mut walk: Vec<usize> = (0..jpeg.get_coefficients_count())
+            .filter(|&index| jpeg.is_ac(index))
+            .collect();
+
+        KeyStream::from_password(password, WALK_CONTEXT).shuffle(&mut walk);
+        Ok(walk)
+    }
+
+    /// Returns header bits, masked with password-derived bits
+    fn get_masked_header(password: &str, header: &[u8]) -> Vec<bool> {
+        let mask = derive_key(password, HEADER_CONTEXT);
+        let masked: Vec<u8> = header
+            .iter()
+            .zip(mask.iter())
+            .map(|(byte, mask)| byte ^ mask)
+            .collect();
+
+        convert_byte_vec_to_bit_array(&convert_u8_vec_to_byte_array(&masked))
+    }
+
+    /// Encode given file in self image
+    pub fn encode_file(&mut self, filepath: &str, password: &str) -> Result<(), Error> {
+        let input_file = File::open(filepath)?;
+        let mut buf_reader = BufReader::new(input_file);
+        let mut contents = String::new();
+        buf_reader.read_to_string(&mut contents)?;
+        self.encode(contents.as_str(), password)
+    }
+
+    /// Encode given message in self image
+    pub fn encode(&mut self, message: &str, password: &str) -> Result<(), Error> {
+        self.encode_sealed(message, &PasswordSealing::new(password))
+    }
+
+    /// Encode given message in self image, sealed with given method
+    /// Highest matrix encoding parameter the picture can afford is used, to change fewer coefficients
+    pub fn encode_sealed(&mut self, message: &str, sealing: &dyn Sealing) -> Result<(), Error> {
+        let password = &sealing.get_carrier_key();
+        check_message_and_password(message, password)?;
+
+        let vector = frame_message(&sealing.seal(message)?);
+        let walk = self.get_walk(password)?;
+        let jpeg = self.get_jpeg()?;
+        let coefficients: Vec<i32> = walk
+            .iter()
+            .map(|&index| jpeg.get_coefficient(index))
+            .collect();
+
+        // Header goes first with k = 1, then the message with the chosen k
+        let stego = (1..=MAX_K).rev().find_map(|k| {
+            let magic = HEADER_MAGIC.to_be_bytes();
+            let header = Self::get_masked_header(password, &[magic[0], magic[1], k as u8]);
+            let mut stego = coefficients.clone();
+
+            f5::embed(&mut stego, 0, &header, 1)
+                .and_then(|cursor| f5::embed(&mut stego, cursor, &vector, k))
+                .map(|_| stego)
+        });
+
+        let stego = stego.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "stegano/encode : Input file not big enough to store message!",
+            )
+        })?;
+
+        if let Ok(ref mut jpeg) = self.jpeg {
+            for (&index, &coefficient) in walk.iter().zip(stego.iter()) {
+                jpeg.set_coefficient(index, coefficient);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove any hidden payload : bit held by every non-zero AC coefficient is overwritten
+    /// with a random one, in the DCT domain, so that the picture is not re-compressed
+    /// F5 hides a single bit per coefficient, hence no other number of low bits
+    pub fn wipe(&mut self, options: &WipeOptions) -> Result<(), Error> {
+        if options.low_bits.is_some_and(|low_bits| low_bits != 1) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/wipe : Number of low bits must be 1 for JPEG pictures",
+            ));
+        }
+
+        let jpeg = match self.jpeg {
+            Ok(ref mut jpeg) => jpeg,
+            Err(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "stegano/wipe : Unable to open inner image!",
+                ))
+            }
+        };
+        let walk: Vec<usize> = (0..jpeg.get_coefficients_count())
+            .filter(|&index| jpeg.is_ac(index))
+            .collect();
+        let mut coefficients: Vec<i32> = walk
+            .iter()
+            .map(|&index| jpeg.get_coefficient(index))
+            .collect();
+
+        f5::randomize(&mut coefficients);
+        for (&index, &coefficient) in walk.iter().zip(coefficients.iter()) {
+            jpeg.set_coefficient(index, coefficient);
+        }
+
+        Ok(())
+    }
+
+    /// Decodes image and write result file
+    pub fn decode_and_write(&self, filepath: &str, password: &str) -> Result<(), Error> {
+        let decoded_string = &self.decode(password)?;
+        let mut output_file = File::create(filepath)?;
+        output_file.write_all(decoded_string.as_bytes())
+    }
+
+    /// Decodes image and return result string
+    pub fn decode(&self, password: &str) -> Result<String, Error> {
+        self.decode_sealed(&PasswordSealing::new(password))
+    }
+
+    /// Decodes image sealed with given method and return result string
+    pub fn decode_sealed(&self, sealing: &dyn Sealing) -> Result<String, Error> {
+        let password = &sealing.get_carrier_key();
+        if !is_one_byte_chars_message(password) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Input password must be 1 byte chars",
+            ));
+        }
+
+        let nothing_hidden = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                "stegano/decode : Nothing hidden in this file!",
+            )
+        };
+
+        let walk = self.get_walk(password)?;
+        let jpeg = self.get_jpeg()?;
+        let coefficients: Vec<i32> = walk
+            .iter()
+            .map(|&index| jpeg.get_coefficient(index))
+            .collect();
+
+        // Read header
+        let (masked, cursor) =
+            f5::extract(&coefficients, 0, HEADER_BYTES * 8, 1).ok_or_else(nothing_hidden)?;
+        let unmasked: Vec<bool> = masked
+            .iter()
+            .zip(Self::get_masked_header(password, &[0; HEADER_BYTES]))
+            .map(|(bit, mask)| bit ^ mask)
+            .collect();
+        let header = convert_bit_array_to_u8_vec(&unmasked)?;
+        let k = header[2] as usize;
+
+        if u16::from_be_bytes([header[0], header[1]]) != HEADER_MAGIC || k == 0 || k > MAX_K {
+            return Err(nothing_hidden());
+        }
+
+        // Then message length, and message itself
+        let (length_vector, _) =
+            f5::extract(&coefficients, cursor, LENGTH_BITS, k).ok_or_else(nothing_hidden)?;
+        let length = convert_bit_array_to_u32(&length_vector)? as usize * 8;
+        let (vector, _) = f5::extract(&coefficients, cursor, LENGTH_BITS + length, k)
+            .ok_or_else(nothing_hidden)?;
+
+        sealing
+            .open(&convert_bit_array_to_u8_vec(&vector[LENGTH_BITS..])?)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::dot_matrix::tests::{get_output_filepath, TEST_FILEPATH};
+    use super::*;
+    use std::process;
+
+    extern crate image;
+
+    /// Write a JPEG version of the reference picture, returning its path
+    fn write_jpeg_picture(filename: &str) -> String {
+        let output_filepath = get_output_filepath(filename);
+
+        image::open(TEST_FILEPATH)
+            .and_then(|picture| picture.save(&output_filepath).map_err(From::from))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in write_jpeg_picture: {}", err);
+                process::exit(1);
+            });
+
+        output_filepath
+    }
+
+    #[test]
+    fn test_rewrite_is_lossless() {
+        let input_filepath = write_jpeg_picture("test_jpeg.jpg");
+        let output_filepath = get_output_filepath("test_jpeg_rewritten.jpg");
+
+        let matrix = JpegMatrix::new(&input_filepath);
+        matrix
+            .write_to_file(&output_filepath)
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_rewrite_is_lossless: {}", err);
+                process::exit(1);
+            });
+
+        // Decoded pixels must be exactly the same
+        let decode = |filepath: &str| match image::open(filepath) {
+            Ok(picture) => picture.to_rgb().into_raw(),
+            Err(_) => Vec::new(),
+        };
+
+        let original = decode(&input_filepath);
+        assert!(!original.is_empty());
+        assert!(original == decode(&output_filepath));
+    }
+
+    #[test]
+    fn test_global_jpeg() {
+        let input_filepath = write_jpeg_picture("test_jpeg_f5.jpg");
+        let output_filepath = get_output_filepath("test_jpeg_f5_stego.jpg");
+
+        let mut matrix = JpegMatrix::new(&input_filepath);
+        matrix
+            .encode("Hello how is the weather today", "Password")
+            .and_then(|_| matrix.write_to_file(&output_filepath))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global_jpeg: {}", err);
+                process::exit(1);
+            });
+
+        let matrix2 = JpegMatrix::new(&output_filepath);
+        let res = matrix2.decode("Password").unwrap_or_else(|err| {
+            eprintln!("Error in test_global_jpeg: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(res, "Hello how is the weather today".to_string());
+        assert!(matrix2.decode("Wrong password").is_err());
+        assert!(JpegMatrix::new(&input_filepath).decode("Password").is_err());
+    }
+
+    #[test]
+    fn test_global_wipe() {
+        let input_filepath = write_jpeg_picture("test_jpeg_wipe.jpg");
+        let output_filepath = get_output_filepath("test_jpeg_wiped.jpg");
+
+        let mut matrix = JpegMatrix::new(&input_filepath);
+        matrix
+            .encode("Hello how is the weather today", "Password")
+            .and_then(|_| matrix.wipe(&WipeOptions::default()))
+            .and_then(|_| matrix.write_to_file(&output_filepath))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global_wipe: {}", err);
+                process::exit(1);
+            });
+
+        assert!(JpegMatrix::new(&output_filepath)
+            .decode("Password")
+            .is_err());
+        assert!(matrix.wipe(&WipeOptions { low_bits: Some(2) }).is_err());
+    }
+}
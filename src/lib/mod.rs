@@ -1,3 +1,26 @@
+pub mod animation;
+pub mod animation_matrix;
 pub mod binary;
+pub mod carrier;
+pub mod container;
 pub mod cypher;
-pub mod dot_matrix;
\ No newline at end of file
+pub mod distortion;
+pub mod dot_matrix;
+pub mod f5;
+pub mod jpeg;
+pub mod jpeg_matrix;
+pub mod palette;
+pub mod parity;
+pub mod picture;
+pub mod pvd;
+pub mod quality;
+pub mod reversible;
+pub mod sanitize;
+pub mod sealing;
+pub mod shamir;
+pub mod signature;
+pub mod stc;
+pub mod steganalysis;
+pub mod strategy;
+pub mod wav;
+pub mod wav_carrier;
@@ -0,0 +1,135 @@
+//! EzStego : hide bits in palette (indexed-color) pictures, without altering the palette
+//! Palette entries are sorted by luminance, and each pixel holds a bit in the parity of the rank
+//! of its entry : changing a bit swaps the color for one of close luminance
+
+/// Palette entries ranked by luminance
+/// Entries left out of the ranking are never read nor written
+pub struct SortedPalette {
+    sorted: Vec<u8>,
+    ranks: Vec<Option<usize>>,
+}
+
+impl SortedPalette {
+    /// Rank palette entries, darkest first, palette order among equal luminances
+    /// Returns None if the palette has fewer than 2 or more than 256 entries
+    pub fn new(palette: &[[u8; 3]]) -> Option<SortedPalette> {
+        SortedPalette::new_opaque(palette, &[])
+    }
+
+    /// Rank opaque palette entries only, transparency giving the alpha of the first entries
+    /// Pixels of other entries may show what lies behind them, as in animation frames
+    /// Returns None if fewer than 2 entries are opaque, or if there are more than 256 entries
+    pub fn new_opaque(palette: &[[u8; 3]], transparency: &[u8]) -> Option<SortedPalette> {
+        if palette.len() > 256 {
+            return None;
+        }
+
+        let mut sorted: Vec<u8> = (0..palette.len())
+            .filter(|&index| *transparency.get(index).unwrap_or(&255) == 255)
+            .map(|index| index as u8)
+            .collect();
+
+        if sorted.len() < 2 {
+            return None;
+        }
+
+        sorted.sort_by_key(|&index| {
+            let [red, green, blue] = palette[index as usize];
+            299 * red as u32 + 587 * green as u32 + 114 * blue as u32
+        });
+
+        let mut ranks = vec![None; palette.len()];
+        for (rank, &index) in sorted.iter().enumerate() {
+            ranks[index as usize] = Some(rank);
+        }
+
+        Some(SortedPalette { sorted, ranks })
+    }
+
+    /// Returns rank of palette entry, indexes falling off the ranking being read as the darkest
+    fn get_rank(&self, index: u8) -> usize {
+        self.ranks
+            .get(index as usize)
+            .cloned()
+            .flatten()
+            .unwrap_or(0)
+    }
+
+    /// Returns true if palette entry is ranked, and so may hold a bit
+    pub fn is_ranked(&self, index: u8) -> bool {
+        self.ranks.get(index as usize).is_some_and(Option::is_some)
+    }
+
+    /// Returns bit held by palette entry
+    pub fn get_bit(&self, index: u8) -> bool {
+        self.get_rank(index) % 2 == 1
+    }
+
+    /// Returns palette entry holding given bit : the entry itself, or its luminance neighbour
+    pub fn store_bit(&self, index: u8, bit: bool) -> u8 {
+        let rank = self.get_rank(index);
+
+        if (rank % 2 == 1) == bit {
+            index
+        } else if rank ^ 1 < self.sorted.len() {
+            self.sorted[rank ^ 1]
+        } else {
+            // Last entry of an odd-sized palette has no pair, use the previous one
+            self.sorted[rank - 1]
+        }
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted_palette_ranks() {
+        let palette = [[255, 255, 255], [0, 0, 0], [200, 0, 0], [0, 200, 0]];
+        let sorted = SortedPalette::new(&palette).expect("Palette must be usable");
+
+        assert_eq!(sorted.sorted, vec![1, 2, 3, 0]);
+        assert!(!sorted.get_bit(1));
+        assert!(sorted.get_bit(2));
+        assert!(SortedPalette::new(&palette[..1]).is_none());
+    }
+
+    #[test]
+    fn test_opaque_entries_only() {
+        let palette = [[10, 10, 10], [20, 20, 20], [30, 30, 30], [40, 40, 40]];
+        let sorted =
+            SortedPalette::new_opaque(&palette, &[255, 0]).expect("Palette must be usable");
+
+        assert!(!sorted.is_ranked(1));
+        assert!(sorted.is_ranked(3));
+        for &index in &[0, 2, 3] {
+            for &bit in &[false, true] {
+                assert_ne!(sorted.store_bit(index, bit), 1);
+            }
+        }
+        assert!(SortedPalette::new_opaque(&palette, &[0, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn test_store_bit() {
+        let palette = [
+            [10, 10, 10],
+            [20, 20, 20],
+            [30, 30, 30],
+            [40, 40, 40],
+            [50, 50, 50],
+        ];
+        let sorted = SortedPalette::new(&palette).expect("Palette must be usable");
+
+        for index in 0..palette.len() as u8 {
+            for &bit in &[false, true] {
+                let stored = sorted.store_bit(index, bit);
+
+                assert_eq!(sorted.get_bit(stored), bit);
+                assert!((stored as i32 - index as i32).abs() <= 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! Payloads spread over several pictures, with parity chunks : a payload cut into data chunks
+//! gets as many parity chunks as pictures which may be lost, any data chunks count of them
+//! rebuilding it (Reed-Solomon erasure coding over GF(256), with a Cauchy matrix)
+//!
+//! Chunks are written as text, to be hidden in several cover pictures like any message
+extern crate crypto;
+
+use self::crypto::{digest::Digest, sha2::Sha256};
+
+use std::{
+    fmt,
+    io::{Error, ErrorKind},
+    str::FromStr,
+};
+
+use super::sealing::{hex_to_bytes, to_hex};
+use super::shamir::{gf_inv, gf_mul, random_id, to_id, ID_BYTES};
+
+/// First line of chunks
+const CHUNK_MAGIC: &str = "STEGANO-CHUNK";
+
+/// One chunk of a payload, data or parity one
+/// Chunks of the same payload have the same header, and distinct indexes,
+/// data chunks coming first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub id: [u8; ID_BYTES],
+    pub data_count: u8,
+    pub parity_count: u8,
+    pub index: u8,
+    pub length: u32,
+    pub check: [u8; ID_BYTES],
+    pub data: Vec<u8>,
+}
+
+impl Chunk {
+    /// Returns true if chunk holds parity, false if it holds a part of the payload
+    pub fn is_parity(&self) -> bool {
+        self.index >= self.data_count
+    }
+
+    /// Returns true if both chunks belong to the same payload
+    fn is_sibling(&self, other: &Chunk) -> bool {
+        (
+            self.id,
+            self.data_count,
+            self.parity_count,
+            self.length,
+            self.check,
+        ) == (
+            other.id,
+            other.data_count,
+            other.parity_count,
+            other.length,
+            other.check,
+        ) && self.data.len() == other.data.len()
+    }
+}
+
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            CHUNK_MAGIC,
+            to_hex(&self.id),
+            self.data_count,
+            self.parity_count,
+            self.index,
+            self.length,
+            to_hex(&self.check),
+            to_hex(&self.data)
+        )
+    }
+}
+
+impl FromStr for Chunk {
+    type Err = Error;
+
+    /// Parse a chunk, as written by Display
+    fn from_str(text: &str) -> Result<Chunk, Error> {
+        let invalid_chunk = || Error::new(ErrorKind::InvalidData, "stegano/chunk : Invalid chunk!");
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.len() != 8 || lines[0] != CHUNK_MAGIC {
+            return Err(invalid_chunk());
+        }
+
+        let chunk = Chunk {
+            id: to_id(lines[1]).ok_or_else(invalid_chunk)?,
+            data_count: lines[2].parse().map_err(|_| invalid_chunk())?,
+            parity_count: lines[3].parse().map_err(|_| invalid_chunk())?,
+            index: lines[4].parse().map_err(|_| invalid_chunk())?,
+            length: lines[5].parse().map_err(|_| invalid_chunk())?,
+            check: to_id(lines[6]).ok_or_else(invalid_chunk)?,
+            data: hex_to_bytes(lines[7]).ok_or_else(invalid_chunk)?,
+        };
+
+        if chunk.data_count == 0 || chunk.index as usize >= get_total(&chunk) {
+            return Err(invalid_chunk());
+        }
+        Ok(chunk)
+    }
+}
+
+/// Returns check of a payload : first bytes of the SHA-256 digest of its identifier and itself
+fn get_check(id: &[u8; ID_BYTES], payload: &[u8]) -> [u8; ID_BYTES] {
+    let mut digest = [0u8; 32];
+    let mut hasher = Sha256::new();
+    hasher.input(id);
+    hasher.input(payload);
+    hasher.result(&mut digest);
+
+    let mut check = [0u8; ID_BYTES];
+    check.copy_from_slice(&digest[..ID_BYTES]);
+    check
+}
+
+/// Returns chunks count of the payload given chunk belongs to
+fn get_total(chunk: &Chunk) -> usize {
+    chunk.data_count as usize + chunk.parity_count as usize
+}
+
+/// Returns coefficients of given chunk over data chunks : unit row for data chunks,
+/// Cauchy matrix row for parity chunks, any data chunks count of rows being invertible
+fn get_row(index: u8, data_count: u8) -> Vec<u8> {
+    (0..data_count)
+        .map(|column| {
+            if index >= data_count {
+                gf_inv(index ^ column)
+            } else if column == index {
+                1
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+/// Invert a square matrix over GF(256), Gauss-Jordan elimination
+fn invert(mut matrix: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+    let size = matrix.len();
+    let mut inverse: Vec<Vec<u8>> = (0..size as u8)
+        .map(|row| get_row(row, size as u8))
+        .collect();
+
+    for column in 0..size {
+        let pivot = (column..size).find(|&row| matrix[row][column] != 0)?;
+        matrix.swap(column, pivot);
+        inverse.swap(column, pivot);
+
+        let factor = gf_inv(matrix[column][column]);
+        for value in matrix[column].iter_mut().chain(inverse[column].iter_mut()) {
+            *value = gf_mul(*value, factor);
+        }
+
+        for row in (0..size).filter(|&row| row != column) {
+            let factor = matrix[row][column];
+            for index in 0..size {
+                matrix[row][index] ^= gf_mul(factor, matrix[column][index]);
+                inverse[row][index] ^= gf_mul(factor, inverse[column][index]);
+            }
+        }
+    }
+
+    Some(inverse)
+}
+
+/// Cut payload into data chunks, then add parity chunks, so that it survives the loss
+/// of as many chunks as parity ones
+pub fn spread(payload: &[u8], data_count: u8, parity_count: u8) -> Result<Vec<Chunk>, Error> {
+    if data_count == 0 || data_count as usize + parity_count as usize > u8::MAX as usize {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "stegano/spread : Payload takes 1 to 255 chunks, parity ones included!",
+        ));
+    }
+
+    // Data chunks are padded with zeros to the same size
+    let size = payload.len().div_ceil(data_count as usize).max(1);
+    let mut data = payload.to_vec();
+    data.resize(size * data_count as usize, 0);
+
+    let id = random_id()?;
+    let check = get_check(&id, payload);
+    Ok((0..data_count + parity_count)
+        .map(|index| Chunk {
+            id,
+            data_count,
+            parity_count,
+            index,
+            length: payload.len() as u32,
+            check,
+            data: (0..size)
+                .map(|position| {
+                    get_row(index, data_count)
+                        .iter()
+                        .zip(data.chunks(size))
+                        .fold(0, |byte, (&coefficient, chunk)| {
+                            byte ^ gf_mul(coefficient, chunk[position])
+                        })
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+/// Rebuild payload from its chunks, duplicates being ignored
+/// Returns payload and indexes of the data chunks rebuilt from parity ones
+/// Fails if chunks come from different payloads, or if fewer than data chunks count are given
+pub fn gather(chunks: &[Chunk]) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let first = chunks
+        .first()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "stegano/gather : No chunk given!"))?;
+
+    let mut unique_chunks: Vec<&Chunk> = Vec::new();
+    for chunk in chunks {
+        if !chunk.is_sibling(first) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/gather : Chunks come from different payloads!",
+            ));
+        }
+        if unique_chunks
+            .iter()
+            .all(|unique| unique.index != chunk.index)
+        {
+            unique_chunks.push(chunk);
+        }
+    }
+
+    let data_count = first.data_count as usize;
+    if unique_chunks.len() < data_count {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "stegano/gather : Only {} chunks, {} needed!",
+                unique_chunks.len(),
+                data_count
+            ),
+        ));
+    }
+
+    // Data chunks first, parity ones only standing in for missing data chunks
+    unique_chunks.sort_by_key(|chunk| chunk.index);
+    let chunks = &unique_chunks[..data_count];
+    let rebuilt: Vec<u8> = (0..first.data_count)
+        .filter(|&index| chunks.iter().all(|chunk| chunk.index != index))
+        .collect();
+
+    let inverse = invert(
+        chunks
+            .iter()
+            .map(|chunk| get_row(chunk.index, first.data_count))
+            .collect(),
+    )
+    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "stegano/gather : Invalid chunks!"))?;
+
+    let mut payload: Vec<u8> = inverse
+        .iter()
+        .flat_map(|row| {
+            (0..first.data.len()).map(move |position| {
+                row.iter()
+                    .zip(chunks)
+                    .fold(0, |byte, (&coefficient, chunk)| {
+                        byte ^ gf_mul(coefficient, chunk.data[position])
+                    })
+            })
+        })
+        .collect();
+    payload.truncate(first.length as usize);
+
+    if get_check(&first.id, &payload) != first.check {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "stegano/gather : Rebuilt payload does not match its check, a chunk is corrupted!",
+        ));
+    }
+    Ok((payload, rebuilt))
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn test_spread_and_gather() {
+        let payload = b"Hello how is the weather today, and tomorrow ?";
+        let chunks = spread(payload, 4, 2).unwrap_or_else(|err| {
+            eprintln!("Error in test_spread_and_gather: {}", err);
+            process::exit(1);
+        });
+        assert_eq!(chunks.len(), 6);
+        assert_eq!(chunks.iter().filter(|chunk| chunk.is_parity()).count(), 2);
+
+        // Any 4 chunks rebuild the payload, once written as text and parsed back
+        for (picked, rebuilt) in &[
+            (vec![0, 1, 2, 3], vec![]),
+            (vec![5, 1, 4, 3], vec![0, 2]),
+            (vec![0, 2, 3, 5, 5], vec![1]),
+        ] {
+            let picked: Vec<Chunk> = picked
+                .iter()
+                .map(|&index: &usize| chunks[index].to_string().parse::<Chunk>())
+                .collect::<Result<Vec<Chunk>, Error>>()
+                .unwrap_or_else(|err| {
+                    eprintln!("Error in test_spread_and_gather: {}", err);
+                    process::exit(1);
+                });
+            assert_eq!(
+                gather(&picked).ok(),
+                Some((payload.to_vec(), rebuilt.clone()))
+            );
+        }
+
+        // Too few or corrupted chunks are refused
+        assert!(gather(&chunks[2..5]).is_err());
+        let mut corrupted = chunks[4].clone();
+        corrupted.data[0] ^= 1;
+        assert!(gather(&[
+            chunks[0].clone(),
+            chunks[1].clone(),
+            chunks[3].clone(),
+            corrupted
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,751 @@
+//! Picture samples, kept in their original color type and bit depth
+//! PNG files are read and written through the png crate, so that grayscale, 1 to 4 bits
+//! and 16 bits pictures are never converted, and their ancillary chunks are written back
+//! as they were ; other formats go through the image crate
+//! Palette pictures (GIF and indexed PNG) keep their palette and their indexed format
+extern crate gif;
+extern crate image;
+extern crate png;
+
+use self::gif::SetParameter;
+use self::image::{DynamicImage, ImageBuffer};
+
+use std::{
+    borrow::Cow,
+    fs,
+    fs::File,
+    io::{BufWriter, Error, ErrorKind, Read, Write},
+    path::Path,
+};
+
+/// PNG file signature
+pub const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// GIF file signature, version excluded
+pub const GIF_SIGNATURE: [u8; 4] = [71, 73, 70, 56];
+
+/// PNG chunks written from the picture itself, every other chunk being kept as it is
+const REBUILT_CHUNKS: [&[u8; 4]; 3] = [b"IHDR", b"IDAT", b"IEND"];
+
+/// PNG chunk : type and data
+pub type Chunk = ([u8; 4], Vec<u8>);
+
+/// Color types handled, alpha channel is never used to hide data
+/// Indexed pictures hold one palette index per pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorType {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+    Indexed,
+}
+
+impl ColorType {
+    /// Returns number of samples per pixel, alpha included
+    pub fn get_samples_per_pixel(self) -> usize {
+        match self {
+            ColorType::Gray | ColorType::Indexed => 1,
+            ColorType::GrayAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    /// Returns number of color samples per pixel, alpha excluded
+    pub fn get_color_channels(self) -> usize {
+        match self {
+            ColorType::Gray | ColorType::GrayAlpha | ColorType::Indexed => 1,
+            ColorType::Rgb | ColorType::Rgba => 3,
+        }
+    }
+}
+
+/// Pixels samples, row after row, alpha included
+/// Palette and palette transparency are only used by indexed pictures
+/// PNG chunks found before and after image data are written back around it
+#[derive(Clone)]
+pub struct Picture {
+    width: u32,
+    height: u32,
+    color_type: ColorType,
+    bit_depth: u8,
+    samples: Vec<u16>,
+    palette: Vec<[u8; 3]>,
+    transparency: Vec<u8>,
+    chunks_before_data: Vec<Chunk>,
+    chunks_after_data: Vec<Chunk>,
+}
+
+impl Picture {
+    /// Constructor
+    /// Grayscale pictures may have 1, 2 or 4 bits samples, other ones 8 or 16 bits samples
+    pub fn new(
+        width: u32,
+        height: u32,
+        color_type: ColorType,
+        bit_depth: u8,
+        samples: Vec<u16>,
+    ) -> Result<Picture, Error> {
+        let is_low_bit_depth = [1, 2, 4].contains(&bit_depth) && color_type == ColorType::Gray;
+        if samples.len() != (width * height) as usize * color_type.get_samples_per_pixel()
+            || (bit_depth != 8 && bit_depth != 16 && !is_low_bit_depth)
+            || color_type == ColorType::Indexed
+            || samples
+                .iter()
+                .any(|&sample| sample as u32 >= 1 << bit_depth)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/picture : Samples do not match picture dimensions!",
+            ));
+        }
+
+        Ok(Picture {
+            width,
+            height,
+            color_type,
+            bit_depth,
+            samples,
+            palette: Vec::new(),
+            transparency: Vec::new(),
+            chunks_before_data: Vec::new(),
+            chunks_after_data: Vec::new(),
+        })
+    }
+
+    /// Constructor for palette pictures
+    /// Transparency gives the alpha of the first palette entries, the other ones being opaque
+    pub fn new_indexed(
+        width: u32,
+        height: u32,
+        palette: Vec<[u8; 3]>,
+        transparency: Vec<u8>,
+        indexes: Vec<u8>,
+    ) -> Result<Picture, Error> {
+        if indexes.len() != (width * height) as usize
+            || palette.is_empty()
+            || palette.len() > 256
+            || transparency.len() > palette.len()
+            || indexes.iter().any(|&index| index as usize >= palette.len())
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/picture : Indexes do not match picture palette!",
+            ));
+        }
+
+        Ok(Picture {
+            width,
+            height,
+            color_type: ColorType::Indexed,
+            bit_depth: 8,
+            samples: indexes.iter().map(|&index| index as u16).collect(),
+            palette,
+            transparency,
+            chunks_before_data: Vec::new(),
+            chunks_after_data: Vec::new(),
+        })
+    }
+
+    /// Read picture from file
+    pub fn open(filepath: &str) -> Result<Picture, Error> {
+        let mut signature: [u8; 8] = [0; 8];
+        let is_read = File::open(filepath)?.read_exact(&mut signature).is_ok();
+
+        if is_read && signature == PNG_SIGNATURE {
+            Picture::from_png_bytes(&fs::read(filepath)?)
+        } else if is_read && signature[..4] == GIF_SIGNATURE {
+            Picture::open_gif(filepath)
+        } else {
+            match image::open(filepath) {
+                Ok(image) => Picture::from_dynamic_image(image),
+                Err(err) => Err(Error::new(ErrorKind::InvalidData, err.to_string())),
+            }
+        }
+    }
+
+    /// Read PNG file contents, keeping its color type, bit depth and ancillary chunks
+    pub fn from_png_bytes(bytes: &[u8]) -> Result<Picture, Error> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+
+        let (info, mut reader) = decoder.read_info()?;
+        let mut buffer = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buffer)?;
+
+        // Samples packed on less than 8 bits are unpacked
+        let bits = info.bit_depth as usize;
+        let samples_per_row = info.width as usize * info.color_type.samples();
+        let samples: Vec<u16> = buffer
+            .chunks(info.line_size)
+            .take(info.height as usize)
+            .flat_map(|row| {
+                (0..samples_per_row).map(move |x| match bits {
+                    16 => u16::from_be_bytes([row[2 * x], row[2 * x + 1]]),
+                    8 => row[x] as u16,
+                    _ => {
+                        let shift = 8 - bits - (x * bits) % 8;
+                        ((row[x * bits / 8] >> shift) & ((1 << bits) - 1) as u8) as u16
+                    }
+                })
+            })
+            .collect();
+
+        let mut picture = match info.color_type {
+            png::ColorType::Indexed => {
+                let palette = reader
+                    .info()
+                    .palette
+                    .clone()
+                    .unwrap_or_default()
+                    .chunks(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                    .collect();
+
+                // Indexes packed on less than 8 bits are written back as they were
+                Picture::new_indexed(
+                    info.width,
+                    info.height,
+                    palette,
+                    reader.info().trns.clone().unwrap_or_default(),
+                    samples.iter().map(|&index| index as u8).collect(),
+                )
+                .map(|picture| Picture {
+                    bit_depth: bits as u8,
+                    ..picture
+                })
+            }
+            color_type => Picture::new(
+                info.width,
+                info.height,
+                match color_type {
+                    png::ColorType::Grayscale => ColorType::Gray,
+                    png::ColorType::GrayscaleAlpha => ColorType::GrayAlpha,
+                    png::ColorType::RGB => ColorType::Rgb,
+                    _ => ColorType::Rgba,
+                },
+                bits as u8,
+                samples,
+            ),
+        }?;
+
+        // Palette and its transparency are written from the picture itself, where they were
+        let mut is_data_read = false;
+        for (chunk_type, data) in read_chunks(bytes)? {
+            if &chunk_type == b"IDAT" {
+                is_data_read = true;
+            } else if !REBUILT_CHUNKS.contains(&&chunk_type) {
+                let chunks = if is_data_read {
+                    &mut picture.chunks_after_data
+                } else {
+                    &mut picture.chunks_before_data
+                };
+                chunks.push((chunk_type, data));
+            }
+        }
+
+        Ok(picture)
+    }
+
+    /// Read first frame of GIF file, keeping its palette
+    fn open_gif(filepath: &str) -> Result<Picture, Error> {
+        let to_error =
+            |err: gif::DecodingError| Error::new(ErrorKind::InvalidData, err.to_string());
+
+        let mut decoder = gif::Decoder::new(File::open(filepath)?);
+        decoder.set(gif::ColorOutput::Indexed);
+        let mut reader = decoder.read_info().map_err(to_error)?;
+
+        let global_palette = reader.global_palette().map(|palette| palette.to_vec());
+        let frame = reader.read_next_frame().map_err(to_error)?.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "stegano/picture : No frame in GIF file!",
+            )
+        })?;
+
+        let palette = frame
+            .palette
+            .clone()
+            .or(global_palette)
+            .unwrap_or_default()
+            .chunks(3)
+            .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+            .collect();
+
+        // Transparent entry is the only one having an alpha
+        let transparency = match frame.transparent {
+            Some(index) => {
+                let mut transparency = vec![255; index as usize];
+                transparency.push(0);
+                transparency
+            }
+            None => Vec::new(),
+        };
+
+        Picture::new_indexed(
+            frame.width as u32,
+            frame.height as u32,
+            palette,
+            transparency,
+            frame.buffer.to_vec(),
+        )
+    }
+
+    /// Convert image crate picture, always 8 bits
+    fn from_dynamic_image(image: DynamicImage) -> Result<Picture, Error> {
+        let (color_type, width, height, raw) = match image {
+            DynamicImage::ImageLuma8(buffer) => (
+                ColorType::Gray,
+                buffer.width(),
+                buffer.height(),
+                buffer.into_raw(),
+            ),
+            DynamicImage::ImageLumaA8(buffer) => (
+                ColorType::GrayAlpha,
+                buffer.width(),
+                buffer.height(),
+                buffer.into_raw(),
+            ),
+            DynamicImage::ImageRgb8(buffer) => (
+                ColorType::Rgb,
+                buffer.width(),
+                buffer.height(),
+                buffer.into_raw(),
+            ),
+            DynamicImage::ImageBgr8(_) => {
+                let buffer = image.to_rgb();
+                (
+                    ColorType::Rgb,
+                    buffer.width(),
+                    buffer.height(),
+                    buffer.into_raw(),
+                )
+            }
+            _ => {
+                let buffer = image.to_rgba();
+                (
+                    ColorType::Rgba,
+                    buffer.width(),
+                    buffer.height(),
+                    buffer.into_raw(),
+                )
+            }
+        };
+
+        Picture::new(
+            width,
+            height,
+            color_type,
+            8,
+            raw.iter().map(|&sample| sample as u16).collect(),
+        )
+    }
+
+    /// Write picture into file, format being deduced from file extension
+    pub fn save(&self, filepath: &str) -> Result<(), Error> {
+        let has_extension = |expected: &str| {
+            Path::new(filepath)
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case(expected))
+        };
+
+        if has_extension("png") {
+            BufWriter::new(File::create(filepath)?).write_all(&self.to_png_bytes()?)
+        } else if has_extension("gif") && self.color_type == ColorType::Indexed {
+            self.save_gif(filepath)
+        } else if self.bit_depth == 16 {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/picture : 16 bits pictures can only be saved as PNG!",
+            ))
+        } else {
+            self.to_dynamic_image()?
+                .save(filepath)
+                .map_err(|err| Error::new(ErrorKind::InvalidInput, err.to_string()))
+        }
+    }
+
+    /// Returns PNG file contents, with the same color type and bit depth as the input one
+    pub fn to_png_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::<u8>::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+
+        encoder.set_color(match self.color_type {
+            ColorType::Gray => png::ColorType::Grayscale,
+            ColorType::GrayAlpha => png::ColorType::GrayscaleAlpha,
+            ColorType::Rgb => png::ColorType::RGB,
+            ColorType::Rgba => png::ColorType::RGBA,
+            ColorType::Indexed => png::ColorType::Indexed,
+        });
+
+        encoder.set_depth(match self.bit_depth {
+            1 => png::BitDepth::One,
+            2 => png::BitDepth::Two,
+            4 => png::BitDepth::Four,
+            16 => png::BitDepth::Sixteen,
+            _ => png::BitDepth::Eight,
+        });
+
+        // Samples on less than 8 bits are packed, each row starting on a new byte
+        let bits = self.bit_depth as usize;
+        let samples_per_row = self.width as usize * self.color_type.get_samples_per_pixel();
+        let data: Vec<u8> = match bits {
+            16 => self
+                .samples
+                .iter()
+                .flat_map(|sample| sample.to_be_bytes().to_vec())
+                .collect(),
+            8 => self.samples.iter().map(|&sample| sample as u8).collect(),
+            _ => self
+                .samples
+                .chunks(samples_per_row.max(1))
+                .flat_map(|row| {
+                    row.chunks(8 / bits).map(|packed| {
+                        packed.iter().enumerate().fold(0, |byte, (x, &sample)| {
+                            byte | (sample as u8) << (8 - bits - x * bits)
+                        })
+                    })
+                })
+                .collect(),
+        };
+
+        let mut writer = encoder.write_header()?;
+
+        // Palette goes before image data, where it was read from if it was
+        let is_indexed = self.color_type == ColorType::Indexed;
+        let mut is_palette_written = false;
+        for (chunk_type, data) in &self.chunks_before_data {
+            match chunk_type {
+                b"PLTE" | b"tRNS" if is_indexed => {
+                    if !is_palette_written {
+                        self.write_palette(&mut writer)?;
+                        is_palette_written = true;
+                    }
+                }
+                _ => writer.write_chunk(*chunk_type, data)?,
+            }
+        }
+        if is_indexed && !is_palette_written {
+            self.write_palette(&mut writer)?;
+        }
+
+        writer.write_image_data(&data)?;
+        for (chunk_type, data) in &self.chunks_after_data {
+            writer.write_chunk(*chunk_type, data)?;
+        }
+        drop(writer);
+
+        Ok(bytes)
+    }
+
+    /// Write PLTE and tRNS chunks of indexed pictures
+    fn write_palette<W: Write>(&self, writer: &mut png::Writer<W>) -> Result<(), Error> {
+        writer.write_chunk(*b"PLTE", &self.palette.concat())?;
+        if !self.transparency.is_empty() {
+            writer.write_chunk(*b"tRNS", &self.transparency)?;
+        }
+        Ok(())
+    }
+
+    /// Forget PNG chunks read along with the picture, so that none is written back
+    pub fn strip_chunks(&mut self) {
+        self.chunks_before_data.clear();
+        self.chunks_after_data.clear();
+    }
+
+    /// Write GIF file, with the same palette as the input one
+    fn save_gif(&self, filepath: &str) -> Result<(), Error> {
+        if self.width > u16::MAX as u32 || self.height > u16::MAX as u32 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/picture : Picture too big to be saved as GIF!",
+            ));
+        }
+
+        let mut encoder = gif::Encoder::new(
+            BufWriter::new(File::create(filepath)?),
+            self.width as u16,
+            self.height as u16,
+            &self.palette.concat(),
+        )?;
+
+        let indexes: Vec<u8> = self.samples.iter().map(|&index| index as u8).collect();
+        let frame = gif::Frame {
+            width: self.width as u16,
+            height: self.height as u16,
+            transparent: self
+                .transparency
+                .iter()
+                .position(|&alpha| alpha == 0)
+                .map(|index| index as u8),
+            buffer: Cow::Borrowed(&indexes),
+            ..gif::Frame::default()
+        };
+
+        encoder.write_frame(&frame)
+    }
+
+    /// Convert into image crate picture, 8 bits only
+    /// Palette pictures are expanded to RGBA
+    pub fn to_dynamic_image(&self) -> Result<DynamicImage, Error> {
+        if self.color_type == ColorType::Indexed {
+            return ImageBuffer::from_raw(self.width, self.height, self.to_rgba8())
+                .map(DynamicImage::ImageRgba8)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        "stegano/picture : Unable to convert picture!",
+                    )
+                });
+        }
+
+        let raw: Vec<u8> = self
+            .samples
+            .iter()
+            .map(|&sample| self.to_8_bits(sample))
+            .collect();
+        let (width, height) = (self.width, self.height);
+
+        let image = match self.color_type {
+            ColorType::Gray => {
+                ImageBuffer::from_raw(width, height, raw).map(DynamicImage::ImageLuma8)
+            }
+            ColorType::GrayAlpha => {
+                ImageBuffer::from_raw(width, height, raw).map(DynamicImage::ImageLumaA8)
+            }
+            ColorType::Rgb => {
+                ImageBuffer::from_raw(width, height, raw).map(DynamicImage::ImageRgb8)
+            }
+            ColorType::Rgba | ColorType::Indexed => {
+                ImageBuffer::from_raw(width, height, raw).map(DynamicImage::ImageRgba8)
+            }
+        };
+
+        image.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "stegano/picture : Unable to convert picture!",
+            )
+        })
+    }
+
+    /// Returns picture dimensions
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns alpha of the first palette entries of indexed pictures, the other ones being opaque
+    pub fn get_transparency(&self) -> &[u8] {
+        &self.transparency
+    }
+
+    /// Returns picture color type
+    pub fn get_color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    /// Returns palette of indexed pictures, empty for other ones
+    pub fn get_palette(&self) -> &[[u8; 3]] {
+        &self.palette
+    }
+
+    /// Returns number of bits per sample : 8 or 16, 1 to 4 for some grayscale pictures
+    pub fn get_bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    /// Returns highest value a sample can hold
+    pub fn get_max_value(&self) -> u16 {
+        ((1u32 << self.bit_depth) - 1) as u16
+    }
+
+    /// Returns sample scaled to 8 bits
+    fn to_8_bits(&self, sample: u16) -> u8 {
+        if self.bit_depth >= 8 {
+            (sample >> (self.bit_depth - 8)) as u8
+        } else {
+            (sample as u32 * u8::MAX as u32 / self.get_max_value() as u32) as u8
+        }
+    }
+
+    /// Returns number of color samples, alpha excluded
+    pub fn get_color_samples_count(&self) -> usize {
+        (self.width * self.height) as usize * self.color_type.get_color_channels()
+    }
+
+    /// Returns position, within samples, of the given color sample
+    fn get_sample_position(&self, index: usize) -> usize {
+        let channels = self.color_type.get_color_channels();
+        (index / channels) * self.color_type.get_samples_per_pixel() + index % channels
+    }
+
+    /// Returns color sample at given index, color samples being numbered pixel after pixel
+    pub fn get_color_sample(&self, index: usize) -> u16 {
+        self.samples[self.get_sample_position(index)]
+    }
+
+    /// Replace color sample at given index
+    pub fn set_color_sample(&mut self, index: usize, value: u16) {
+        let position = self.get_sample_position(index);
+        self.samples[position] = value;
+    }
+
+    /// Returns all values of a color channel, row after row
+    pub fn get_color_plane(&self, channel: usize) -> Vec<u16> {
+        self.samples
+            .iter()
+            .skip(channel)
+            .step_by(self.color_type.get_samples_per_pixel())
+            .cloned()
+            .collect()
+    }
+
+    /// Returns picture as 8 bits RGBA samples, whatever its color type and bit depth
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let samples_per_pixel = self.color_type.get_samples_per_pixel();
+
+        self.samples
+            .chunks(samples_per_pixel)
+            .flat_map(|pixel| {
+                let pixel: Vec<u8> = pixel
+                    .iter()
+                    .map(|&sample| match self.color_type {
+                        ColorType::Indexed => sample as u8,
+                        _ => self.to_8_bits(sample),
+                    })
+                    .collect();
+                match self.color_type {
+                    ColorType::Gray => vec![pixel[0], pixel[0], pixel[0], 255],
+                    ColorType::GrayAlpha => vec![pixel[0], pixel[0], pixel[0], pixel[1]],
+                    ColorType::Rgb => vec![pixel[0], pixel[1], pixel[2], 255],
+                    ColorType::Rgba => pixel,
+                    ColorType::Indexed => {
+                        let [red, green, blue] = self.palette[pixel[0] as usize];
+                        let alpha = *self.transparency.get(pixel[0] as usize).unwrap_or(&255);
+                        vec![red, green, blue, alpha]
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Split PNG file contents into chunks, signature excluded
+pub fn read_chunks(bytes: &[u8]) -> Result<Vec<Chunk>, Error> {
+    let mut chunks = Vec::new();
+    let mut position = PNG_SIGNATURE.len();
+
+    while position + 12 <= bytes.len() {
+        let length = u32::from_be_bytes([
+            bytes[position],
+            bytes[position + 1],
+            bytes[position + 2],
+            bytes[position + 3],
+        ]) as usize;
+        let end = position + 12 + length;
+        if end > bytes.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/picture : Truncated PNG chunk!",
+            ));
+        }
+
+        let mut chunk_type = [0; 4];
+        chunk_type.copy_from_slice(&bytes[position + 4..position + 8]);
+        chunks.push((chunk_type, bytes[position + 8..end - 4].to_vec()));
+        position = end;
+
+        if &chunk_type == b"IEND" {
+            break;
+        }
+    }
+
+    Ok(chunks)
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn test_color_samples() {
+        let mut picture = Picture::new(2, 1, ColorType::Rgba, 8, vec![1, 2, 3, 4, 5, 6, 7, 8])
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_color_samples: {}", err);
+                process::exit(1);
+            });
+
+        assert_eq!(picture.get_color_samples_count(), 6);
+        assert_eq!(picture.get_color_sample(3), 5);
+
+        picture.set_color_sample(5, 70);
+        assert_eq!(picture.get_color_plane(2), vec![3, 70]);
+        assert_eq!(picture.get_color_plane(3), vec![4, 8]);
+    }
+
+    #[test]
+    fn test_save_open_keeps_format() {
+        let filepath = env::temp_dir()
+            .join("stegano_test_picture_16.png")
+            .to_string_lossy()
+            .to_string();
+
+        let samples: Vec<u16> = (0..24).map(|i| i * 2731).collect();
+        let picture =
+            Picture::new(4, 3, ColorType::GrayAlpha, 16, samples.clone()).unwrap_or_else(|err| {
+                eprintln!("Error in test_save_open_keeps_format: {}", err);
+                process::exit(1);
+            });
+
+        picture.save(&filepath).unwrap_or_else(|err| {
+            eprintln!("Error in test_save_open_keeps_format: {}", err);
+            process::exit(1);
+        });
+
+        let picture2 = Picture::open(&filepath).unwrap_or_else(|err| {
+            eprintln!("Error in test_save_open_keeps_format: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(picture2.get_color_type(), ColorType::GrayAlpha);
+        assert_eq!(picture2.get_bit_depth(), 16);
+        assert_eq!(picture2.samples, samples);
+    }
+
+    #[test]
+    fn test_png_keeps_bit_depth_and_chunks() {
+        // 4 bits grayscale picture, with a transparent gray level and metadata
+        let samples: Vec<u16> = (0..15).collect();
+        let mut picture =
+            Picture::new(5, 3, ColorType::Gray, 4, samples.clone()).unwrap_or_else(|err| {
+                eprintln!("Error in test_png_keeps_bit_depth_and_chunks: {}", err);
+                process::exit(1);
+            });
+        picture.chunks_before_data = vec![
+            (*b"gAMA", 45455u32.to_be_bytes().to_vec()),
+            (*b"tRNS", vec![0, 7]),
+        ];
+        picture.chunks_after_data = vec![(*b"tEXt", b"Comment\0Hello".to_vec())];
+
+        let picture2 = picture
+            .to_png_bytes()
+            .and_then(|bytes| Picture::from_png_bytes(&bytes))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_png_keeps_bit_depth_and_chunks: {}", err);
+                process::exit(1);
+            });
+
+        assert_eq!(picture2.get_color_type(), ColorType::Gray);
+        assert_eq!(picture2.get_bit_depth(), 4);
+        assert_eq!(picture2.samples, samples);
+        assert_eq!(picture2.chunks_before_data, picture.chunks_before_data);
+        assert_eq!(picture2.chunks_after_data, picture.chunks_after_data);
+
+        picture.strip_chunks();
+        assert!(picture.chunks_before_data.is_empty() && picture.chunks_after_data.is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+//! Pixel-value differencing : hide bits in the difference between two adjacent pixel values
+//! The larger the difference (edges), the more bits a pair holds
+//!
+//! Pairs are handled through the integer average / difference transform, so that the
+//! average is never altered and both encoder and decoder agree on which pairs are usable
+//! Values are 8 or 16 bits samples, the highest value being given by the caller
+
+/// Difference ranges, each one holding log2(width) bits
+/// Ranges above 255 are only reached by 16 bits samples
+const RANGES: [(i32, i32); 14] = [
+    (0, 7),
+    (8, 15),
+    (16, 31),
+    (32, 63),
+    (64, 127),
+    (128, 255),
+    (256, 511),
+    (512, 1023),
+    (1024, 2047),
+    (2048, 4095),
+    (4096, 8191),
+    (8192, 16383),
+    (16384, 32767),
+    (32768, 65535),
+];
+
+/// Returns range the absolute difference belongs to
+fn get_range(difference: i32) -> (i32, i32) {
+    let difference = difference.abs();
+    *RANGES
+        .iter()
+        .find(|(lower, upper)| *lower <= difference && difference <= *upper)
+        .unwrap_or(&RANGES[RANGES.len() - 1])
+}
+
+/// Returns number of bits held by a range
+fn get_bits_count(range: (i32, i32)) -> usize {
+    (range.1 - range.0 + 1).trailing_zeros() as usize
+}
+
+/// Rebuild pixel pair from average and difference, None if falling off [0, max]
+fn from_average_and_difference(average: i32, difference: i32, max: u16) -> Option<(u16, u16)> {
+    let first = average - difference.div_euclid(2);
+    let second = average + (difference + 1).div_euclid(2);
+    let bounds = 0..=max as i32;
+
+    if bounds.contains(&first) && bounds.contains(&second) {
+        Some((first as u16, second as u16))
+    } else {
+        None
+    }
+}
+
+/// Returns average and difference of a pixel pair
+fn to_average_and_difference(first: u16, second: u16) -> (i32, i32) {
+    (
+        (first as i32 + second as i32).div_euclid(2),
+        second as i32 - first as i32,
+    )
+}
+
+/// Returns number of bits the pair can hold, or None if embedding could overflow
+pub fn get_capacity(first: u16, second: u16, max: u16) -> Option<usize> {
+    let (average, difference) = to_average_and_difference(first, second);
+    let range = get_range(difference);
+
+    // Both signs are checked, so that the test does not depend on the embedded bits
+    if from_average_and_difference(average, range.1, max).is_some()
+        && from_average_and_difference(average, -range.1, max).is_some()
+    {
+        Some(get_bits_count(range))
+    } else {
+        None
+    }
+}
+
+/// Hide bits in pixel pair, missing bits being read as false
+/// Returns new pair and number of bits consumed, or None if pair is not usable
+pub fn embed_pair(first: u16, second: u16, max: u16, bits: &[bool]) -> Option<(u16, u16, usize)> {
+    let count = get_capacity(first, second, max)?;
+    let (average, difference) = to_average_and_difference(first, second);
+    let range = get_range(difference);
+
+    let value = (0..count).fold(0, |acc, i| acc * 2 + *bits.get(i).unwrap_or(&false) as i32);
+    let new_difference = if difference < 0 {
+        -(range.0 + value)
+    } else {
+        range.0 + value
+    };
+
+    let (new_first, new_second) = from_average_and_difference(average, new_difference, max)?;
+
+    Some((new_first, new_second, count.min(bits.len())))
+}
+
+/// Returns bits hidden in pixel pair, or None if pair is not usable
+pub fn extract_pair(first: u16, second: u16, max: u16) -> Option<Vec<bool>> {
+    let count = get_capacity(first, second, max)?;
+    let (_, difference) = to_average_and_difference(first, second);
+    let value = difference.abs() - get_range(difference).0;
+
+    Some(
+        (0..count)
+            .rev()
+            .map(|shift| (value >> shift) & 1 == 1)
+            .collect(),
+    )
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_range() {
+        assert_eq!(get_range(0), (0, 7));
+        assert_eq!(get_range(-12), (8, 15));
+        assert_eq!(get_range(200), (128, 255));
+        assert_eq!(get_range(40000), (32768, 65535));
+        assert_eq!(get_bits_count((32, 63)), 5);
+    }
+
+    #[test]
+    fn test_average_and_difference_round_trip() {
+        for first in 0..=255 {
+            for second in (0..=255).step_by(7) {
+                let (average, difference) = to_average_and_difference(first, second);
+                assert_eq!(
+                    from_average_and_difference(average, difference, 255),
+                    Some((first, second))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_embed_extract_pair() {
+        let bits = [
+            true, false, true, true, false, true, true, false, true, true,
+        ];
+
+        for &(first, second, max) in &[
+            (100, 103, 255),
+            (100, 97, 255),
+            (50, 90, 255),
+            (90, 50, 255),
+            (120, 120, 255),
+            (30000, 31000, 65535),
+        ] {
+            let (new_first, new_second, used) =
+                embed_pair(first, second, max, &bits).expect("Pair must be usable");
+
+            assert_eq!(
+                extract_pair(new_first, new_second, max),
+                Some(bits[..used].to_vec())
+            );
+            assert_eq!(
+                get_capacity(first, second, max),
+                get_capacity(new_first, new_second, max)
+            );
+        }
+    }
+
+    #[test]
+    fn test_unusable_pair() {
+        assert_eq!(get_capacity(250, 255, 255), None);
+        assert_eq!(get_capacity(0, 255, 255), Some(7));
+        assert_eq!(get_capacity(250, 255, 65535), Some(3));
+        assert_eq!(embed_pair(255, 250, 255, &[true]), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! Picture quality metrics, telling how much a picture was changed compared to another one
+use std::io::{Error, ErrorKind};
+
+use super::picture::{ColorType, Picture};
+
+/// Side of the windows SSIM is computed over
+const SSIM_WINDOW: usize = 8;
+
+/// Distortion of a picture compared to its cover
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Peak signal-to-noise ratio, in dB, infinite for identical pictures
+    pub psnr: f64,
+    /// Structural similarity, mean over windows and channels, 1 for identical pictures
+    pub ssim: f64,
+    /// Mean squared error
+    pub mse: f64,
+    /// Number of pixels with at least one changed channel value, and number of pixels
+    pub changed_pixels: (usize, usize),
+    /// Number of changed channel values, and number of channel values
+    pub changed_values: (usize, usize),
+    /// Count of every value in the picture minus its count in the cover, all channels together
+    pub histogram_delta: Vec<i64>,
+}
+
+impl Comparison {
+    /// Returns percentage of changed pixels
+    pub fn get_changed_pixels_percentage(&self) -> f64 {
+        100.0 * self.changed_pixels.0 as f64 / self.changed_pixels.1.max(1) as f64
+    }
+
+    /// Returns percentage of changed channel values
+    pub fn get_changed_values_percentage(&self) -> f64 {
+        100.0 * self.changed_values.0 as f64 / self.changed_values.1.max(1) as f64
+    }
+
+    /// Returns number of values which moved from one histogram bin to another
+    pub fn get_histogram_moves(&self) -> i64 {
+        self.histogram_delta
+            .iter()
+            .map(|delta| delta.abs())
+            .sum::<i64>()
+            / 2
+    }
+}
+
+/// Returns color values of both pictures, compared channel by channel, and their highest value
+/// Pictures of the same format are compared sample by sample, other ones as 8 bits RGB
+fn get_color_values(cover: &Picture, other: &Picture) -> Result<(Vec<f64>, Vec<f64>, f64), Error> {
+    if cover.dimensions() != other.dimensions() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "stegano/quality : Pictures dimensions differ!",
+        ));
+    }
+
+    let same_format = cover.get_color_type() == other.get_color_type()
+        && cover.get_bit_depth() == other.get_bit_depth()
+        && cover.get_color_type() != ColorType::Indexed;
+
+    if same_format {
+        let values = |picture: &Picture| -> Vec<f64> {
+            (0..picture.get_color_samples_count())
+                .map(|index| picture.get_color_sample(index) as f64)
+                .collect()
+        };
+        Ok((values(cover), values(other), cover.get_max_value() as f64))
+    } else {
+        let values = |picture: &Picture| -> Vec<f64> {
+            picture
+                .to_rgba8()
+                .chunks(4)
+                .flat_map(|pixel| pixel[..3].iter().map(|&value| value as f64))
+                .collect()
+        };
+        Ok((values(cover), values(other), u8::MAX as f64))
+    }
+}
+
+/// Returns mean squared error between both pictures colors
+pub fn get_mse(cover: &Picture, other: &Picture) -> Result<f64, Error> {
+    let (cover_values, other_values, _) = get_color_values(cover, other)?;
+    let sum: f64 = cover_values
+        .iter()
+        .zip(&other_values)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum();
+    Ok(sum / cover_values.len().max(1) as f64)
+}
+
+/// Returns peak signal-to-noise ratio between both pictures, in dB
+/// Identical pictures get an infinite ratio
+pub fn get_psnr(cover: &Picture, other: &Picture) -> Result<f64, Error> {
+    let (_, _, max_value) = get_color_values(cover, other)?;
+    let mse = get_mse(cover, other)?;
+    Ok(10.0 * (max_value * max_value / mse).log10())
+}
+
+/// Returns structural similarity of both channel planes, mean over windows
+fn get_ssim(cover: &[f64], other: &[f64], width: usize, max_value: f64) -> f64 {
+    let c1 = (0.01 * max_value) * (0.01 * max_value);
+    let c2 = (0.03 * max_value) * (0.03 * max_value);
+    let height = cover.len() / width.max(1);
+
+    let (mut sum, mut windows) = (0.0, 0);
+    for top in (0..height).step_by(SSIM_WINDOW) {
+        for left in (0..width).step_by(SSIM_WINDOW) {
+            let indexes: Vec<usize> = (top..(top + SSIM_WINDOW).min(height))
+                .flat_map(|row| {
+                    (left..(left + SSIM_WINDOW).min(width)).map(move |x| row * width + x)
+                })
+                .collect();
+            let count = indexes.len() as f64;
+
+            let mean = |values: &[f64]| indexes.iter().map(|&i| values[i]).sum::<f64>() / count;
+            let (mean_x, mean_y) = (mean(cover), mean(other));
+            let (mut var_x, mut var_y, mut covariance) = (0.0, 0.0, 0.0);
+            for &index in &indexes {
+                let (dx, dy) = (cover[index] - mean_x, other[index] - mean_y);
+                var_x += dx * dx;
+                var_y += dy * dy;
+                covariance += dx * dy;
+            }
+            let (var_x, var_y, covariance) = (var_x / count, var_y / count, covariance / count);
+
+            sum += ((2.0 * mean_x * mean_y + c1) * (2.0 * covariance + c2))
+                / ((mean_x * mean_x + mean_y * mean_y + c1) * (var_x + var_y + c2));
+            windows += 1;
+        }
+    }
+    sum / windows.max(1) as f64
+}
+
+/// Compare picture with its cover : PSNR, SSIM, MSE, changed pixels and values, histogram delta
+pub fn compare(cover: &Picture, other: &Picture) -> Result<Comparison, Error> {
+    let (cover_values, other_values, max_value) = get_color_values(cover, other)?;
+    let (width, height) = cover.dimensions();
+    let pixels = (width * height) as usize;
+    let channels = cover_values.len() / pixels.max(1);
+
+    let changed_values = cover_values
+        .iter()
+        .zip(&other_values)
+        .filter(|(a, b)| a != b)
+        .count();
+    let changed_pixels = cover_values
+        .chunks(channels.max(1))
+        .zip(other_values.chunks(channels.max(1)))
+        .filter(|(a, b)| a != b)
+        .count();
+
+    let mut histogram_delta = vec![0i64; max_value as usize + 1];
+    for (&a, &b) in cover_values.iter().zip(&other_values) {
+        histogram_delta[a as usize] -= 1;
+        histogram_delta[b as usize] += 1;
+    }
+
+    // SSIM is computed channel by channel
+    let plane = |values: &[f64], channel: usize| -> Vec<f64> {
+        values
+            .iter()
+            .skip(channel)
+            .step_by(channels)
+            .cloned()
+            .collect()
+    };
+    let ssim = (0..channels)
+        .map(|channel| {
+            get_ssim(
+                &plane(&cover_values, channel),
+                &plane(&other_values, channel),
+                width as usize,
+                max_value,
+            )
+        })
+        .sum::<f64>()
+        / channels.max(1) as f64;
+
+    Ok(Comparison {
+        psnr: get_psnr(cover, other)?,
+        ssim,
+        mse: get_mse(cover, other)?,
+        changed_pixels: (changed_pixels, pixels),
+        changed_values: (changed_values, cover_values.len()),
+        histogram_delta,
+    })
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn test_psnr() {
+        let cover = Picture::new(2, 1, ColorType::Rgb, 8, vec![10, 20, 30, 40, 50, 60])
+            .expect("Cover picture");
+        let other = Picture::new(2, 1, ColorType::Rgb, 8, vec![11, 20, 30, 40, 50, 59])
+            .expect("Other picture");
+
+        assert_eq!(get_mse(&cover, &other).ok(), Some(2.0 / 6.0));
+        assert_eq!(get_psnr(&cover, &cover).ok(), Some(f64::INFINITY));
+        assert!((get_psnr(&cover, &other).unwrap_or_default() - 52.9).abs() < 0.1);
+
+        let comparison = compare(&cover, &other).unwrap_or_else(|err| {
+            eprintln!("Error in test_psnr: {}", err);
+            process::exit(1);
+        });
+        assert_eq!(comparison.changed_pixels, (2, 2));
+        assert_eq!(comparison.changed_values, (2, 6));
+        assert_eq!(comparison.get_histogram_moves(), 2);
+        assert_eq!(
+            (
+                comparison.histogram_delta[10],
+                comparison.histogram_delta[11]
+            ),
+            (-1, 1)
+        );
+        assert!(comparison.ssim > 0.9 && comparison.ssim < 1.0);
+        assert_eq!(
+            compare(&cover, &cover).map(|same| same.ssim).ok(),
+            Some(1.0)
+        );
+    }
+}
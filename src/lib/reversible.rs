@@ -0,0 +1,155 @@
+//! Histogram shifting : reversible data hiding, the original values being restorable bit for bit
+//! Values between the histogram peak and an empty bin are shifted by one towards the empty bin,
+//! which frees the bin next to the peak : each peak value then holds one bit
+
+/// Peak and empty bins of a channel histogram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bins {
+    pub peak: u16,
+    pub zero: u16,
+}
+
+impl Bins {
+    /// Find the most frequent value, and the empty bin closest to it, values ranging from 0 to max
+    /// Returns None if no value is missing from the histogram
+    pub fn from_values<I: Iterator<Item = u16>>(values: I, max: u16) -> Option<Bins> {
+        let bins_count = max as usize + 1;
+        let mut histogram = vec![0usize; bins_count];
+        for value in values {
+            histogram[value as usize] += 1;
+        }
+
+        // First most frequent value
+        let peak = (0..bins_count).fold(0, |best, value| {
+            if histogram[value] > histogram[best] {
+                value
+            } else {
+                best
+            }
+        });
+
+        (1..bins_count as isize)
+            .flat_map(|distance| vec![peak as isize + distance, peak as isize - distance])
+            .find(|&value| {
+                (0..bins_count as isize).contains(&value) && histogram[value as usize] == 0
+            })
+            .map(|zero| Bins {
+                peak: peak as u16,
+                zero: zero as u16,
+            })
+    }
+
+    /// Returns true if shifting goes upwards
+    fn is_upwards(self) -> bool {
+        self.zero > self.peak
+    }
+
+    /// Returns value standing for a hidden 1
+    fn get_marked_peak(self) -> u16 {
+        if self.is_upwards() {
+            self.peak + 1
+        } else {
+            self.peak - 1
+        }
+    }
+
+    /// Returns true if original value lies strictly between peak and empty bin
+    fn is_shifted(self, value: u16) -> bool {
+        if self.is_upwards() {
+            value > self.peak && value < self.zero
+        } else {
+            value < self.peak && value > self.zero
+        }
+    }
+
+    /// Embed bit into value, if value is a peak one
+    /// Returns new value, and whether the bit was consumed
+    pub fn embed(self, value: u16, bit: bool) -> (u16, bool) {
+        if value == self.peak {
+            (if bit { self.get_marked_peak() } else { value }, true)
+        } else if self.is_shifted(value) {
+            (
+                if self.is_upwards() {
+                    value + 1
+                } else {
+                    value - 1
+                },
+                false,
+            )
+        } else {
+            (value, false)
+        }
+    }
+
+    /// Returns bit hidden in value, if any
+    pub fn extract(self, value: u16) -> Option<bool> {
+        if value == self.peak {
+            Some(false)
+        } else if value == self.get_marked_peak() {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Returns original value
+    pub fn restore(self, value: u16) -> u16 {
+        if value == self.get_marked_peak() {
+            self.peak
+        } else if self.is_upwards() && value > self.peak + 1 && value <= self.zero {
+            value - 1
+        } else if !self.is_upwards() && value < self.peak - 1 && value >= self.zero {
+            value + 1
+        } else {
+            value
+        }
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_values() {
+        let values = vec![10, 10, 10, 11, 12, 9, 8, 7, 13];
+
+        assert_eq!(
+            Bins::from_values(values.into_iter(), 255),
+            Some(Bins { peak: 10, zero: 14 })
+        );
+        assert_eq!(Bins::from_values(0..=255, 255), None);
+        assert_eq!(
+            Bins::from_values(0..=255, 65535),
+            Some(Bins { peak: 0, zero: 256 })
+        );
+    }
+
+    #[test]
+    fn test_embed_extract_restore() {
+        let values: Vec<u16> = vec![50, 50, 51, 52, 50, 49, 53, 60, 50, 200];
+
+        for &bins in &[Bins { peak: 50, zero: 54 }, Bins { peak: 50, zero: 48 }] {
+            let bits = [true, false, true, true];
+            let mut cursor = 0;
+
+            let stego: Vec<u16> = values
+                .iter()
+                .map(|&value| {
+                    let (new_value, consumed) = bins.embed(value, bits[cursor % 4]);
+                    if consumed {
+                        cursor += 1;
+                    }
+                    new_value
+                })
+                .collect();
+
+            let extracted: Vec<bool> = stego.iter().filter_map(|&v| bins.extract(v)).collect();
+            let restored: Vec<u16> = stego.iter().map(|&v| bins.restore(v)).collect();
+
+            assert_eq!(extracted, bits.to_vec());
+            assert_eq!(restored, values);
+        }
+    }
+}
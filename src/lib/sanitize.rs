@@ -0,0 +1,72 @@
+//! Sanitizer for incoming pictures : low bits are re-randomized and samples dithered,
+//! so that no LSB payload survives, and pictures are written back from their samples only,
+//! so that no metadata chunk nor appended data survives either
+use super::picture::PNG_SIGNATURE;
+
+/// Sanitizing result
+#[derive(Debug, Clone, PartialEq)]
+pub struct SanitizeReport {
+    /// Peak signal-to-noise ratio of the sanitized picture versus the input one, in dB
+    pub psnr: f64,
+    /// Ancillary chunks of the input PNG file, which are not written back
+    pub stripped_chunks: Vec<String>,
+    /// Number of bytes found after the end of the input PNG file, which are not written back
+    pub appended_bytes: usize,
+}
+
+/// Returns ancillary chunk types of PNG file contents, and number of bytes after its end
+/// Critical chunks (IHDR, PLTE, IDAT, IEND) and tRNS are left out, as they are written back
+pub fn get_png_extras(bytes: &[u8]) -> (Vec<String>, usize) {
+    let mut chunks = Vec::new();
+    if !bytes.starts_with(&PNG_SIGNATURE) {
+        return (chunks, 0);
+    }
+
+    // Each chunk : length, type, data, then CRC
+    let mut position = PNG_SIGNATURE.len();
+    while position + 12 <= bytes.len() {
+        let mut length = [0u8; 4];
+        length.copy_from_slice(&bytes[position..position + 4]);
+        let chunk_type = String::from_utf8_lossy(&bytes[position + 4..position + 8]).to_string();
+        position += 12 + u32::from_be_bytes(length) as usize;
+
+        if chunk_type == "IEND" {
+            return (chunks, bytes.len().saturating_sub(position));
+        }
+
+        // Ancillary chunks have a lowercase first letter
+        if chunk_type.starts_with(|letter: char| letter.is_ascii_lowercase())
+            && chunk_type != "tRNS"
+        {
+            chunks.push(chunk_type);
+        }
+    }
+
+    (chunks, 0)
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_extras() {
+        let mut bytes = PNG_SIGNATURE.to_vec();
+        for (chunk_type, data) in &[
+            ("IHDR", &[0u8; 13][..]),
+            ("tEXt", &b"Comment\0Hello"[..]),
+            ("tRNS", &[0u8; 1][..]),
+            ("IEND", &[][..]),
+        ] {
+            bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(chunk_type.as_bytes());
+            bytes.extend_from_slice(data);
+            bytes.extend_from_slice(&[0; 4]);
+        }
+        bytes.extend_from_slice(b"appended");
+
+        assert_eq!(get_png_extras(&bytes), (vec!["tEXt".to_string()], 8));
+        assert_eq!(get_png_extras(b"GIF89a"), (Vec::new(), 0));
+    }
+}
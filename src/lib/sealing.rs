@@ -0,0 +1,667 @@
+//! Sealing methods : how messages are encrypted before being hidden
+//! A sealing method also gives the carrier key, which masks headers and drives
+//! key-derived embedding orders, so that both sides find the message back
+//!
+//! Password sealing is the historical AES one, recipient sealing encrypts to an X25519 public key
+//! with an ephemeral key exchange, so that only the holder of the private key can decode
+//!
+//! Public keys are published, so they cannot hide where a payload lies : recipient sealing
+//! derives its carrier key from an embedding key, a passphrase shared beforehand by sender and
+//! recipient. Whoever knows the embedding key can find and extract the ciphertext, yet not
+//! decrypt it ; whoever does not cannot tell anything is hidden
+//!
+//! Several recipients share one payload as in age : a random content key encrypts the message,
+//! and is itself wrapped once per recipient, in stanzas which name no recipient ;
+//! they share one embedding key too, the same trade-off applying to all of them
+extern crate crypto;
+extern crate rand;
+
+use self::crypto::{
+    aead::{AeadDecryptor, AeadEncryptor},
+    chacha20poly1305::ChaCha20Poly1305,
+    curve25519::{curve25519, curve25519_base},
+    hkdf::{hkdf_expand, hkdf_extract},
+    sha2::Sha256,
+};
+use self::rand::{rngs::OsRng, RngCore};
+
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+};
+
+use super::cypher::{
+    check_password, derive_key, derive_key_with_keyfiles, read_keyfiles, simple_decrypt,
+    simple_encrypt, KeyStream,
+};
+
+/// Size of X25519 keys, in bytes
+pub const KEY_BYTES: usize = 32;
+
+/// Size of the authentication tag ending recipient sealed messages
+const TAG_BYTES: usize = 16;
+
+/// Key derivation context of recipient sealing
+const RECIPIENT_CONTEXT: &str = "stegano/x25519";
+
+/// Key derivation context of recipient sealing carrier keys, followed by the public key
+const RECIPIENT_CARRIER_CONTEXT: &str = "stegano/x25519/carrier";
+
+/// Key derivation context of several recipients sealing carrier keys
+const RECIPIENTS_CARRIER_CONTEXT: &str = "stegano/recipients/carrier";
+
+/// Key derivation context of passwords mixed with keyfiles
+const KEYFILES_CONTEXT: &str = "stegano/keyfiles";
+
+/// Size of a stanza : salt or ephemeral public key, then wrapped content key and its tag
+const STANZA_BYTES: usize = KEY_BYTES * 2 + TAG_BYTES;
+
+/// Way of encrypting messages, and of deriving the carrier key
+pub trait Sealing {
+    /// Returns key masking headers and driving embedding orders, used where passwords were
+    fn get_carrier_key(&self) -> String;
+
+    /// Encrypt message
+    fn seal(&self, message: &str) -> Result<Vec<u8>, Error>;
+
+    /// Decrypt message
+    fn open(&self, sealed: &[u8]) -> Result<String, Error>;
+}
+
+/// Shared password sealing, with simple_encrypt / simple_decrypt
+pub struct PasswordSealing {
+    password: String,
+}
+
+impl PasswordSealing {
+    /// Constructor
+    pub fn new(password: &str) -> PasswordSealing {
+        PasswordSealing {
+            password: password.to_string(),
+        }
+    }
+
+    /// Constructor mixing password with keyfiles, see get_keyfiles_password
+    pub fn with_keyfiles(password: &str, keyfiles: &[String]) -> Result<PasswordSealing, Error> {
+        Ok(PasswordSealing::new(&get_keyfiles_password(
+            password, keyfiles,
+        )?))
+    }
+}
+
+/// Returns password to use in place of given one, keyfile digests being mixed with it
+/// by key derivation, so that password alone is useless ; no keyfile leaves password as it is
+/// Password is checked before, the derived one always being long enough
+pub fn get_keyfiles_password(password: &str, keyfiles: &[String]) -> Result<String, Error> {
+    if keyfiles.is_empty() {
+        return Ok(password.to_string());
+    }
+
+    check_password(password)?;
+    Ok(to_hex(&derive_key_with_keyfiles(
+        password,
+        &read_keyfiles(keyfiles)?,
+        KEYFILES_CONTEXT,
+    )))
+}
+
+impl Sealing for PasswordSealing {
+    /// Password itself is the carrier key
+    fn get_carrier_key(&self) -> String {
+        self.password.clone()
+    }
+
+    fn seal(&self, message: &str) -> Result<Vec<u8>, Error> {
+        simple_encrypt(message, &self.password)
+    }
+
+    fn open(&self, sealed: &[u8]) -> Result<String, Error> {
+        simple_decrypt(sealed, &self.password)
+    }
+}
+
+/// X25519 recipient sealing : ephemeral public key, then ChaCha20-Poly1305 ciphertext and tag
+/// The key comes from the exchange between the ephemeral private key and the recipient public key
+/// Decoding needs the recipient private key, encoding only the public one,
+/// both sides needing the embedding key they share
+pub struct RecipientSealing {
+    public_key: [u8; KEY_BYTES],
+    secret_key: Option<[u8; KEY_BYTES]>,
+    carrier_key: String,
+}
+
+impl RecipientSealing {
+    /// Constructor sealing to given public key, hidden with given embedding key
+    pub fn to_recipient(
+        public_key: [u8; KEY_BYTES],
+        embedding_key: &str,
+    ) -> Result<RecipientSealing, Error> {
+        Ok(RecipientSealing {
+            public_key,
+            secret_key: None,
+            carrier_key: get_recipient_carrier_key(embedding_key, &public_key)?,
+        })
+    }
+
+    /// Constructor opening with given private key, payload being hidden with given embedding key
+    pub fn from_identity(
+        secret_key: [u8; KEY_BYTES],
+        embedding_key: &str,
+    ) -> Result<RecipientSealing, Error> {
+        let public_key = curve25519_base(&secret_key);
+        Ok(RecipientSealing {
+            public_key,
+            secret_key: Some(secret_key),
+            carrier_key: get_recipient_carrier_key(embedding_key, &public_key)?,
+        })
+    }
+
+    /// Returns recipient public key
+    pub fn get_public_key(&self) -> [u8; KEY_BYTES] {
+        self.public_key
+    }
+}
+
+/// Returns carrier key derived from an embedding key, bound to recipient public key
+/// Embedding key is checked as any password, the carrier key being out of reach of checks
+fn get_recipient_carrier_key(
+    embedding_key: &str,
+    public_key: &[u8; KEY_BYTES],
+) -> Result<String, Error> {
+    check_password(embedding_key)?;
+    Ok(to_hex(&derive_key(
+        embedding_key,
+        &format!("{}/{}", RECIPIENT_CARRIER_CONTEXT, to_hex(public_key)),
+    )))
+}
+
+/// Returns symmetric key derived from an exchanged secret, bound to both public keys
+fn derive_sealing_key(
+    shared: &[u8],
+    ephemeral_public: &[u8],
+    public_key: &[u8],
+) -> [u8; KEY_BYTES] {
+    let mut salt = ephemeral_public.to_vec();
+    salt.extend_from_slice(public_key);
+
+    let mut pseudo_random_key = [0u8; KEY_BYTES];
+    hkdf_extract(Sha256::new(), &salt, shared, &mut pseudo_random_key);
+
+    let mut key = [0u8; KEY_BYTES];
+    hkdf_expand(
+        Sha256::new(),
+        &pseudo_random_key,
+        RECIPIENT_CONTEXT.as_bytes(),
+        &mut key,
+    );
+    key
+}
+
+impl Sealing for RecipientSealing {
+    /// Carrier key comes from the embedding key, public key alone telling nothing
+    fn get_carrier_key(&self) -> String {
+        self.carrier_key.clone()
+    }
+
+    fn seal(&self, message: &str) -> Result<Vec<u8>, Error> {
+        let (ephemeral_secret, ephemeral_public) = generate_keypair()?;
+        let shared = curve25519(&ephemeral_secret, &self.public_key);
+        let key = derive_sealing_key(&shared, &ephemeral_public, &self.public_key);
+
+        // Every message gets its own key, so a zero nonce is never reused
+        let mut sealed = ephemeral_public.to_vec();
+        sealed.append(&mut seal_with_key(
+            &key,
+            message.as_bytes(),
+            &ephemeral_public,
+        ));
+        Ok(sealed)
+    }
+
+    fn open(&self, sealed: &[u8]) -> Result<String, Error> {
+        let secret_key = self.secret_key.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/open : Private key is needed to decode!",
+            )
+        })?;
+        if sealed.len() < KEY_BYTES + TAG_BYTES {
+            return Err(unable_to_open());
+        }
+
+        let (ephemeral_public, rest) = sealed.split_at(KEY_BYTES);
+        let shared = curve25519(&secret_key, ephemeral_public);
+        let key = derive_sealing_key(&shared, ephemeral_public, &self.public_key);
+        let message = open_with_key(&key, rest, ephemeral_public).ok_or_else(unable_to_open)?;
+
+        String::from_utf8(message).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "stegano/open : Unable to convert decrypted message to UTF8",
+            )
+        })
+    }
+}
+
+/// Returns an error telling sealed message cannot be opened
+fn unable_to_open() -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        "stegano/open : Unable to decrypt message",
+    )
+}
+
+/// Encrypt data with a single-use key, returning ciphertext followed by its tag
+pub fn seal_with_key(key: &[u8], data: &[u8], additional_data: &[u8]) -> Vec<u8> {
+    let mut sealed = vec![0u8; data.len() + TAG_BYTES];
+    let (ciphertext, tag) = sealed.split_at_mut(data.len());
+    ChaCha20Poly1305::new(key, &[0; 8], additional_data).encrypt(data, ciphertext, tag);
+    sealed
+}
+
+/// Decrypt data sealed with a single-use key, None if it was not sealed with that key
+pub fn open_with_key(key: &[u8], sealed: &[u8], additional_data: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < TAG_BYTES {
+        return None;
+    }
+
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_BYTES);
+    let mut data = vec![0u8; ciphertext.len()];
+    if ChaCha20Poly1305::new(key, &[0; 8], additional_data).decrypt(ciphertext, &mut data, tag) {
+        Some(data)
+    } else {
+        None
+    }
+}
+
+/// Someone a payload is sealed to : password holder or private key holder
+pub enum Recipient {
+    Password(String),
+    PublicKey([u8; KEY_BYTES]),
+}
+
+impl Recipient {
+    /// Constructor of password recipients, password being mixed with keyfiles
+    pub fn from_password(password: &str, keyfiles: &[String]) -> Result<Recipient, Error> {
+        get_keyfiles_password(password, keyfiles).map(Recipient::Password)
+    }
+}
+
+/// Secret opening a payload : password or private key
+pub enum Identity {
+    Password(String),
+    SecretKey([u8; KEY_BYTES]),
+}
+
+impl Identity {
+    /// Constructor of password identities, password being mixed with keyfiles
+    pub fn from_password(password: &str, keyfiles: &[String]) -> Result<Identity, Error> {
+        get_keyfiles_password(password, keyfiles).map(Identity::Password)
+    }
+}
+
+/// Several recipients sealing : stanzas count, stanzas, then message
+/// Each stanza wraps the content key, using a random salt with passwords
+/// and an ephemeral key exchange with public keys ; stanzas name no recipient,
+/// yet ephemeral public keys are not uniformly random, telling key stanzas from password ones
+/// Payload is hidden with the embedding key every recipient shares
+pub struct RecipientsSealing {
+    recipients: Vec<Recipient>,
+    identity: Option<Identity>,
+    carrier_key: String,
+}
+
+impl RecipientsSealing {
+    /// Constructor sealing to given recipients, hidden with given embedding key
+    pub fn to_recipients(
+        recipients: Vec<Recipient>,
+        embedding_key: &str,
+    ) -> Result<RecipientsSealing, Error> {
+        Ok(RecipientsSealing {
+            recipients,
+            identity: None,
+            carrier_key: get_recipients_carrier_key(embedding_key)?,
+        })
+    }
+
+    /// Constructor opening with given identity, payload being hidden with given embedding key
+    pub fn from_identity(
+        identity: Identity,
+        embedding_key: &str,
+    ) -> Result<RecipientsSealing, Error> {
+        Ok(RecipientsSealing {
+            recipients: Vec::new(),
+            identity: Some(identity),
+            carrier_key: get_recipients_carrier_key(embedding_key)?,
+        })
+    }
+
+    /// Returns stanza wrapping content key for given recipient
+    fn wrap(recipient: &Recipient, content_key: &[u8]) -> Result<Vec<u8>, Error> {
+        let (share, key) = match *recipient {
+            Recipient::Password(ref password) => {
+                let salt = random_key()?;
+                (salt, get_password_key(password, &salt))
+            }
+            Recipient::PublicKey(ref public_key) => {
+                let (ephemeral_secret, ephemeral_public) = generate_keypair()?;
+                let shared = curve25519(&ephemeral_secret, public_key);
+                (
+                    ephemeral_public,
+                    derive_sealing_key(&shared, &ephemeral_public, public_key),
+                )
+            }
+        };
+
+        let mut stanza = share.to_vec();
+        stanza.append(&mut seal_with_key(&key, content_key, &share));
+        Ok(stanza)
+    }
+
+    /// Returns content key wrapped by given stanza, None if it is not for given identity
+    fn unwrap(identity: &Identity, stanza: &[u8]) -> Option<Vec<u8>> {
+        let (share, wrapped) = stanza.split_at(KEY_BYTES);
+        let key = match *identity {
+            Identity::Password(ref password) => get_password_key(password, share),
+            Identity::SecretKey(ref secret_key) => {
+                let shared = curve25519(secret_key, share);
+                derive_sealing_key(&shared, share, &curve25519_base(secret_key))
+            }
+        };
+
+        open_with_key(&key, wrapped, share)
+    }
+}
+
+/// Returns carrier key derived from the embedding key shared by every recipient
+fn get_recipients_carrier_key(embedding_key: &str) -> Result<String, Error> {
+    check_password(embedding_key)?;
+    Ok(to_hex(&derive_key(
+        embedding_key,
+        RECIPIENTS_CARRIER_CONTEXT,
+    )))
+}
+
+/// Returns key wrapping content keys for a password, with given salt
+fn get_password_key(password: &str, salt: &[u8]) -> [u8; KEY_BYTES] {
+    derive_key(password, &format!("stegano/recipients/{}", to_hex(salt)))
+}
+
+impl Sealing for RecipientsSealing {
+    /// Carrier key comes from the embedding key, stanzas alone telling nothing
+    fn get_carrier_key(&self) -> String {
+        self.carrier_key.clone()
+    }
+
+    fn seal(&self, message: &str) -> Result<Vec<u8>, Error> {
+        if self.recipients.is_empty() || self.recipients.len() > u8::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/seal : From 1 to 255 recipients are needed!",
+            ));
+        }
+
+        let content_key = random_key()?;
+        let mut stanzas = self
+            .recipients
+            .iter()
+            .map(|recipient| RecipientsSealing::wrap(recipient, &content_key))
+            .collect::<Result<Vec<Vec<u8>>, Error>>()?;
+
+        // Stanzas order does not follow recipients one
+        KeyStream::new(&random_key()?).shuffle(&mut stanzas);
+
+        let mut sealed = vec![stanzas.len() as u8];
+        for stanza in stanzas {
+            sealed.extend(stanza);
+        }
+        let mut body = seal_with_key(&content_key, message.as_bytes(), &sealed);
+        sealed.append(&mut body);
+        Ok(sealed)
+    }
+
+    fn open(&self, sealed: &[u8]) -> Result<String, Error> {
+        let identity = self.identity.as_ref().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/open : Password or private key is needed to decode!",
+            )
+        })?;
+
+        let stanzas_end = 1 + STANZA_BYTES * *sealed.first().ok_or_else(unable_to_open)? as usize;
+        if sealed.len() < stanzas_end {
+            return Err(unable_to_open());
+        }
+
+        let (stanzas, body) = sealed.split_at(stanzas_end);
+        let message = stanzas[1..]
+            .chunks(STANZA_BYTES)
+            .find_map(|stanza| RecipientsSealing::unwrap(identity, stanza))
+            .and_then(|content_key| open_with_key(&content_key, body, stanzas))
+            .ok_or_else(unable_to_open)?;
+
+        String::from_utf8(message).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "stegano/open : Unable to convert decrypted message to UTF8",
+            )
+        })
+    }
+}
+
+/// Returns 32 random bytes, from the operating system
+pub fn random_key() -> Result<[u8; KEY_BYTES], Error> {
+    let mut key = [0u8; KEY_BYTES];
+    OsRng
+        .try_fill_bytes(&mut key)
+        .map_err(|err| Error::other(format!("stegano/random : {}", err)))?;
+    Ok(key)
+}
+
+/// Returns a new X25519 key pair : private key, then public key
+pub fn generate_keypair() -> Result<([u8; KEY_BYTES], [u8; KEY_BYTES]), Error> {
+    let mut secret_key = random_key()?;
+
+    // Clamped as X25519 does, so that stored private keys are the actual scalars
+    secret_key[0] &= 248;
+    secret_key[31] &= 127;
+    secret_key[31] |= 64;
+
+    Ok((secret_key, curve25519_base(&secret_key)))
+}
+
+/// Returns lowercase hexadecimal string of given bytes
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Returns bytes given as an hexadecimal string, None if it is not one
+pub fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len() / 2)
+        .map(|index| u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .filter(|bytes| bytes.len() * 2 == hex.len())
+}
+
+/// Returns key given as an hexadecimal string
+pub fn from_hex(hex: &str) -> Result<[u8; KEY_BYTES], Error> {
+    let hex = hex.trim();
+    let invalid_key = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "stegano/key : Keys must be 64 hexadecimal characters!",
+        )
+    };
+
+    if hex.len() != KEY_BYTES * 2 || !hex.is_ascii() {
+        return Err(invalid_key());
+    }
+
+    let mut key = [0u8; KEY_BYTES];
+    for (index, byte) in key.iter_mut().enumerate() {
+        *byte =
+            u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| invalid_key())?;
+    }
+    Ok(key)
+}
+
+/// Returns key given either as an hexadecimal string, or as the path of a file holding one
+pub fn read_key(key_or_filepath: &str) -> Result<[u8; KEY_BYTES], Error> {
+    match from_hex(key_or_filepath) {
+        Ok(key) => Ok(key),
+        Err(err) => match fs::read_to_string(key_or_filepath) {
+            Ok(contents) => from_hex(&contents),
+            Err(_) => Err(err),
+        },
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn test_recipient_sealing() {
+        let (secret_key, public_key) = generate_keypair().unwrap_or_else(|err| {
+            eprintln!("Error in test_recipient_sealing: {}", err);
+            process::exit(1);
+        });
+        assert_eq!(from_hex(&to_hex(&public_key)).ok(), Some(public_key));
+
+        let (sealing, identity) = RecipientSealing::to_recipient(public_key, "Embedding key")
+            .and_then(|sealing| {
+                Ok((
+                    sealing,
+                    RecipientSealing::from_identity(secret_key, "Embedding key")?,
+                ))
+            })
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_recipient_sealing: {}", err);
+                process::exit(1);
+            });
+        assert_eq!(identity.get_carrier_key(), sealing.get_carrier_key());
+
+        // Carrier key needs the embedding key, public key alone does not give it
+        assert!(!sealing.get_carrier_key().contains(&to_hex(&public_key)));
+        assert_ne!(
+            RecipientSealing::to_recipient(public_key, "Other embedding key")
+                .map(|other| other.get_carrier_key())
+                .ok(),
+            Some(sealing.get_carrier_key())
+        );
+        assert!(RecipientSealing::to_recipient(public_key, "Short").is_err());
+
+        let sealed = sealing
+            .seal("Hello how is the weather today")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_recipient_sealing: {}", err);
+                process::exit(1);
+            });
+
+        // Ephemeral keys make every sealing different
+        assert_ne!(
+            sealing.seal("Hello how is the weather today").ok(),
+            Some(sealed.clone())
+        );
+        assert_eq!(
+            identity.open(&sealed).ok(),
+            Some("Hello how is the weather today".to_string())
+        );
+
+        // Public key alone, other private keys and altered messages cannot open it
+        let (other_secret_key, _) = generate_keypair().expect("Key pair");
+        assert!(sealing.open(&sealed).is_err());
+        assert!(
+            RecipientSealing::from_identity(other_secret_key, "Embedding key")
+                .and_then(|other| other.open(&sealed))
+                .is_err()
+        );
+        let mut altered = sealed.clone();
+        altered[KEY_BYTES] ^= 1;
+        assert!(identity.open(&altered).is_err());
+    }
+
+    #[test]
+    fn test_recipients_sealing() {
+        let (secret_key, public_key) = generate_keypair().expect("Key pair");
+        let (other_secret_key, _) = generate_keypair().expect("Key pair");
+        let sealing = RecipientsSealing::to_recipients(
+            vec![
+                Recipient::Password("Password".to_string()),
+                Recipient::PublicKey(public_key),
+            ],
+            "Embedding key",
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("Error in test_recipients_sealing: {}", err);
+            process::exit(1);
+        });
+        assert!(RecipientsSealing::to_recipients(Vec::new(), "Short").is_err());
+
+        let sealed = sealing
+            .seal("Hello how is the weather today")
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_recipients_sealing: {}", err);
+                process::exit(1);
+            });
+        assert_eq!(sealed.len(), 1 + 2 * STANZA_BYTES + 30 + TAG_BYTES);
+
+        // Every recipient opens the payload, others cannot
+        for identity in [
+            Identity::Password("Password".to_string()),
+            Identity::SecretKey(secret_key),
+        ] {
+            assert_eq!(
+                RecipientsSealing::from_identity(identity, "Embedding key")
+                    .and_then(|identity| {
+                        assert_eq!(identity.get_carrier_key(), sealing.get_carrier_key());
+                        identity.open(&sealed)
+                    })
+                    .ok(),
+                Some("Hello how is the weather today".to_string())
+            );
+        }
+        for identity in [
+            Identity::Password("Wrong password".to_string()),
+            Identity::SecretKey(other_secret_key),
+        ] {
+            assert!(RecipientsSealing::from_identity(identity, "Embedding key")
+                .and_then(|identity| identity.open(&sealed))
+                .is_err());
+        }
+        assert!(
+            RecipientsSealing::to_recipients(Vec::new(), "Embedding key")
+                .and_then(|sealing| sealing.seal("Hello"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_keyfiles_password() {
+        let keyfiles = ["test_files/test.png".to_string()];
+        let sealing = PasswordSealing::with_keyfiles("Password", &keyfiles).unwrap_or_else(|err| {
+            eprintln!("Error in test_keyfiles_password: {}", err);
+            process::exit(1);
+        });
+        let sealed = sealing.seal("Hello").unwrap_or_else(|err| {
+            eprintln!("Error in test_keyfiles_password: {}", err);
+            process::exit(1);
+        });
+
+        // Password alone opens nothing, and is checked before being mixed
+        assert_eq!(sealing.open(&sealed).ok(), Some("Hello".to_string()));
+        assert!(PasswordSealing::new("Password").open(&sealed).is_err());
+        assert_ne!(sealing.get_carrier_key(), "Password".to_string());
+        assert!(PasswordSealing::with_keyfiles("Short", &keyfiles).is_err());
+        assert_eq!(
+            get_keyfiles_password("Short", &[]).ok(),
+            Some("Short".to_string())
+        );
+        assert!(matches!(
+            Recipient::from_password("Password", &keyfiles),
+            Ok(Recipient::Password(ref password)) if *password == sealing.get_carrier_key()
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+//! Shamir secret sharing over GF(256) : a secret is split into shares, any threshold of them
+//! rebuilding it while fewer tell nothing about it
+//!
+//! Shares are written as text, to be hidden in several cover pictures like any message
+//!
+//! Shares hold nothing computed from the secret alone : a random key is split along with the
+//! secret sealed under it, so that integrity is only checked once shares are combined
+extern crate rand;
+
+use self::rand::{rngs::OsRng, RngCore};
+
+use std::{
+    fmt,
+    io::{Error, ErrorKind},
+    str::FromStr,
+};
+
+use super::sealing::{hex_to_bytes, open_with_key, random_key, seal_with_key, to_hex, KEY_BYTES};
+
+/// First line of shares
+const SHARE_MAGIC: &str = "STEGANO-SHARE";
+
+/// Size of secret identifiers and checks, in bytes
+pub const ID_BYTES: usize = 8;
+
+/// One share of a secret
+/// Shares of the same secret have the same identifier and threshold, and distinct indexes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    pub id: [u8; ID_BYTES],
+    pub threshold: u8,
+    pub index: u8,
+    pub data: Vec<u8>,
+}
+
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\n{}\n{}\n{}\n{}",
+            SHARE_MAGIC,
+            to_hex(&self.id),
+            self.threshold,
+            self.index,
+            to_hex(&self.data)
+        )
+    }
+}
+
+impl FromStr for Share {
+    type Err = Error;
+
+    /// Parse a share, as written by Display
+    fn from_str(text: &str) -> Result<Share, Error> {
+        let invalid_share = || Error::new(ErrorKind::InvalidData, "stegano/share : Invalid share!");
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.len() != 5 || lines[0] != SHARE_MAGIC {
+            return Err(invalid_share());
+        }
+
+        let to_bytes = |line: &str| hex_to_bytes(line).ok_or_else(invalid_share);
+        let to_id = |line: &str| to_id(line).ok_or_else(invalid_share);
+
+        Ok(Share {
+            id: to_id(lines[1])?,
+            threshold: lines[2].parse().map_err(|_| invalid_share())?,
+            index: lines[3].parse().map_err(|_| invalid_share())?,
+            data: to_bytes(lines[4])?,
+        })
+    }
+}
+
+/// Returns identifier or check given as an hexadecimal string, None if it is not one
+pub fn to_id(hex: &str) -> Option<[u8; ID_BYTES]> {
+    let bytes = hex_to_bytes(hex).filter(|bytes| bytes.len() == ID_BYTES)?;
+    let mut id = [0u8; ID_BYTES];
+    id.copy_from_slice(&bytes);
+    Some(id)
+}
+
+/// Returns random identifier
+pub fn random_id() -> Result<[u8; ID_BYTES], Error> {
+    let mut id = [0u8; ID_BYTES];
+    OsRng
+        .try_fill_bytes(&mut id)
+        .map_err(|err| Error::other(format!("stegano/random : {}", err)))?;
+    Ok(id)
+}
+
+/// Multiply two elements of GF(256), AES polynomial
+pub fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 };
+        b >>= 1;
+    }
+    product
+}
+
+/// Returns inverse of a non zero element of GF(256) : a^254
+pub fn gf_inv(a: u8) -> u8 {
+    let mut inverse = 1;
+    for _ in 0..254 {
+        inverse = gf_mul(inverse, a);
+    }
+    inverse
+}
+
+/// Returns data authenticated along with sealed secrets : identifier and threshold
+fn get_additional_data(id: &[u8; ID_BYTES], threshold: u8) -> Vec<u8> {
+    let mut additional_data = id.to_vec();
+    additional_data.push(threshold);
+    additional_data
+}
+
+/// Split secret into given number of shares, any threshold of them rebuilding it
+/// What is split is a random key, then the secret sealed under it
+pub fn split(secret: &[u8], threshold: u8, shares: u8) -> Result<Vec<Share>, Error> {
+    if threshold < 2 || threshold > shares {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "stegano/split : Threshold must be at least 2, and at most the number of shares!",
+        ));
+    }
+
+    let id = random_id()?;
+    let key = random_key()?;
+    let mut sealed = key.to_vec();
+    sealed.append(&mut seal_with_key(
+        &key,
+        secret,
+        &get_additional_data(&id, threshold),
+    ));
+
+    // Each secret byte is the constant term of a random polynomial of degree threshold - 1,
+    // evaluated at share index
+    let mut coefficients = vec![0u8; sealed.len() * (threshold as usize - 1)];
+    OsRng
+        .try_fill_bytes(&mut coefficients)
+        .map_err(|err| Error::other(format!("stegano/random : {}", err)))?;
+    Ok((1..=shares)
+        .map(|index| Share {
+            id,
+            threshold,
+            index,
+            data: sealed
+                .iter()
+                .zip(coefficients.chunks(threshold as usize - 1))
+                .map(|(&byte, polynomial)| {
+                    // Horner's method, constant term last
+                    let value = polynomial
+                        .iter()
+                        .rev()
+                        .fold(0, |value, &coefficient| gf_mul(value, index) ^ coefficient);
+                    gf_mul(value, index) ^ byte
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+/// Returns error of shares combined into a secret which does not authenticate
+fn corrupted_share() -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        "stegano/combine : Rebuilt secret does not authenticate, a share is corrupted!",
+    )
+}
+
+/// Rebuild secret from its shares, duplicates being ignored
+/// Fails if shares come from different secrets, or if fewer than threshold are given
+/// Only the first threshold shares are combined : a corrupted one among them makes it fail,
+/// even if other shares would have rebuilt the secret
+pub fn combine(shares: &[Share]) -> Result<Vec<u8>, Error> {
+    let first = shares
+        .first()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "stegano/combine : No share given!"))?;
+
+    let mut unique_shares: Vec<&Share> = Vec::new();
+    for share in shares {
+        if (share.id, share.threshold) != (first.id, first.threshold)
+            || share.data.len() != first.data.len()
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/combine : Shares come from different secrets!",
+            ));
+        }
+        if share.index != 0
+            && unique_shares
+                .iter()
+                .all(|unique| unique.index != share.index)
+        {
+            unique_shares.push(share);
+        }
+    }
+
+    if unique_shares.len() < first.threshold as usize {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "stegano/combine : Only {} valid shares, {} needed!",
+                unique_shares.len(),
+                first.threshold
+            ),
+        ));
+    }
+
+    // Lagrange interpolation at 0, over threshold shares
+    let shares = &unique_shares[..first.threshold as usize];
+    let weights: Vec<u8> = shares
+        .iter()
+        .map(|share| {
+            shares
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold(1, |weight, other| {
+                    gf_mul(
+                        weight,
+                        gf_mul(other.index, gf_inv(other.index ^ share.index)),
+                    )
+                })
+        })
+        .collect();
+    let sealed: Vec<u8> = (0..first.data.len())
+        .map(|position| {
+            shares
+                .iter()
+                .zip(&weights)
+                .fold(0, |byte, (share, &weight)| {
+                    byte ^ gf_mul(share.data[position], weight)
+                })
+        })
+        .collect();
+
+    if sealed.len() < KEY_BYTES {
+        return Err(corrupted_share());
+    }
+    let (key, sealed) = sealed.split_at(KEY_BYTES);
+    open_with_key(
+        key,
+        sealed,
+        &get_additional_data(&first.id, first.threshold),
+    )
+    .ok_or_else(corrupted_share)
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn test_split_and_combine() {
+        let secret = b"Hello how is the weather today";
+        let shares = split(secret, 3, 5).unwrap_or_else(|err| {
+            eprintln!("Error in test_split_and_combine: {}", err);
+            process::exit(1);
+        });
+        assert_eq!(shares.len(), 5);
+
+        // Any 3 shares rebuild the secret, once written as text and parsed back
+        for picked in &[[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let picked: Vec<Share> = picked
+                .iter()
+                .map(|&index| shares[index].to_string().parse::<Share>())
+                .collect::<Result<Vec<Share>, Error>>()
+                .unwrap_or_else(|err| {
+                    eprintln!("Error in test_split_and_combine: {}", err);
+                    process::exit(1);
+                });
+            assert_eq!(combine(&picked).ok(), Some(secret.to_vec()));
+        }
+
+        // Too few or corrupted shares are refused
+        assert!(combine(&[shares[0].clone(), shares[1].clone(), shares[1].clone()]).is_err());
+        let mut corrupted = shares[2].clone();
+        corrupted.data[0] ^= 1;
+        assert!(combine(&[shares[0].clone(), shares[1].clone(), corrupted]).is_err());
+        assert!(split(secret, 6, 5).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Sender signatures : messages may be signed with an Ed25519 key before being sealed,
+//! so that recipients know who hid them and that nobody substituted them
+//!
+//! Signed messages are wrapped in a text envelope (signer public key, signing time, signature),
+//! which is encrypted along with the message, so that signers are only known to recipients
+extern crate crypto;
+
+use self::crypto::{digest::Digest, ed25519, sha2::Sha256};
+
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::sealing::{from_hex, hex_to_bytes, to_hex, KEY_BYTES};
+
+/// First line of signed messages
+const ENVELOPE_MAGIC: &str = "STEGANO-SIGNED-MESSAGE";
+
+/// Context prepended to signed data, so that signatures cannot be used elsewhere
+const SIGNATURE_CONTEXT: &[u8] = b"stegano/signature\0";
+
+/// Size of Ed25519 signatures, in bytes
+const SIGNATURE_BYTES: usize = 64;
+
+/// Signer of a message, as read from its envelope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signer {
+    pub public_key: [u8; KEY_BYTES],
+    pub timestamp: u64,
+}
+
+impl Signer {
+    /// Returns signer public key fingerprint
+    pub fn get_fingerprint(&self) -> String {
+        get_fingerprint(&self.public_key)
+    }
+}
+
+/// Returns fingerprint of a public key : first 16 bytes of its SHA-256 digest, colon separated
+pub fn get_fingerprint(public_key: &[u8]) -> String {
+    let mut digest = [0u8; 32];
+    let mut hasher = Sha256::new();
+    hasher.input(public_key);
+    hasher.result(&mut digest);
+
+    digest[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+/// Returns public key of given signing key (Ed25519 seed)
+pub fn get_public_key(signing_key: &[u8; KEY_BYTES]) -> [u8; KEY_BYTES] {
+    ed25519::keypair(signing_key).1
+}
+
+/// Returns data covered by signatures : context, signer, signing time and message
+fn get_signed_data(public_key: &[u8], timestamp: u64, message: &str) -> Vec<u8> {
+    let mut data = SIGNATURE_CONTEXT.to_vec();
+    data.extend_from_slice(public_key);
+    data.extend_from_slice(&timestamp.to_be_bytes());
+    data.extend_from_slice(message.as_bytes());
+    data
+}
+
+/// Wrap message in a signed envelope, with given signing key (Ed25519 seed)
+pub fn sign_message(message: &str, signing_key: &[u8; KEY_BYTES]) -> String {
+    let (secret_key, public_key) = ed25519::keypair(signing_key);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let signature = ed25519::signature(
+        &get_signed_data(&public_key, timestamp, message),
+        &secret_key,
+    );
+
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        ENVELOPE_MAGIC,
+        to_hex(&public_key),
+        timestamp,
+        to_hex(&signature),
+        message
+    )
+}
+
+/// Unwrap a decoded message : returns message and its signer, None if it was not signed
+/// Signed messages whose signature does not match are refused
+pub fn open_message(decoded: &str) -> Result<(String, Option<Signer>), Error> {
+    let mut lines = decoded.splitn(5, '\n');
+    if lines.next() != Some(ENVELOPE_MAGIC) {
+        return Ok((decoded.to_string(), None));
+    }
+
+    let invalid_signature = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            "stegano/signature : Message signature is invalid!",
+        )
+    };
+
+    let public_key = from_hex(lines.next().unwrap_or_default()).map_err(|_| invalid_signature())?;
+    let timestamp = lines
+        .next()
+        .and_then(|timestamp| timestamp.parse::<u64>().ok())
+        .ok_or_else(invalid_signature)?;
+    let signature = lines.next().unwrap_or_default();
+    let message = lines.next().ok_or_else(invalid_signature)?;
+
+    let signature_bytes = hex_to_bytes(signature)
+        .filter(|bytes| bytes.len() == SIGNATURE_BYTES)
+        .ok_or_else(invalid_signature)?;
+
+    if !ed25519::verify(
+        &get_signed_data(&public_key, timestamp, message),
+        &public_key,
+        &signature_bytes,
+    ) {
+        return Err(invalid_signature());
+    }
+
+    Ok((
+        message.to_string(),
+        Some(Signer {
+            public_key,
+            timestamp,
+        }),
+    ))
+}
+
+/// Trusted signers : public keys, with names
+/// File holds one key per line, hexadecimal public key then name, '#' starting comments
+pub struct TrustedKeys {
+    keys: Vec<([u8; KEY_BYTES], String)>,
+}
+
+impl TrustedKeys {
+    /// Read trusted keys file
+    pub fn open(filepath: &str) -> Result<TrustedKeys, Error> {
+        TrustedKeys::from_string(&fs::read_to_string(filepath)?)
+    }
+
+    /// Parse trusted keys file contents
+    pub fn from_string(contents: &str) -> Result<TrustedKeys, Error> {
+        let mut keys = Vec::new();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, char::is_whitespace);
+            let key = from_hex(parts.next().unwrap_or_default())?;
+            keys.push((key, parts.next().unwrap_or_default().trim().to_string()));
+        }
+
+        Ok(TrustedKeys { keys })
+    }
+
+    /// Returns name of given public key, None if it is not trusted
+    pub fn get_name(&self, public_key: &[u8; KEY_BYTES]) -> Option<&str> {
+        self.keys
+            .iter()
+            .find(|(key, _)| key == public_key)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::sealing::random_key;
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn test_sign_and_open_message() {
+        let signing_key = random_key().unwrap_or_else(|err| {
+            eprintln!("Error in test_sign_and_open_message: {}", err);
+            process::exit(1);
+        });
+        let public_key = get_public_key(&signing_key);
+
+        let signed = sign_message("Hello how is the\nweather today", &signing_key);
+        let (message, signer) = open_message(&signed).unwrap_or_else(|err| {
+            eprintln!("Error in test_sign_and_open_message: {}", err);
+            process::exit(1);
+        });
+        assert_eq!(message, "Hello how is the\nweather today");
+        assert_eq!(signer.map(|signer| signer.public_key), Some(public_key));
+
+        // Unsigned messages are left as they are, substituted ones are refused
+        assert_eq!(
+            open_message("Hello").ok(),
+            Some(("Hello".to_string(), None))
+        );
+        assert!(open_message(&signed.replace("weather", "wheather")).is_err());
+
+        let trusted_keys =
+            TrustedKeys::from_string(&format!("# Team\n{} Alice\n", to_hex(&public_key)))
+                .expect("Trusted keys");
+        assert_eq!(trusted_keys.get_name(&public_key), Some("Alice"));
+        assert_eq!(trusted_keys.get_name(&[0; KEY_BYTES]), None);
+        assert_eq!(get_fingerprint(&public_key).len(), 47);
+    }
+}
@@ -0,0 +1,211 @@
+//! Syndrome-trellis codes : embed a message as the syndrome of the cover bits,
+//! choosing, through a Viterbi walk, the stego bits with the minimal total distortion cost
+use std::{
+    cmp,
+    io::{Error, ErrorKind},
+};
+
+use super::cypher::KeyStream;
+
+/// Height of the parity-check submatrix, trellis has 2^CONSTRAINT_HEIGHT states
+pub const CONSTRAINT_HEIGHT: usize = 7;
+
+/// Number of trellis states
+const STATES: usize = 1 << CONSTRAINT_HEIGHT;
+
+/// Generate the parity-check submatrix, as `width` columns of CONSTRAINT_HEIGHT bits
+/// First and last rows are always set, as recommended by Filler, Judas and Fridrich
+pub fn generate_submatrix(stream: &mut KeyStream, width: usize) -> Vec<u32> {
+    let edges = 1 | (1 << (CONSTRAINT_HEIGHT - 1));
+
+    (0..width)
+        .map(|_| (stream.next_u32() & (STATES as u32 - 1)) | edges)
+        .collect()
+}
+
+/// Returns the number of cover bits used per message bit
+pub fn get_width(cover_length: usize, message_length: usize) -> usize {
+    cover_length.checked_div(message_length).unwrap_or(0)
+}
+
+/// Returns submatrix column, truncated to the rows remaining after block index
+fn get_column(submatrix: &[u32], block: usize, column: usize, message_length: usize) -> u32 {
+    let height = cmp::min(CONSTRAINT_HEIGHT, message_length - block);
+    submatrix[column] & ((1 << height) - 1)
+}
+
+/// Embed message into cover bits, given each cover bit's flipping cost
+/// Returns stego bits, whose syndrome is the message
+pub fn embed(
+    cover: &[bool],
+    costs: &[f64],
+    message: &[bool],
+    submatrix: &[u32],
+) -> Result<Vec<bool>, Error> {
+    let message_length = message.len();
+    let width = submatrix.len();
+
+    if cover.len() != costs.len() || width * message_length > cover.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "stegano/stc_embed : Cover too small for message!",
+        ));
+    }
+
+    // Weight of the best path leading to each state, and the bit chosen at each step
+    let mut weights = vec![f64::INFINITY; STATES];
+    let mut new_weights = vec![f64::INFINITY; STATES];
+    let mut paths = vec![0u128; width * message_length];
+
+    weights[0] = 0.0;
+
+    for (block, message_bit) in message.iter().enumerate() {
+        for column in 0..width {
+            let index = block * width + column;
+            let col = get_column(submatrix, block, column, message_length) as usize;
+
+            // Cost of keeping the cover bit, and cost of flipping it
+            let (cost0, cost1) = if cover[index] {
+                (costs[index], 0.0)
+            } else {
+                (0.0, costs[index])
+            };
+
+            let mut path = 0u128;
+            for (state, new_weight) in new_weights.iter_mut().enumerate() {
+                let weight0 = weights[state] + cost0;
+                let weight1 = weights[state ^ col] + cost1;
+
+                if weight1 < weight0 {
+                    *new_weight = weight1;
+                    path |= 1 << state;
+                } else {
+                    *new_weight = weight0;
+                }
+            }
+
+            paths[index] = path;
+            weights.copy_from_slice(&new_weights);
+        }
+
+        // Lowest state bit must match message bit, then move one row down
+        for state in 0..STATES / 2 {
+            new_weights[state] = weights[2 * state + *message_bit as usize];
+        }
+        for weight in new_weights.iter_mut().skip(STATES / 2) {
+            *weight = f64::INFINITY;
+        }
+        weights.copy_from_slice(&new_weights);
+    }
+
+    if !weights[0].is_finite() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "stegano/stc_embed : No path found in trellis!",
+        ));
+    }
+
+    // Walk back the trellis from the final state
+    let mut stego = cover.to_vec();
+    let mut state = 0;
+
+    for block in (0..message_length).rev() {
+        state = ((state << 1) | message[block] as usize) & (STATES - 1);
+
+        for column in (0..width).rev() {
+            let index = block * width + column;
+            let bit = (paths[index] >> state) & 1 == 1;
+
+            stego[index] = bit;
+            if bit {
+                state ^= get_column(submatrix, block, column, message_length) as usize;
+            }
+        }
+    }
+
+    Ok(stego)
+}
+
+/// Extract message of given length from stego bits, computing their syndrome
+pub fn extract(stego: &[bool], message_length: usize, submatrix: &[u32]) -> Vec<bool> {
+    let width = submatrix.len();
+    let mut syndrome = vec![false; message_length];
+
+    for block in 0..message_length {
+        for column in 0..width {
+            if stego[block * width + column] {
+                let col = get_column(submatrix, block, column, message_length);
+                for (row, bit) in syndrome.iter_mut().skip(block).enumerate() {
+                    if row >= CONSTRAINT_HEIGHT {
+                        break;
+                    }
+                    *bit ^= (col >> row) & 1 == 1;
+                }
+            }
+        }
+    }
+
+    syndrome
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn test_embed_extract() {
+        let mut stream = KeyStream::from_password("Password", "stegano/test");
+
+        let cover: Vec<bool> = (0..4000).map(|_| stream.next_u32() % 2 == 1).collect();
+        let costs: Vec<f64> = (0..4000)
+            .map(|_| 1.0 + stream.next_below(10) as f64)
+            .collect();
+        let message: Vec<bool> = (0..1000).map(|_| stream.next_u32() % 2 == 1).collect();
+
+        let submatrix = generate_submatrix(&mut stream, get_width(cover.len(), message.len()));
+
+        let stego = embed(&cover, &costs, &message, &submatrix).unwrap_or_else(|err| {
+            eprintln!("Error in test_embed_extract: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(extract(&stego, message.len(), &submatrix), message);
+
+        // Matrix embedding must change far less bits than plain replacement would
+        let changes = cover
+            .iter()
+            .zip(stego.iter())
+            .filter(|(c, s)| c != s)
+            .count();
+        assert!(changes < 400);
+    }
+
+    #[test]
+    fn test_embed_avoids_costly_bits() {
+        let mut stream = KeyStream::from_password("Password", "stegano/test");
+
+        let cover: Vec<bool> = (0..2000).map(|_| stream.next_u32() % 2 == 1).collect();
+        let message: Vec<bool> = (0..200).map(|_| stream.next_u32() % 2 == 1).collect();
+
+        // Even cover bits are nearly forbidden
+        let costs: Vec<f64> = (0..2000)
+            .map(|i| if i % 2 == 0 { 1000.0 } else { 1.0 })
+            .collect();
+
+        let submatrix = generate_submatrix(&mut stream, get_width(cover.len(), message.len()));
+
+        let stego = embed(&cover, &costs, &message, &submatrix).unwrap_or_else(|err| {
+            eprintln!("Error in test_embed_avoids_costly_bits: {}", err);
+            process::exit(1);
+        });
+
+        assert_eq!(extract(&stego, message.len(), &submatrix), message);
+        assert!(cover
+            .iter()
+            .zip(stego.iter())
+            .enumerate()
+            .all(|(i, (c, s))| i % 2 == 1 || c == s));
+    }
+}
@@ -0,0 +1,352 @@
+//! Steganalysis : detectors telling how likely a picture holds an LSB payload,
+//! to measure how detectable encoded pictures are, or to scan suspicious ones
+//!
+//! Chi-square attack (Westfeld and Pfitzmann) : LSB replacement evens out the counts of
+//! values 2k and 2k + 1, which natural pictures seldom have
+//!
+//! RS analysis (Fridrich, Goljan and Du) and sample pair analysis (Dumitrescu, Wu and Wang)
+//! estimate the embedding rate, LSB replacement breaking the smoothness statistics
+//! of neighboring pixels in a way they can measure
+extern crate image;
+
+use self::image::DynamicImage;
+
+use super::picture::{ColorType, Picture};
+
+/// Names of the channels analyzed by embedding rate estimators
+pub const RGB_CHANNEL_NAMES: [&str; 3] = ["red", "green", "blue"];
+
+/// Flipping mask of RS analysis groups
+const RS_MASK: [i16; 4] = [0, 1, 1, 0];
+
+/// Chi-square attack result, for one color channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChiSquare {
+    /// Probability of embedding, over the whole channel
+    pub probability: f64,
+    /// Probability of embedding over the first rows, block of rows after block of rows
+    /// Sequential embedding shows a curve falling from about 1 where the payload ends
+    pub curve: Vec<f64>,
+}
+
+/// Returns names of the color channels of given color type
+pub fn get_channel_names(color_type: ColorType) -> Vec<&'static str> {
+    match color_type {
+        ColorType::Gray | ColorType::GrayAlpha => vec!["gray"],
+        ColorType::Rgb | ColorType::Rgba => RGB_CHANNEL_NAMES.to_vec(),
+        ColorType::Indexed => vec!["index"],
+    }
+}
+
+/// Returns natural logarithm of the gamma function (Lanczos approximation)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000000000190015, |sum, (index, coefficient)| {
+            sum + coefficient / (x + 1.0 + index as f64)
+        });
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// Returns regularized lower incomplete gamma function P(a, x)
+/// Series expansion below a + 1, continued fraction above
+fn lower_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let (mut term, mut sum) = (1.0 / a, 1.0 / a);
+        for n in 1..1000 {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        (sum * prefix).min(1.0)
+    } else {
+        // Lentz's method
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut fraction = d;
+        for n in 1..1000 {
+            let an = -(n as f64) * (n as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            d = if d.abs() < tiny { tiny } else { d };
+            c = b + an / c;
+            c = if c.abs() < tiny { tiny } else { c };
+            d = 1.0 / d;
+            fraction *= d * c;
+            if (d * c - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        (1.0 - prefix * fraction).max(0.0)
+    }
+}
+
+/// Returns probability of embedding, given histogram of values
+/// Pairs of values never seen are left out
+fn get_chi_square_probability(histogram: &[u64]) -> f64 {
+    let (statistic, pairs) = histogram
+        .chunks(2)
+        .filter(|pair| pair.len() == 2 && pair[0] + pair[1] > 0)
+        .fold((0.0, 0), |(statistic, pairs), pair| {
+            let expected = (pair[0] + pair[1]) as f64 / 2.0;
+            let deviation = pair[0] as f64 - expected;
+            (statistic + deviation * deviation / expected, pairs + 1)
+        });
+
+    if pairs < 2 {
+        return 0.0;
+    }
+
+    // Probability that the statistic is that low with evened out pairs, pairs - 1 degrees of freedom
+    1.0 - lower_gamma((pairs - 1) as f64 / 2.0, statistic / 2.0)
+}
+
+/// Run chi-square attack on every color channel
+/// Curve gets a point every given number of rows
+pub fn chi_square(picture: &Picture, block_rows: usize) -> Vec<ChiSquare> {
+    let (width, _) = picture.dimensions();
+    let block_size = width as usize * block_rows.max(1);
+
+    (0..picture.get_color_type().get_color_channels())
+        .map(|channel| {
+            let plane = picture.get_color_plane(channel);
+            let mut histogram = vec![0u64; picture.get_max_value() as usize + 1];
+
+            let curve = plane
+                .chunks(block_size.max(1))
+                .map(|block| {
+                    for &value in block {
+                        histogram[value as usize] += 1;
+                    }
+                    get_chi_square_probability(&histogram)
+                })
+                .collect();
+
+            ChiSquare {
+                probability: get_chi_square_probability(&histogram),
+                curve,
+            }
+        })
+        .collect()
+}
+
+/// Returns planes of the red, green and blue channels, row after row, with picture width
+fn get_rgb_planes(image: &DynamicImage) -> (Vec<Vec<i16>>, usize) {
+    let buffer = image.to_rgb();
+    let width = buffer.width() as usize;
+    let raw = buffer.into_raw();
+
+    let planes = (0..3)
+        .map(|channel| {
+            raw.iter()
+                .skip(channel)
+                .step_by(3)
+                .map(|&value| value as i16)
+                .collect()
+        })
+        .collect();
+    (planes, width)
+}
+
+/// Returns the smaller root, in absolute value, of a x^2 + b x + c, None if it has none
+/// Without real root, the real part of the complex ones is returned, as statistics are noisy
+fn get_smaller_root(a: f64, b: f64, c: f64) -> Option<f64> {
+    if a.abs() < 1e-12 {
+        return if b.abs() < 1e-12 { None } else { Some(-c / b) };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Some(-b / (2.0 * a));
+    }
+
+    let roots = [
+        (-b + discriminant.sqrt()) / (2.0 * a),
+        (-b - discriminant.sqrt()) / (2.0 * a),
+    ];
+    Some(if roots[0].abs() < roots[1].abs() {
+        roots[0]
+    } else {
+        roots[1]
+    })
+}
+
+/// Apply LSB flipping (F1 : 2k <-> 2k + 1) or shifted flipping (F-1 : 2k <-> 2k - 1)
+fn flip(value: i16, direction: i16) -> i16 {
+    match direction {
+        1 => value ^ 1,
+        -1 => ((value + 1) ^ 1) - 1,
+        _ => value,
+    }
+}
+
+/// Returns smoothness of a group of pixels : sum of absolute differences of neighbors
+fn get_smoothness(group: &[i16]) -> i32 {
+    group
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs() as i32)
+        .sum()
+}
+
+/// Returns differences between regular and singular groups proportions,
+/// with mask then with negative mask
+fn get_rs_differences(plane: &[i16], width: usize) -> (f64, f64) {
+    let (mut differences, mut groups) = ([0i64; 2], 0);
+
+    for row in plane.chunks(width) {
+        for group in row.chunks_exact(RS_MASK.len()) {
+            let smoothness = get_smoothness(group);
+            groups += 1;
+
+            for (difference, sign) in differences.iter_mut().zip(&[1, -1]) {
+                let flipped: Vec<i16> = group
+                    .iter()
+                    .zip(&RS_MASK)
+                    .map(|(&value, &mask)| flip(value, mask * sign))
+                    .collect();
+                *difference += (get_smoothness(&flipped) - smoothness).signum() as i64;
+            }
+        }
+    }
+
+    let groups = groups.max(1) as f64;
+    (
+        differences[0] as f64 / groups,
+        differences[1] as f64 / groups,
+    )
+}
+
+/// RS analysis : returns estimated embedding rate of the red, green and blue channels,
+/// from 0 for clean pictures up to 1 for pictures whose every LSB was replaced
+pub fn rs_analysis(image: &DynamicImage) -> Vec<f64> {
+    let (planes, width) = get_rgb_planes(image);
+
+    planes
+        .iter()
+        .map(|plane| {
+            // Statistics of the picture, then of the picture with every LSB flipped
+            let (d0, dn0) = get_rs_differences(plane, width);
+            let flipped: Vec<i16> = plane.iter().map(|&value| value ^ 1).collect();
+            let (d1, dn1) = get_rs_differences(&flipped, width);
+
+            get_smaller_root(2.0 * (d1 + d0), dn0 - dn1 - d1 - 3.0 * d0, d0 - dn0)
+                .map(|z| z / (z - 0.5))
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// Sample pair analysis : returns estimated embedding rate of the red, green and blue channels,
+/// from horizontally adjacent pixels pairs
+pub fn sample_pair_analysis(image: &DynamicImage) -> Vec<f64> {
+    let (planes, width) = get_rgb_planes(image);
+
+    planes
+        .iter()
+        .map(|plane| {
+            // X : pairs whose larger value is odd, Y : even, Z : equal values,
+            // W : pairs of Y only differing by their LSB
+            let (mut x, mut y, mut z, mut w, mut pairs) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for row in plane.chunks(width) {
+                for pair in row.windows(2) {
+                    let (u, v) = (pair[0], pair[1]);
+                    pairs += 1.0;
+
+                    if u == v {
+                        z += 1.0;
+                    } else if (v % 2 == 0 && u < v) || (v % 2 == 1 && u > v) {
+                        x += 1.0;
+                    } else {
+                        y += 1.0;
+                        if u / 2 == v / 2 {
+                            w += 1.0;
+                        }
+                    }
+                }
+            }
+
+            get_smaller_root((w + z) / 2.0, 2.0 * x - pairs, y - x)
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+    use super::*;
+
+    #[test]
+    fn test_chi_square() {
+        // Values 2k and 2k + 1 evened out in the first half only, as sequential LSB embedding does
+        let width = 64;
+        let samples: Vec<u16> = (0..width * 64)
+            .map(|index| {
+                let value = (index * 7 % 61) * 4;
+                if index < width * 32 {
+                    value + (index / 3 % 2)
+                } else {
+                    value
+                }
+            })
+            .collect();
+        let picture =
+            Picture::new(width as u32, 64, ColorType::Gray, 8, samples).expect("Gray picture");
+
+        let results = chi_square(&picture, 8);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].curve.len(), 8);
+        assert!(results[0].curve[0] > 0.9);
+        assert!(results[0].probability < 0.1);
+
+        assert!((lower_gamma(1.0, 2.0) - (1.0 - (-2.0f64).exp())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_embedding_rate_estimators() {
+        // Half of the LSBs of the test picture are replaced with random bits
+        let picture = Picture::open("test_files/test.png").expect("Test picture");
+        let mut stego = picture.clone();
+        let mut rng = rand::thread_rng();
+        for index in 0..stego.get_color_samples_count() {
+            if rng.gen::<bool>() {
+                let value = stego.get_color_sample(index);
+                stego.set_color_sample(index, (value & !1) | rng.gen::<bool>() as u16);
+            }
+        }
+
+        let cover = picture.to_dynamic_image().expect("Cover image");
+        let stego = stego.to_dynamic_image().expect("Stego image");
+        for estimator in &[rs_analysis, sample_pair_analysis] {
+            let (cover_rates, stego_rates) = (estimator(&cover), estimator(&stego));
+            assert_eq!(cover_rates.len(), 3);
+            assert!(cover_rates.iter().all(|&rate| rate < 0.1));
+            assert!(stego_rates.iter().all(|&rate| (rate - 0.5).abs() < 0.1));
+        }
+    }
+}
@@ -0,0 +1,487 @@
+//! Embedding strategies : how framed message bits are spread over the slots of a carrier
+//! A strategy is known by an identifier and a one byte parameter, both written in the header,
+//! so that decoding finds the matching strategy in a registry
+//!
+//! Built-in strategies use identifiers below 128, identifiers from 128 being left to third parties
+//! Plain LSB and PVD are embedding modes, not strategies, so that each lives in one place
+extern crate rand;
+
+use self::rand::Rng;
+
+use std::io::{Error, ErrorKind};
+
+use super::carrier::{self, read_framed_message, Carrier};
+use super::cypher::KeyStream;
+
+/// Key derivation context of the random walk
+const WALK_CONTEXT: &str = "stegano/strategy/walk";
+
+/// Highest number of low bits LSB strategies may use
+const MAX_LOW_BITS: u8 = 8;
+
+/// Default number of message bits held by a matrix embedding group
+const DEFAULT_MATRIX_BITS: u8 = 3;
+
+/// Highest number of message bits held by a matrix embedding group
+const MAX_MATRIX_BITS: u8 = 7;
+
+/// First identifier left to third party strategies
+pub const FIRST_CUSTOM_ID: u8 = 128;
+
+/// Way of spreading framed message bits over carrier slots, from a given slot index
+pub trait EmbeddingStrategy {
+    /// Returns strategy identifier, as stored in the header
+    fn get_id(&self) -> u8;
+
+    /// Returns strategy parameter, as stored in the header
+    fn get_parameter(&self) -> u8 {
+        0
+    }
+
+    /// Store framed message bits in carrier slots, from slot at given index
+    fn embed(
+        &self,
+        carrier: &mut dyn Carrier,
+        start: usize,
+        bits: &[bool],
+        password: &str,
+    ) -> Result<(), Error>;
+
+    /// Read framed message from carrier slots, from slot at given index
+    fn extract(
+        &self,
+        carrier: &dyn Carrier,
+        start: usize,
+        password: &str,
+    ) -> Result<Vec<u8>, Error>;
+}
+
+/// Builds a strategy from the parameter read in the header, 0 meaning the default one
+pub type StrategyFactory = fn(u8) -> Result<Box<dyn EmbeddingStrategy>, Error>;
+
+/// Returns error telling carrier is too small
+fn capacity_error() -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        "stegano/encode : Input file not big enough to store message!",
+    )
+}
+
+/// Returns error telling strategy parameter is out of range
+fn parameter_error(name: &str, max: u8) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "stegano/strategy : {} parameter must range from 1 to {}",
+            name, max
+        ),
+    )
+}
+
+/// Returns number of low bits given by a parameter, 0 meaning 1
+fn get_low_bits(parameter: u8) -> Result<u8, Error> {
+    match parameter {
+        0 => Ok(1),
+        1..=MAX_LOW_BITS => Ok(parameter),
+        _ => Err(parameter_error("LSB", MAX_LOW_BITS)),
+    }
+}
+
+/// Least significant bits, slots being visited in a password-derived order
+pub struct RandomWalkLsb {
+    low_bits: u8,
+}
+
+impl RandomWalkLsb {
+    /// Constructor, with number of low bits used in each slot
+    pub fn new(low_bits: u8) -> Result<RandomWalkLsb, Error> {
+        Ok(RandomWalkLsb {
+            low_bits: get_low_bits(low_bits)?,
+        })
+    }
+
+    /// Returns slots from given index, shuffled by the password
+    fn get_walk(carrier: &dyn Carrier, start: usize, password: &str) -> Vec<usize> {
+        let mut walk: Vec<usize> = (start..carrier.get_slots_count()).collect();
+        KeyStream::from_password(password, WALK_CONTEXT).shuffle(&mut walk);
+        walk
+    }
+}
+
+impl EmbeddingStrategy for RandomWalkLsb {
+    fn get_id(&self) -> u8 {
+        1
+    }
+
+    fn get_parameter(&self) -> u8 {
+        self.low_bits
+    }
+
+    /// Slots left out of the walk get random bits, to hide message length
+    fn embed(
+        &self,
+        carrier: &mut dyn Carrier,
+        start: usize,
+        bits: &[bool],
+        password: &str,
+    ) -> Result<(), Error> {
+        let walk = RandomWalkLsb::get_walk(carrier, start, password);
+        if walk.len() * (self.low_bits as usize) < bits.len() {
+            return Err(capacity_error());
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut chunks = bits.chunks(self.low_bits as usize);
+
+        for index in walk {
+            match chunks.next() {
+                Some(chunk) => carrier.write_slot(index, self.low_bits, chunk)?,
+                None => {
+                    let random: Vec<bool> = (0..self.low_bits).map(|_| rng.gen()).collect();
+                    carrier.write_slot(index, self.low_bits, &random)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extract(
+        &self,
+        carrier: &dyn Carrier,
+        start: usize,
+        password: &str,
+    ) -> Result<Vec<u8>, Error> {
+        read_framed_message(
+            RandomWalkLsb::get_walk(carrier, start, password)
+                .into_iter()
+                .map(|index| carrier.read_slot(index, self.low_bits)),
+        )
+    }
+}
+
+/// LSB matching (±1 embedding) : slots whose least significant bit differs from the message
+/// are randomly incremented or decremented, instead of having their bit replaced
+/// Slots must hold plain values
+pub struct LsbMatching;
+
+impl EmbeddingStrategy for LsbMatching {
+    fn get_id(&self) -> u8 {
+        2
+    }
+
+    /// Remaining slots get random bits, to hide message length
+    fn embed(
+        &self,
+        carrier: &mut dyn Carrier,
+        start: usize,
+        bits: &[bool],
+        _password: &str,
+    ) -> Result<(), Error> {
+        let max = carrier.get_slot_max_value().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "stegano/strategy : LSB matching needs slots holding plain values!",
+            )
+        })?;
+        if carrier.get_slots_count().saturating_sub(start) < bits.len() {
+            return Err(capacity_error());
+        }
+
+        let mut rng = rand::thread_rng();
+
+        for index in start..carrier.get_slots_count() {
+            let bit = match bits.get(index - start) {
+                Some(&bit) => bit,
+                None => rng.gen(),
+            };
+            let value = carrier.read_slot_value(index)?;
+
+            if (value & 1 == 1) != bit {
+                let value = if value == 0 || (value < max && rng.gen()) {
+                    value + 1
+                } else {
+                    value - 1
+                };
+                carrier.write_slot_value(index, value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extract(
+        &self,
+        carrier: &dyn Carrier,
+        start: usize,
+        _password: &str,
+    ) -> Result<Vec<u8>, Error> {
+        carrier::read_framed_from(carrier, start, 1)
+    }
+}
+
+/// Matrix embedding (Hamming codes) : each group of 2^k - 1 slots holds k bits in the syndrome
+/// of its least significant bits, at the cost of one changed slot at most
+pub struct MatrixEmbedding {
+    bits_per_group: u8,
+}
+
+impl MatrixEmbedding {
+    /// Constructor, with number of message bits held by each group
+    pub fn new(bits_per_group: u8) -> Result<MatrixEmbedding, Error> {
+        match bits_per_group {
+            0 => Ok(MatrixEmbedding {
+                bits_per_group: DEFAULT_MATRIX_BITS,
+            }),
+            1..=MAX_MATRIX_BITS => Ok(MatrixEmbedding { bits_per_group }),
+            _ => Err(parameter_error("Matrix embedding", MAX_MATRIX_BITS)),
+        }
+    }
+
+    /// Returns number of slots of a group
+    fn get_group_size(&self) -> usize {
+        (1 << self.bits_per_group) - 1
+    }
+
+    /// Returns syndrome of group starting at given slot : xor of positions of odd slots
+    fn get_syndrome(&self, carrier: &dyn Carrier, first: usize) -> Result<usize, Error> {
+        let mut syndrome = 0;
+        for position in 0..self.get_group_size() {
+            if carrier.read_slot(first + position, 1)?[0] {
+                syndrome ^= position + 1;
+            }
+        }
+        Ok(syndrome)
+    }
+}
+
+impl EmbeddingStrategy for MatrixEmbedding {
+    fn get_id(&self) -> u8 {
+        3
+    }
+
+    fn get_parameter(&self) -> u8 {
+        self.bits_per_group
+    }
+
+    /// Remaining groups get random bits, to hide message length
+    fn embed(
+        &self,
+        carrier: &mut dyn Carrier,
+        start: usize,
+        bits: &[bool],
+        _password: &str,
+    ) -> Result<(), Error> {
+        let size = self.get_group_size();
+        let groups = carrier.get_slots_count().saturating_sub(start) / size;
+        let count = self.bits_per_group as usize;
+        if groups * count < bits.len() {
+            return Err(capacity_error());
+        }
+
+        let mut rng = rand::thread_rng();
+
+        for group in 0..groups {
+            let first = start + group * size;
+            let target = (0..count).fold(0, |target, position| {
+                let bit = match bits.get(group * count + position) {
+                    Some(&bit) => bit,
+                    None => group * count >= bits.len() && rng.gen(),
+                };
+                (target << 1) | bit as usize
+            });
+
+            let change = self.get_syndrome(carrier, first)? ^ target;
+            if change != 0 {
+                let index = first + change - 1;
+                let bit = carrier.read_slot(index, 1)?[0];
+                carrier.write_slot(index, 1, &[!bit])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extract(
+        &self,
+        carrier: &dyn Carrier,
+        start: usize,
+        _password: &str,
+    ) -> Result<Vec<u8>, Error> {
+        let size = self.get_group_size();
+        let count = self.bits_per_group as usize;
+        let groups = carrier.get_slots_count().saturating_sub(start) / size;
+
+        read_framed_message((0..groups).map(|group| {
+            let syndrome = self.get_syndrome(carrier, start + group * size)?;
+            Ok((0..count)
+                .rev()
+                .map(|shift| (syndrome >> shift) & 1 == 1)
+                .collect())
+        }))
+    }
+}
+
+/// Known strategies, by identifier and name
+pub struct StrategyRegistry {
+    entries: Vec<(u8, String, StrategyFactory)>,
+}
+
+impl StrategyRegistry {
+    /// Constructor of an empty registry
+    pub fn new() -> StrategyRegistry {
+        StrategyRegistry {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add a strategy, identifier and name being unique
+    /// Name is matched whatever its case
+    pub fn register(&mut self, id: u8, name: &str, factory: StrategyFactory) -> Result<(), Error> {
+        let name = name.to_uppercase();
+
+        if self
+            .entries
+            .iter()
+            .any(|(known_id, known_name, _)| *known_id == id || *known_name == name)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "stegano/strategy : Strategy {} ({}) is already registered",
+                    name, id
+                ),
+            ));
+        }
+
+        self.entries.push((id, name, factory));
+        Ok(())
+    }
+
+    /// Returns strategy matching identifier and parameter read in the header
+    pub fn from_header(&self, id: u8, parameter: u8) -> Result<Box<dyn EmbeddingStrategy>, Error> {
+        match self.entries.iter().find(|(known_id, _, _)| *known_id == id) {
+            Some((_, _, factory)) => factory(parameter),
+            None => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("stegano/strategy : Unknown strategy identifier {}", id),
+            )),
+        }
+    }
+
+    /// Returns strategy matching name, as typed by user : NAME or NAME:parameter
+    pub fn from_name(&self, name: &str) -> Result<Box<dyn EmbeddingStrategy>, Error> {
+        let mut parts = name.splitn(2, ':');
+        let strategy_name = parts.next().unwrap_or_default().to_uppercase();
+        let parameter = match parts.next().map(|parameter| parameter.parse::<u8>()) {
+            None => 0,
+            Some(Ok(parameter)) => parameter,
+            Some(Err(error)) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("stegano/strategy : {}", error),
+                ))
+            }
+        };
+
+        match self
+            .entries
+            .iter()
+            .find(|(_, known_name, _)| *known_name == strategy_name)
+        {
+            Some((_, _, factory)) => factory(parameter),
+            None => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("stegano/strategy : Unknown strategy {}", name),
+            )),
+        }
+    }
+
+    /// Returns names of registered strategies
+    pub fn get_names(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|(_, name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// Registry of built-in strategies
+impl Default for StrategyRegistry {
+    fn default() -> StrategyRegistry {
+        let mut registry = StrategyRegistry::new();
+        let builtins: [(u8, &str, StrategyFactory); 3] = [
+            (1, "RANDOM_WALK", |parameter| {
+                Ok(Box::new(RandomWalkLsb::new(parameter)?))
+            }),
+            (2, "MATCHING", |_| Ok(Box::new(LsbMatching))),
+            (3, "MATRIX", |parameter| {
+                Ok(Box::new(MatrixEmbedding::new(parameter)?))
+            }),
+        ];
+
+        for &(id, name, factory) in builtins.iter() {
+            registry
+                .register(id, name, factory)
+                .expect("Built-in strategies are unique");
+        }
+
+        registry
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::carrier::tests::MemoryCarrier;
+    use super::*;
+
+    #[test]
+    fn test_strategies() {
+        let registry = StrategyRegistry::default();
+        let message = b"Hello how is the weather today";
+        let bits = carrier::frame_message(message);
+
+        for name in &[
+            "RANDOM_WALK",
+            "random_walk:2",
+            "MATCHING",
+            "MATRIX",
+            "MATRIX:5",
+        ] {
+            let strategy = registry.from_name(name).expect("Known strategy");
+            let mut carrier = MemoryCarrier {
+                bytes: (0..3000).map(|i| ((i * 37) % 251) as u8).collect(),
+            };
+
+            strategy
+                .embed(&mut carrier, 10, &bits, "Password")
+                .expect("Message embedded");
+
+            // Strategy is rebuilt from what the header holds
+            let decoder = registry
+                .from_header(strategy.get_id(), strategy.get_parameter())
+                .expect("Known identifier");
+            assert_eq!(
+                decoder
+                    .extract(&carrier, 10, "Password")
+                    .expect("Extracted"),
+                message.to_vec(),
+                "Strategy {}",
+                name
+            );
+
+            let mut small = MemoryCarrier {
+                bytes: vec![100; 50],
+            };
+            assert!(strategy.embed(&mut small, 10, &bits, "Password").is_err());
+        }
+
+        assert!(registry.from_name("UNKNOWN").is_err());
+        assert!(registry.from_name("MATRIX:9").is_err());
+        assert!(registry.from_header(200, 0).is_err());
+        assert!(StrategyRegistry::default()
+            .register(1, "OTHER", |_| Ok(Box::new(LsbMatching)))
+            .is_err());
+    }
+}
@@ -0,0 +1,293 @@
+//! WAV audio files holding 8, 16 or 24 bits PCM samples
+//! Samples are changed in place within the file contents, so that every other byte,
+//! headers and extra chunks included, is written back as it was read
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Error, ErrorKind, Read, Write},
+    ops::Range,
+};
+
+/// Format tag of PCM samples
+const WAVE_FORMAT_PCM: u16 = 1;
+
+/// Format tag of extensible format, the actual one being given by the sub-format
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Returns a format error, with given message
+fn format_error(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("stegano/wav : {}", message))
+}
+
+/// Returns little endian u16 found at given position
+fn read_u16(bytes: &[u8], position: usize) -> u16 {
+    u16::from_le_bytes([bytes[position], bytes[position + 1]])
+}
+
+/// Returns little endian u32 found at given position
+fn read_u32(bytes: &[u8], position: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[position],
+        bytes[position + 1],
+        bytes[position + 2],
+        bytes[position + 3],
+    ])
+}
+
+/// WAV file : whole contents, format parameters, and position of samples within contents
+pub struct Wav {
+    bytes: Vec<u8>,
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+    data: Range<usize>,
+}
+
+impl Wav {
+    /// Read WAV file
+    pub fn open(filepath: &str) -> Result<Wav, Error> {
+        Wav::from_bytes(fs::read(filepath)?)
+    }
+
+    /// Returns true if given bytes start like a WAV file
+    pub fn is_wav(bytes: &[u8]) -> bool {
+        bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WAVE"
+    }
+
+    /// Returns true if file at given path is a WAV one
+    pub fn is_wav_file(filepath: &str) -> bool {
+        let mut signature = [0u8; 12];
+        File::open(filepath)
+            .and_then(|mut file| file.read_exact(&mut signature))
+            .is_ok()
+            && Wav::is_wav(&signature)
+    }
+
+    /// Parse WAV file contents, looking for format and data chunks
+    /// Trailing bytes of an incomplete sample frame are left out of the samples
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Wav, Error> {
+        if !Wav::is_wav(&bytes) {
+            return Err(format_error("Not a WAV file!"));
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut position = 12;
+
+        while position + 8 <= bytes.len() {
+            let size = read_u32(&bytes, position + 4) as usize;
+            let start = position + 8;
+            let end = start + size;
+
+            match &bytes[position..position + 4] {
+                b"fmt " if size >= 16 && end <= bytes.len() => format = Some((start, size)),
+                b"data" => data = Some(start..end.min(bytes.len())),
+                _ => (),
+            }
+
+            // Chunks are padded to an even size
+            position = end + size % 2;
+        }
+
+        let (format, format_size) = format.ok_or_else(|| format_error("Missing format chunk!"))?;
+        let data = data.ok_or_else(|| format_error("Missing data chunk!"))?;
+
+        let mut format_tag = read_u16(&bytes, format);
+        if format_tag == WAVE_FORMAT_EXTENSIBLE && format_size >= 40 {
+            format_tag = read_u16(&bytes, format + 24);
+        }
+
+        let channels = read_u16(&bytes, format + 2);
+        let bits_per_sample = read_u16(&bytes, format + 14);
+
+        if format_tag != WAVE_FORMAT_PCM
+            || channels == 0
+            || (bits_per_sample != 8 && bits_per_sample != 16 && bits_per_sample != 24)
+        {
+            return Err(format_error(
+                "Only 8, 16 and 24 bits PCM samples are handled!",
+            ));
+        }
+
+        let frame_size = channels as usize * bits_per_sample as usize / 8;
+        let data = data.start..data.end - (data.end - data.start) % frame_size;
+
+        Ok(Wav {
+            channels,
+            sample_rate: read_u32(&bytes, format + 4),
+            bits_per_sample,
+            data,
+            bytes,
+        })
+    }
+
+    /// Write WAV file, header being the input one
+    pub fn save(&self, filepath: &str) -> Result<(), Error> {
+        BufWriter::new(File::create(filepath)?).write_all(&self.bytes)
+    }
+
+    /// Returns number of channels
+    pub fn get_channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Returns number of sample frames per second
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns number of bits per sample : 8, 16 or 24
+    pub fn get_bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+
+    /// Returns number of samples, all channels included
+    pub fn get_samples_count(&self) -> usize {
+        (self.data.end - self.data.start) / (self.bits_per_sample as usize / 8)
+    }
+
+    /// Returns position of the low byte of sample at given index, samples being little endian
+    fn get_low_byte_position(&self, index: usize) -> usize {
+        self.data.start + index * (self.bits_per_sample as usize / 8)
+    }
+
+    /// Returns low byte of sample at given index, samples being numbered frame after frame
+    pub fn get_low_byte(&self, index: usize) -> u8 {
+        self.bytes[self.get_low_byte_position(index)]
+    }
+
+    /// Replace low byte of sample at given index
+    pub fn set_low_byte(&mut self, index: usize, value: u8) {
+        let position = self.get_low_byte_position(index);
+        self.bytes[position] = value;
+    }
+
+    /// Returns mask flipping signed samples into offset binary ones, 8 bits samples being unsigned
+    fn get_sign_mask(&self) -> u32 {
+        if self.bits_per_sample > 8 {
+            1 << (self.bits_per_sample - 1)
+        } else {
+            0
+        }
+    }
+
+    /// Returns highest sample value, samples being read as unsigned (offset binary) ones
+    pub fn get_max_value(&self) -> u32 {
+        (1 << self.bits_per_sample) - 1
+    }
+
+    /// Returns value of sample at given index, as an unsigned (offset binary) one
+    pub fn get_sample_value(&self, index: usize) -> u32 {
+        let position = self.get_low_byte_position(index);
+        let raw = self.bytes[position..position + self.bits_per_sample as usize / 8]
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | byte as u32);
+        raw ^ self.get_sign_mask()
+    }
+
+    /// Replace value of sample at given index, given as an unsigned (offset binary) one
+    pub fn set_sample_value(&mut self, index: usize, value: u32) {
+        let position = self.get_low_byte_position(index);
+        let raw = (value & self.get_max_value()) ^ self.get_sign_mask();
+        let bytes_count = self.bits_per_sample as usize / 8;
+        self.bytes[position..position + bytes_count]
+            .copy_from_slice(&raw.to_le_bytes()[..bytes_count]);
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::dot_matrix::tests::get_output_filepath;
+    use super::*;
+    use std::process;
+
+    /// Write a stereo WAV file with a LIST chunk before samples, returning its path
+    /// Samples follow a slow wave, with some noise
+    pub fn write_test_wav(filename: &str, bits_per_sample: u16) -> String {
+        let output_filepath = get_output_filepath(filename);
+        let bytes_per_sample = bits_per_sample as usize / 8;
+        let mut samples = Vec::<u8>::new();
+
+        for index in 0..20000i64 {
+            let wave = ((index / 2) % 200 - 100).abs() * 300 - 15000 + (index * 7919) % 97;
+            let value = wave << (bits_per_sample - 8) >> 8;
+            let bytes = if bits_per_sample == 8 {
+                vec![(value + 128) as u8]
+            } else {
+                value.to_le_bytes()[..bytes_per_sample].to_vec()
+            };
+            samples.extend(bytes);
+        }
+
+        let mut format = Vec::<u8>::new();
+        format.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+        format.extend_from_slice(&2u16.to_le_bytes());
+        format.extend_from_slice(&22050u32.to_le_bytes());
+        format.extend_from_slice(&(22050 * 2 * bytes_per_sample as u32).to_le_bytes());
+        format.extend_from_slice(&(2 * bytes_per_sample as u16).to_le_bytes());
+        format.extend_from_slice(&bits_per_sample.to_le_bytes());
+
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        for (id, chunk) in &[
+            (b"fmt ", format),
+            (b"LIST", b"INFOISFT\x03\0\0\0st\0".to_vec()),
+            (b"data", samples),
+        ] {
+            bytes.extend_from_slice(*id);
+            bytes.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(chunk);
+            if chunk.len() % 2 == 1 {
+                bytes.push(0);
+            }
+        }
+
+        let riff_size = (bytes.len() - 8) as u32;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        File::create(&output_filepath)
+            .and_then(|mut file| file.write_all(&bytes))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in write_test_wav: {}", err);
+                process::exit(1);
+            });
+
+        output_filepath
+    }
+
+    #[test]
+    fn test_open_wav() {
+        for &bits_per_sample in &[8, 16, 24] {
+            let filepath =
+                write_test_wav(&format!("test_{}.wav", bits_per_sample), bits_per_sample);
+            assert!(Wav::is_wav_file(&filepath));
+
+            let mut wav = Wav::open(&filepath).unwrap_or_else(|err| {
+                eprintln!("Error in test_open_wav: {}", err);
+                process::exit(1);
+            });
+
+            assert_eq!(wav.get_channels(), 2);
+            assert_eq!(wav.get_sample_rate(), 22050);
+            assert_eq!(wav.get_bits_per_sample(), bits_per_sample);
+            assert_eq!(wav.get_samples_count(), 20000);
+
+            // Only the low byte of the sample changes
+            let bytes = wav.bytes.clone();
+            let value = wav.get_low_byte(5) ^ 1;
+            wav.set_low_byte(5, value);
+            let position = wav.data.start + 5 * bits_per_sample as usize / 8;
+            assert_eq!(wav.bytes[position], bytes[position] ^ 1);
+            assert_eq!(wav.bytes[..position], bytes[..position]);
+            assert_eq!(wav.bytes[position + 1..], bytes[position + 1..]);
+
+            // Sample values are offset binary ones, low byte included
+            let value = wav.get_sample_value(7);
+            assert_eq!(value & 0xFF, wav.get_low_byte(7) as u32);
+            wav.set_sample_value(7, value + 1);
+            assert_eq!(wav.get_sample_value(7), value + 1);
+        }
+
+        assert!(Wav::from_bytes(b"RIFF\0\0\0\0WAVE".to_vec()).is_err());
+    }
+}
@@ -0,0 +1,374 @@
+//! Module handling WAV audio files
+//! Messages are hidden in the low bits of PCM samples, sample after sample, with the same header,
+//! framing and random filling as the LSB mode of pictures
+use std::io::{Error, ErrorKind};
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+
+use super::binary::*;
+use super::carrier::{self, frame_message, Carrier, LENGTH_BITS};
+use super::dot_matrix::{check_message_and_password, WipeOptions};
+use super::sealing::{PasswordSealing, Sealing};
+use super::wav::Wav;
+
+/// Number of samples holding the header, one bit each
+const HEADER_SAMPLES: usize = 24;
+
+/// Header size : magic number, then number of low bits minus one in the upper half of a byte
+const HEADER_BYTES: usize = 3;
+
+/// Magic number telling the header is valid for the given password
+const HEADER_MAGIC: u16 = 0x5354;
+
+/// Key derivation context of the header mask
+const HEADER_CONTEXT: &str = "stegano/wav/header";
+
+/// Highest number of low bits 16 and 24 bits samples may use
+const MAX_LOW_BITS: u8 = 8;
+
+/// Basic structure : a WAV file, a filepath, and the number of low bits used in each sample
+pub struct WavCarrier {
+    wav: Result<Wav, Error>,
+    input_filepath: String,
+    low_bits: u8,
+}
+
+impl WavCarrier {
+    /// Constructor
+    pub fn new(filepath: &str) -> WavCarrier {
+        WavCarrier {
+            wav: Wav::open(filepath),
+            input_filepath: filepath.to_string(),
+            low_bits: 1,
+        }
+    }
+
+    /// Returns true if file at given path is a WAV one
+    pub fn is_wav_file(filepath: &str) -> bool {
+        Wav::is_wav_file(filepath)
+    }
+
+    /// Accessor returning audio's filepath
+    pub fn get_input_filepath(&self) -> String {
+        self.input_filepath.clone()
+    }
+
+    /// Accessor returning number of low bits used in each sample
+    pub fn get_low_bits(&self) -> u8 {
+        self.low_bits
+    }
+
+    /// Setter for the number of low bits used in each sample
+    /// Only 16 and 24 bits samples may use more than one bit, up to 8
+    pub fn set_low_bits(&mut self, low_bits: u8) -> Result<(), Error> {
+        let max_low_bits = self.get_max_low_bits();
+
+        if low_bits == 0 || low_bits > max_low_bits {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "stegano/set_low_bits : Number of low bits must range from 1 to {} for this file",
+                    max_low_bits
+                ),
+            ));
+        }
+
+        self.low_bits = low_bits;
+        Ok(())
+    }
+
+    /// Returns highest number of low bits samples may use
+    fn get_max_low_bits(&self) -> u8 {
+        match self.wav {
+            Ok(ref wav) if wav.get_bits_per_sample() > 8 => MAX_LOW_BITS,
+            _ => 1,
+        }
+    }
+
+    /// Function to write the audio into target file, header being the input one
+    pub fn write_to_file(&self, filepath: &str) -> Result<(), Error> {
+        self.get_wav()?.save(filepath)
+    }
+
+    /// Returns inner WAV file
+    fn get_wav(&self) -> Result<&Wav, Error> {
+        match self.wav {
+            Ok(ref wav) => Ok(wav),
+            Err(_) => Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/get_wav : Unable to open inner audio!",
+            )),
+        }
+    }
+
+    /// Returns number of samples, all channels included
+    fn get_samples_count(&self) -> usize {
+        match self.wav {
+            Ok(ref wav) => wav.get_samples_count(),
+            Err(_) => 0,
+        }
+    }
+
+    /// Returns number of bytes of encrypted message the audio can hold with current low bits
+    pub fn get_capacity(&self) -> usize {
+        (self.get_samples_count().saturating_sub(HEADER_SAMPLES) * self.low_bits as usize)
+            .saturating_sub(LENGTH_BITS)
+            / 8
+    }
+
+    /// Encode given file in self audio
+    pub fn encode_file(&mut self, filepath: &str, password: &str) -> Result<(), Error> {
+        let input_file = File::open(filepath)?;
+        let mut buf_reader = BufReader::new(input_file);
+        let mut contents = String::new();
+        buf_reader.read_to_string(&mut contents)?;
+        self.encode(contents.as_str(), password)
+    }
+
+    /// Encode given message in self audio
+    pub fn encode(&mut self, message: &str, password: &str) -> Result<(), Error> {
+        self.encode_sealed(message, &PasswordSealing::new(password))
+    }
+
+    /// Encode given message in self audio, sealed with given method
+    /// Message goes sample after sample, right after the header
+    /// Remaining samples get random low bits, to hide message length
+    pub fn encode_sealed(&mut self, message: &str, sealing: &dyn Sealing) -> Result<(), Error> {
+        let password = &sealing.get_carrier_key();
+        check_message_and_password(message, password)?;
+
+        let vector = frame_message(&sealing.seal(message)?);
+        let low_bits = self.low_bits;
+        let index = carrier::store_from(self, HEADER_SAMPLES, low_bits, &vector)?;
+        carrier::store_random_from(self, index, low_bits)?;
+
+        let magic = HEADER_MAGIC.to_be_bytes();
+        let header = [magic[0], magic[1], (low_bits - 1) << 4];
+        carrier::store_header(self, &header, password, HEADER_CONTEXT)
+    }
+
+    /// Remove any hidden payload : low bits of every sample, header ones included,
+    /// are overwritten with fresh random bits
+    pub fn wipe(&mut self, options: &WipeOptions) -> Result<(), Error> {
+        let max_low_bits = self.get_max_low_bits();
+        let low_bits = options.low_bits.unwrap_or(max_low_bits);
+
+        if low_bits == 0 || low_bits > max_low_bits {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "stegano/wipe : Number of low bits must range from 1 to {} for this file",
+                    max_low_bits
+                ),
+            ));
+        }
+
+        self.get_wav()?;
+        carrier::store_random_from(self, 0, low_bits)
+    }
+
+    /// Decodes audio and write result file
+    pub fn decode_and_write(&self, filepath: &str, password: &str) -> Result<(), Error> {
+        let decoded_string = &self.decode(password)?;
+        let mut output_file = File::create(filepath)?;
+        output_file.write_all(decoded_string.as_bytes())
+    }
+
+    /// Decodes audio and return result string
+    pub fn decode(&self, password: &str) -> Result<String, Error> {
+        self.decode_sealed(&PasswordSealing::new(password))
+    }
+
+    /// Decodes audio sealed with given method and return result string
+    /// Number of low bits is read from the header
+    pub fn decode_sealed(&self, sealing: &dyn Sealing) -> Result<String, Error> {
+        let password = &sealing.get_carrier_key();
+        if !is_one_byte_chars_message(password) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Input password must be 1 byte chars",
+            ));
+        }
+
+        let nothing_hidden = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                "stegano/decode : Nothing hidden in this file!",
+            )
+        };
+
+        let header = carrier::read_header(self, HEADER_BYTES, password, HEADER_CONTEXT)?
+            .ok_or_else(nothing_hidden)?;
+        let low_bits = (header[2] >> 4) + 1;
+
+        if u16::from_be_bytes([header[0], header[1]]) != HEADER_MAGIC
+            || header[2] & 0x0F != 0
+            || low_bits > MAX_LOW_BITS
+        {
+            return Err(nothing_hidden());
+        }
+
+        let encrypted_message = carrier::read_framed_from(self, HEADER_SAMPLES, low_bits)?;
+
+        sealing
+            .open(&encrypted_message)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+/// Trait implementation : PCM samples are the slots, frame after frame
+impl Carrier for WavCarrier {
+    /// Returns number of samples, all channels included
+    fn get_slots_count(&self) -> usize {
+        self.get_samples_count()
+    }
+
+    /// Returns the given number of low bits of sample at given index, most significant first
+    fn read_slot(&self, index: usize, count: u8) -> Result<Vec<bool>, Error> {
+        let value = self.get_wav()?.get_low_byte(index);
+
+        Ok((0..count)
+            .rev()
+            .map(|shift| (value >> shift) & 1 == 1)
+            .collect())
+    }
+
+    /// Store bits within the given number of low bits of sample at given index
+    /// If the end of the message is reached, bits may be fewer than count : store what is storable
+    fn write_slot(&mut self, index: usize, count: u8, bits: &[bool]) -> Result<(), Error> {
+        let wav = match self.wav {
+            Ok(ref mut wav) => wav,
+            Err(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "stegano/write_slot : Unable to open inner audio!",
+                ))
+            }
+        };
+        let mut value = wav.get_low_byte(index);
+
+        for (position, &bit) in bits.iter().take(count as usize).enumerate() {
+            let shift = count as usize - 1 - position;
+            value = (value & !(1 << shift)) | ((bit as u8) << shift);
+        }
+
+        wav.set_low_byte(index, value);
+        Ok(())
+    }
+
+    /// Write audio into target file
+    fn save(&self, filepath: &str) -> Result<(), Error> {
+        self.write_to_file(filepath)
+    }
+
+    /// Returns highest sample value, samples being read as unsigned ones
+    fn get_slot_max_value(&self) -> Option<u32> {
+        self.wav.as_ref().ok().map(|wav| wav.get_max_value())
+    }
+
+    /// Returns value of sample at given index, as an unsigned one
+    fn read_slot_value(&self, index: usize) -> Result<u32, Error> {
+        Ok(self.get_wav()?.get_sample_value(index))
+    }
+
+    /// Replace value of sample at given index, given as an unsigned one
+    fn write_slot_value(&mut self, index: usize, value: u32) -> Result<(), Error> {
+        match self.wav {
+            Ok(ref mut wav) => {
+                wav.set_sample_value(index, value);
+                Ok(())
+            }
+            Err(_) => Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/write_slot_value : Unable to open inner audio!",
+            )),
+        }
+    }
+}
+
+// Tests
+#[cfg(test)]
+pub mod tests {
+    use super::super::dot_matrix::tests::get_output_filepath;
+    use super::super::wav::tests::write_test_wav;
+    use super::*;
+    use std::{fs, process};
+
+    #[test]
+    fn test_global_wav() {
+        for &(bits_per_sample, low_bits) in &[(8, 1), (16, 1), (24, 4)] {
+            let input_filepath = write_test_wav(
+                &format!("test_carrier_{}.wav", bits_per_sample),
+                bits_per_sample,
+            );
+            let output_filepath =
+                get_output_filepath(&format!("test_carrier_{}_stego.wav", bits_per_sample));
+
+            let mut carrier = WavCarrier::new(&input_filepath);
+            carrier
+                .set_low_bits(low_bits)
+                .and_then(|_| carrier.encode("Hello how is the weather today", "Password"))
+                .and_then(|_| carrier.write_to_file(&output_filepath))
+                .unwrap_or_else(|err| {
+                    eprintln!("Error in test_global_wav: {}", err);
+                    process::exit(1);
+                });
+
+            let carrier2 = WavCarrier::new(&output_filepath);
+            let res = carrier2.decode("Password").unwrap_or_else(|err| {
+                eprintln!("Error in test_global_wav: {}", err);
+                process::exit(1);
+            });
+
+            assert_eq!(res, "Hello how is the weather today".to_string());
+            assert!(carrier2.decode("Wrong password").is_err());
+
+            // Header and samples high bits are left untouched
+            let (input, output) = (
+                fs::read(&input_filepath).expect("Input file"),
+                fs::read(&output_filepath).expect("Output file"),
+            );
+            let header_size = input.len() - 20000 * bits_per_sample as usize / 8;
+            assert_eq!(output.len(), input.len());
+            assert_eq!(output[..header_size], input[..header_size]);
+
+            let wav = Wav::open(&output_filepath).expect("Output file");
+            assert_eq!(wav.get_bits_per_sample(), bits_per_sample);
+            let mask = !((1u16 << low_bits) - 1) as u8;
+            for index in 0..wav.get_samples_count() {
+                let position = header_size + index * bits_per_sample as usize / 8;
+                assert_eq!(output[position] & mask, input[position] & mask);
+            }
+        }
+
+        assert!(
+            WavCarrier::new(&write_test_wav("test_carrier_low_bits.wav", 8))
+                .set_low_bits(2)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_global_wipe() {
+        let input_filepath = write_test_wav("test_carrier_wipe.wav", 16);
+        let output_filepath = get_output_filepath("test_carrier_wiped.wav");
+
+        let mut carrier = WavCarrier::new(&input_filepath);
+        carrier
+            .set_low_bits(4)
+            .and_then(|_| carrier.encode("Hello how is the weather today", "Password"))
+            .and_then(|_| carrier.wipe(&WipeOptions::default()))
+            .and_then(|_| carrier.write_to_file(&output_filepath))
+            .unwrap_or_else(|err| {
+                eprintln!("Error in test_global_wipe: {}", err);
+                process::exit(1);
+            });
+
+        assert!(WavCarrier::new(&output_filepath)
+            .decode("Password")
+            .is_err());
+        assert!(carrier.wipe(&WipeOptions { low_bits: Some(9) }).is_err());
+    }
+}
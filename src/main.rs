@@ -1,14 +1,43 @@
 //! MAIN
 pub mod lib;
 
-use self::lib::dot_matrix::DotMatrix;
-use std::{env, process};
+use self::lib::animation_matrix::AnimationMatrix;
+use self::lib::dot_matrix::{DotMatrix, EmbeddingMode, WipeOptions};
+use self::lib::jpeg_matrix::JpegMatrix;
+use self::lib::parity::{self, Chunk};
+use self::lib::picture::Picture;
+use self::lib::quality;
+use self::lib::sealing::{
+    self, Identity, PasswordSealing, Recipient, RecipientSealing, RecipientsSealing, Sealing,
+};
+use self::lib::shamir::{self, Share};
+use self::lib::signature::{self, TrustedKeys};
+use self::lib::steganalysis;
+use self::lib::strategy::StrategyRegistry;
+use self::lib::wav_carrier::WavCarrier;
+use std::{
+    convert::TryFrom,
+    env, fs,
+    io::{Error, ErrorKind},
+    process,
+};
 
 // Enum used to display usage depending on first argument entered by user
 enum Usage {
     FULL,
     ENCODE,
     DECODE,
+    KEYGEN,
+    SPLIT,
+    COMBINE,
+    SPREAD,
+    GATHER,
+    CONTAINER,
+    WIPE,
+    SANITIZE,
+    ANALYZE,
+    VISUALIZE,
+    COMPARE,
 }
 
 /// Print stegano usage
@@ -16,27 +45,416 @@ enum Usage {
 fn print_usage(mode: Usage) {
     match mode {
         Usage::FULL =>
-            println!("Usage stegano <ENCODE / DECODE> <input file path> <output file path> <password> [<ASCII file to encode, if encoding>]"),
+            println!("Usage stegano <ENCODE / DECODE> <input file path> <output file path> <password / --recipient <public key> --embedding-key <passphrase shared with recipients> / --recipients <public keys and password:<password> list> --embedding-key <passphrase shared with recipients>, if encoding / --identity <private key> --embedding-key <passphrase shared with sender>, if decoding, the embedding key going with passwords among several recipients too> [<ASCII file to encode, if encoding>] [<LSB / ADAPTIVE / TEXTURED / PVD / REVERSIBLE / PALETTE mode, or RANDOM_WALK / MATCHING / MATRIX[:parameter] strategy, if encoding>] [--low-bits <1 to 8, 16 bits pictures and 16 / 24 bits WAV files only>] [--texture-percent <1 to 100, share of the most textured pixels used by TEXTURED mode, 50 by default>] [--sign <signing key file path>] [--keyfile <any file, mixed with password, as many as needed>] [--decoy-message <ASCII decoy file> --decoy-password <decoy password>, pictures only, without mode nor low bits]\nJPEG pictures (F5) and animated GIF / APNG pictures (all frames) take neither mode nor low bits, WAV files (8, 16 or 24 bits PCM) take LSB mode only"),
         Usage::ENCODE =>
-            println!("Usage stegano ENCODE <input file path> <output file path> <password> <ASCII file to encode>"),
+            println!("Usage stegano ENCODE <input file path> <output file path> <password / --recipient <public key or public key file path> --embedding-key <passphrase shared with recipients> / --recipients <comma separated public keys, public key file paths and password:<password>> --embedding-key <passphrase shared with recipients>> <ASCII file to encode> [<LSB / ADAPTIVE / TEXTURED / PVD / REVERSIBLE / PALETTE mode, or RANDOM_WALK / MATCHING / MATRIX[:parameter] strategy>] [--low-bits <1 to 8, 16 bits pictures and 16 / 24 bits WAV files only>] [--texture-percent <1 to 100, share of the most textured pixels used by TEXTURED mode, 50 by default>] [--sign <signing key file path>] [--keyfile <any file, mixed with password, as many as needed>] [--decoy-message <ASCII decoy file> --decoy-password <decoy password>, pictures only, without mode nor low bits]\nJPEG pictures (F5) and animated GIF / APNG pictures (all frames) take neither mode nor low bits, WAV files (8, 16 or 24 bits PCM) take LSB mode only"),
         Usage::DECODE =>
-            println!("Usage stegano DECODE <input file path> <output file path> <password>"),
+            println!("Usage stegano DECODE <input file path> <output file path> <password / --identity <private key or private key file path>> [--embedding-key <passphrase shared with sender, for keys and passwords among several recipients>] [--restore-cover <restored cover file path>] [--trusted-keys <trusted keys file path, ~/.stegano/trusted_keys by default>] [--require-signature] [--keyfile <any file, mixed with password, as many as needed>]"),
+        Usage::KEYGEN =>
+            println!("Usage stegano KEYGEN <private key file path> <public key file path> [--signing]"),
+        Usage::SPLIT =>
+            println!("Usage stegano SPLIT <ASCII file to split> <password> --threshold <shares needed, at least 2> --shares <shares count> <input file path 1> <output file path 1> ... <input file path N> <output file path N>\nEach share is hidden in its own picture, in LSB mode"),
+        Usage::COMBINE =>
+            println!("Usage stegano COMBINE <output file path> <password> <input file path 1> ... <input file path N>"),
+        Usage::SPREAD =>
+            println!("Usage stegano SPREAD <ASCII file to spread> <password> --parity <pictures which may be lost> <input file path 1> <output file path 1> ... <input file path N> <output file path N>\nEach chunk is hidden in its own picture, in LSB mode"),
+        Usage::GATHER =>
+            println!("Usage stegano GATHER <output file path> <password> <input file path 1> ... <input file path N>"),
+        Usage::CONTAINER =>
+            println!("Usage stegano APPEND <input file path> <output file path> <password> <ASCII file to append>\n      stegano LIST <input file path> <password>\n      stegano REPLACE <input file path> <output file path> <password> <slot> <ASCII file to encode>\n      stegano DELETE <input file path> <output file path> <password> <slot>\nPictures hold up to 8 messages, DECODE reading the first one of its password"),
+        Usage::WIPE =>
+            println!("Usage stegano WIPE <input file path> [<output file path>, input file itself by default] [--low-bits <1 to 8, 16 bits pictures and 16 / 24 bits WAV files only, every low bit the file may hide a payload in by default>]\nJPEG pictures get the F5 bit of every non-zero AC coefficient re-randomized, animated GIF / APNG pictures the bit of every sample of every frame"),
+        Usage::SANITIZE =>
+            println!("Usage stegano SANITIZE <input file path> <output file path>"),
+        Usage::ANALYZE =>
+            println!("Usage stegano ANALYZE CHI2 <input file path> [--block-rows <rows per curve point, 1/16 of picture height by default>]\n      stegano ANALYZE <RS / SPA> <input file path>"),
+        Usage::VISUALIZE =>
+            println!("Usage stegano VISUALIZE [--plane <bit plane, 0 by default>] [--channel <R / G / B / GRAY, or channel number, R by default>] <input file path> <output file path>\n      stegano VISUALIZE --difference <cover file path> [--amplification <factor, 64 by default>] <input file path> <output file path>"),
+        Usage::COMPARE => println!("Usage stegano COMPARE <cover file path> <stego file path>"),
+    }
+}
+
+/// Sealing methods to try, in order
+type Sealings = Vec<Box<dyn Sealing>>;
+
+/// Returns arguments without the key options, if any, and the sealing methods they ask for
+/// Password is the default one, keys given with an option take its place
+/// Public and private keys go with the embedding key shared by sender and recipient
+/// Decoding gets two methods : the single recipient one, then the several recipients one
+/// Keyfiles are mixed with every password, keys taking none
+fn get_sealings(
+    args: &[String],
+    encoding: bool,
+    keyfiles: &[String],
+) -> Result<(Vec<String>, Sealings), Error> {
+    let mut args = args.to_vec();
+    let embedding_key = take_option(&mut args, "--embedding-key");
+    let get_embedding_key = || {
+        embedding_key.clone().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Keys need the embedding key shared with recipients (--embedding-key)",
+            )
+        })
+    };
+
+    let option = args.get(4).map(String::as_str).unwrap_or_default();
+    let (sealings, is_key_option): (Sealings, bool) = match (option, args.get(5)) {
+        ("--recipient", Some(key)) if encoding => (
+            vec![Box::new(RecipientSealing::to_recipient(
+                sealing::read_key(key)?,
+                &get_embedding_key()?,
+            )?)],
+            true,
+        ),
+        ("--recipients", Some(recipients)) if encoding => (
+            vec![Box::new(RecipientsSealing::to_recipients(
+                get_recipients(recipients, keyfiles)?,
+                &get_embedding_key()?,
+            )?)],
+            true,
+        ),
+        ("--identity", Some(key)) if !encoding => {
+            let key = sealing::read_key(key)?;
+            (
+                vec![
+                    Box::new(RecipientSealing::from_identity(key, &get_embedding_key()?)?),
+                    Box::new(RecipientsSealing::from_identity(
+                        Identity::SecretKey(key),
+                        &get_embedding_key()?,
+                    )?),
+                ],
+                true,
+            )
+        }
+        (password, _) if encoding => (
+            vec![Box::new(PasswordSealing::with_keyfiles(
+                password, keyfiles,
+            )?)],
+            false,
+        ),
+        // Passwords among several recipients need the embedding key, other ones do not
+        (password, _) => {
+            let mut sealings: Sealings = vec![Box::new(PasswordSealing::with_keyfiles(
+                password, keyfiles,
+            )?)];
+            if let Some(ref embedding_key) = embedding_key {
+                sealings.push(Box::new(RecipientsSealing::from_identity(
+                    Identity::from_password(password, keyfiles)?,
+                    embedding_key,
+                )?));
+            }
+            (sealings, false)
+        }
+    };
+
+    if (option == "--recipient" || option == "--identity") && !keyfiles.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Keyfiles only go with passwords",
+        ));
+    }
+    if encoding && !is_key_option && embedding_key.is_some() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Embedding key only goes with public keys",
+        ));
+    }
+
+    // Key options take two arguments where passwords take one
+    if is_key_option {
+        args.remove(4);
+    }
+    Ok((args, sealings))
+}
+
+/// Take keyfiles, given with as many --keyfile options
+fn take_keyfiles(args: &mut Vec<String>) -> Vec<String> {
+    let mut keyfiles = Vec::new();
+    while let Some(keyfile) = take_option(args, "--keyfile") {
+        keyfiles.push(keyfile);
+    }
+    keyfiles
+}
+
+/// Returns recipients given as a comma separated list of public keys,
+/// or public key file paths, and of passwords prefixed with "password:"
+/// Passwords are mixed with keyfiles
+fn get_recipients(recipients: &str, keyfiles: &[String]) -> Result<Vec<Recipient>, Error> {
+    recipients
+        .split(',')
+        .map(|recipient| match recipient.strip_prefix("password:") {
+            Some(password) => Recipient::from_password(password, keyfiles),
+            None => sealing::read_key(recipient).map(Recipient::PublicKey),
+        })
+        .collect()
+}
+
+/// Decode with every sealing method, until one succeeds
+/// Returns error of the first method if none does
+fn decode_with_any<F>(sealings: &[Box<dyn Sealing>], mut decode: F) -> Result<String, Error>
+where
+    F: FnMut(&dyn Sealing) -> Result<String, Error>,
+{
+    let mut first_error = None;
+
+    for sealing in sealings {
+        match decode(sealing.as_ref()) {
+            Ok(message) => return Ok(message),
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+
+    Err(first_error.unwrap_or_else(|| Error::new(ErrorKind::InvalidInput, "No sealing method")))
+}
+
+/// Remove option and its value from arguments, returning the value
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+    let position = args.iter().position(|arg| arg == option)?;
+    if position + 1 >= args.len() {
+        return None;
+    }
+
+    args.remove(position);
+    Some(args.remove(position))
+}
+
+/// Remove flag from arguments, returning true if it was given
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let length = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != length
+}
+
+/// Unwrap decoded message, checking its signature against trusted keys
+/// Returns message, and a report telling who signed it
+/// Unsigned messages, and messages signed by untrusted keys, are refused when a signature is required
+fn open_signed_message(
+    decoded: &str,
+    trusted_keys: &Option<TrustedKeys>,
+    require_signature: bool,
+) -> Result<(String, String), Error> {
+    let (message, signer) = signature::open_message(decoded)?;
+
+    let signer = match signer {
+        Some(signer) => signer,
+        None if require_signature => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "stegano/signature : Message is not signed!",
+            ))
+        }
+        None => return Ok((message, "not signed".to_string())),
+    };
+
+    let fingerprint = signer.get_fingerprint();
+    match trusted_keys
+        .as_ref()
+        .and_then(|trusted_keys| trusted_keys.get_name(&signer.public_key))
+    {
+        Some(name) => Ok((message, format!("signed by {} ({})", fingerprint, name))),
+        None if require_signature => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "stegano/signature : Message is signed by untrusted key {}!",
+                fingerprint
+            ),
+        )),
+        None => Ok((
+            message,
+            format!("signed by {} (untrusted key)", fingerprint),
+        )),
+    }
+}
+
+/// Returns trusted keys read from given file, or from the default one if it exists
+fn get_trusted_keys(filepath: Option<String>) -> Result<Option<TrustedKeys>, Error> {
+    match filepath {
+        Some(filepath) => TrustedKeys::open(&filepath).map(Some),
+        None => {
+            let default_filepath = env::var("HOME")
+                .map(|home| format!("{}/.stegano/trusted_keys", home))
+                .unwrap_or_default();
+            Ok(TrustedKeys::open(&default_filepath).ok())
+        }
     }
 }
 
 /// Sub main, for encoding mode
 fn main_sub_encode(args: &[String]) {
+    // Message may be signed with a signing key
+    let mut args = args.to_vec();
+    let signing_key = match take_option(&mut args, "--sign").map(|key| sealing::read_key(&key)) {
+        None => None,
+        Some(Ok(key)) => Some(key),
+        Some(Err(error)) => {
+            println!("Encoding.....ERROR : {}", error);
+            return;
+        }
+    };
+    // Keyfiles are mixed with passwords, decoy one included
+    let keyfiles = take_keyfiles(&mut args);
+    // Pictures may hold a decoy message too, decoded with its own password
+    let decoy = match (
+        take_option(&mut args, "--decoy-message"),
+        take_option(&mut args, "--decoy-password"),
+    ) {
+        (None, None) => None,
+        (Some(message), Some(password)) => Some((message, password)),
+        _ => {
+            print_usage(Usage::ENCODE);
+            return;
+        }
+    };
+    let texture_percentage =
+        take_option(&mut args, "--texture-percent").map(|percentage| percentage.parse::<u8>());
+    let read_message = |filepath: &str| {
+        fs::read_to_string(filepath).map(|message| match signing_key {
+            Some(ref key) => signature::sign_message(&message, key),
+            None => message,
+        })
+    };
+
+    // Password may be replaced by recipients
+    let (args, sealings) = match get_sealings(&args, true, &keyfiles) {
+        Ok(found) => found,
+        Err(error) => {
+            println!("Encoding.....ERROR : {}", error);
+            return;
+        }
+    };
+    let sealing = sealings[0].as_ref();
+
     // Check input arguments number
-    if args.len() != 6 {
+    let low_bits_given = args.len() == 9 && args[7] == "--low-bits";
+    if args.len() != 6 && args.len() != 7 && !low_bits_given {
         print_usage(Usage::ENCODE);
         return;
     }
 
+    // JPEG pictures are encoded in the DCT domain, animations over all of their frames,
+    // where mode and low bits make no sense
+    let is_jpeg = JpegMatrix::is_jpeg_file(&args[2]);
+    if is_jpeg || AnimationMatrix::is_animation_file(&args[2]) {
+        if args.len() != 6 || decoy.is_some() {
+            print_usage(Usage::ENCODE);
+        } else if is_jpeg {
+            let mut input_file = JpegMatrix::new(&args[2]);
+            let encoding = read_message(&args[5])
+                .and_then(|message| input_file.encode_sealed(&message, sealing));
+            print_encoding_and_write(encoding, || input_file.write_to_file(&args[3]));
+        } else {
+            let mut input_file = AnimationMatrix::new(&args[2]);
+            let encoding = read_message(&args[5])
+                .and_then(|message| input_file.encode_sealed(&message, sealing));
+            print_encoding_and_write(encoding, || input_file.write_to_file(&args[3]));
+        }
+        return;
+    }
+
+    // Embedding mode is optional, LSB being the default one
+    // Names which are no mode are looked for among embedding strategies
+    let mut strategy = None;
+    let mode = match args.get(6).map(|mode| mode.parse::<EmbeddingMode>()) {
+        None => EmbeddingMode::Lsb,
+        Some(Ok(mode)) => mode,
+        Some(Err(error)) => match StrategyRegistry::default().from_name(&args[6]) {
+            Ok(found) => {
+                strategy = Some(found);
+                EmbeddingMode::Lsb
+            }
+            Err(_) => {
+                println!("Encoding.....ERROR : {}", error);
+                return;
+            }
+        },
+    };
+
+    // Number of low bits used in each sample is optional, 1 being the default one
+    let low_bits = match args
+        .get(8)
+        .filter(|_| low_bits_given)
+        .map(|low_bits| low_bits.parse::<u8>())
+    {
+        None => 1,
+        Some(Ok(low_bits)) => low_bits,
+        Some(Err(error)) => {
+            println!("Encoding.....ERROR : {}", error);
+            return;
+        }
+    };
+
+    // WAV files are encoded sample after sample, as pictures in LSB mode
+    if WavCarrier::is_wav_file(&args[2]) {
+        if mode != EmbeddingMode::Lsb || strategy.is_some() {
+            println!("Encoding.....ERROR : WAV files can only be encoded in LSB mode");
+            return;
+        }
+        if decoy.is_some() {
+            println!("Encoding.....ERROR : WAV files cannot hold a decoy message");
+            return;
+        }
+
+        let mut input_file = WavCarrier::new(&args[2]);
+        let encoding = input_file
+            .set_low_bits(low_bits)
+            .and_then(|_| read_message(&args[5]))
+            .and_then(|message| input_file.encode_sealed(&message, sealing));
+        print_encoding_and_write(encoding, || input_file.write_to_file(&args[3]));
+        return;
+    }
+
     // Initialize dot matrix from input file
     let mut input_file = DotMatrix::new(&args[2]);
 
-    // Encode input file within matrix
-    let encoding = input_file.encode_file(&args[5], &args[4]);
+    if let Err(error) = input_file.set_low_bits(low_bits) {
+        println!("Encoding.....ERROR : {}", error);
+        return;
+    }
+
+    // Share of the most textured pixels used by textured mode is optional
+    match texture_percentage {
+        None => {}
+        Some(Ok(percentage)) if mode == EmbeddingMode::Textured && strategy.is_none() => {
+            if let Err(error) = input_file.set_texture_percentage(percentage) {
+                println!("Encoding.....ERROR : {}", error);
+                return;
+            }
+        }
+        _ => {
+            print_usage(Usage::ENCODE);
+            return;
+        }
+    }
+
+    // Encode input file within matrix, low bits of strategies being given as their parameter
+    // Decoy messages are spread over one bit of every other sample, whatever the mode
+    let encoding = match (&decoy, strategy) {
+        (Some(_), _) if args.len() != 6 => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Decoy messages take neither mode nor low bits",
+        )),
+        (Some((decoy_filepath, decoy_password)), _) => read_message(&args[5]).and_then(|message| {
+            let decoy_message = read_message(decoy_filepath)?;
+            input_file.encode_sealed_with_decoy(
+                &message,
+                sealing,
+                &decoy_message,
+                &PasswordSealing::with_keyfiles(decoy_password, &keyfiles)?,
+            )
+        }),
+        (None, Some(_)) if low_bits_given => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Low bits of strategies are given as NAME:parameter",
+        )),
+        (None, Some(ref strategy)) => read_message(&args[5]).and_then(|message| {
+            input_file.encode_sealed_with_strategy(&message, sealing, strategy.as_ref())
+        }),
+        (None, None) => read_message(&args[5])
+            .and_then(|message| input_file.encode_sealed_with_mode(&message, sealing, mode)),
+    };
 
     // Check success!
     match encoding {
@@ -63,29 +481,653 @@ fn main_sub_encode(args: &[String]) {
     }
 }
 
+/// Print encoding result, then write encoded file if encoding succeeded
+fn print_encoding_and_write<F>(encoding: Result<(), Error>, write: F)
+where
+    F: FnOnce() -> Result<(), Error>,
+{
+    match encoding {
+        Ok(_) => {
+            println!("Encoding.....SUCCESS");
+
+            match write() {
+                Ok(_) => {
+                    println!("Writing......SUCCESS");
+                    process::exit(0);
+                }
+                Err(error) => {
+                    println!("Writing......ERROR : {}", error);
+                }
+            }
+        }
+        Err(error) => {
+            println!("Encoding.....ERROR : {}", error);
+        }
+    }
+}
+
 /// Sub main, for decoding mode
 fn main_sub_decode(args: &[String]) {
+    // Signatures are checked against trusted keys
+    let mut args = args.to_vec();
+    let require_signature = take_flag(&mut args, "--require-signature");
+    let trusted_keys = match get_trusted_keys(take_option(&mut args, "--trusted-keys")) {
+        Ok(trusted_keys) => trusted_keys,
+        Err(error) => {
+            println!("Decoding.....ERROR : {}", error);
+            return;
+        }
+    };
+    let keyfiles = take_keyfiles(&mut args);
+    let write_message = |decoded: String| {
+        let (message, report) = open_signed_message(&decoded, &trusted_keys, require_signature)?;
+        fs::write(&args[3], message)?;
+        Ok(report)
+    };
+
+    // Password may be replaced by a private key
+    let (args, sealings) = match get_sealings(&args, false, &keyfiles) {
+        Ok(found) => found,
+        Err(error) => {
+            println!("Decoding.....ERROR : {}", error);
+            return;
+        }
+    };
+
     // Check input arguments number
-    if args.len() != 5 {
+    let restore_cover = args.len() == 7 && args[5] == "--restore-cover";
+    if args.len() != 5 && !restore_cover {
         print_usage(Usage::DECODE);
         return;
     }
 
+    // JPEG pictures, animated pictures and WAV files hold no cover to restore
+    let is_jpeg = JpegMatrix::is_jpeg_file(&args[2]);
+    let is_wav = WavCarrier::is_wav_file(&args[2]);
+    if is_jpeg || is_wav || AnimationMatrix::is_animation_file(&args[2]) {
+        if restore_cover {
+            println!("Decoding.....ERROR : Only pictures can be restored");
+            return;
+        }
+
+        let decoding = if is_jpeg {
+            let input_file = JpegMatrix::new(&args[2]);
+            decode_with_any(&sealings, |sealing| input_file.decode_sealed(sealing))
+        } else if is_wav {
+            let input_file = WavCarrier::new(&args[2]);
+            decode_with_any(&sealings, |sealing| input_file.decode_sealed(sealing))
+        } else {
+            let input_file = AnimationMatrix::new(&args[2]);
+            decode_with_any(&sealings, |sealing| input_file.decode_sealed(sealing))
+        }
+        .and_then(write_message);
+
+        match decoding {
+            Ok(report) => {
+                println!("Decoding.....SUCCESS");
+                println!("Signature....{}", report);
+            }
+            Err(error) => println!("Decoding.....ERROR : {}", error),
+        }
+        return;
+    }
+
     // Initialize dot matrix from input file
-    let output_file = DotMatrix::new(&args[2]);
+    let mut output_file = DotMatrix::new(&args[2]);
 
-    // Decode input file within matrix
-    let decoding = output_file.decode_and_write(&args[3], &args[4]);
+    // Decode input file within matrix, restoring the original picture if asked to
+    let decoding = if restore_cover {
+        decode_with_any(&sealings, |sealing| {
+            output_file.extract_and_restore_sealed(sealing)
+        })
+    } else {
+        decode_with_any(&sealings, |sealing| output_file.decode_sealed(sealing))
+    }
+    .and_then(write_message);
 
     // Check success
     match decoding {
-        Ok(_) => {
+        Ok(report) => {
             println!("Decoding.....SUCCESS");
+            println!("Signature....{}", report);
         }
         Err(error) => {
             println!("Decoding.....ERROR : {}", error);
+            return;
+        }
+    }
+
+    // Then write restored cover
+    if restore_cover {
+        match output_file.write_to_file(&args[6]) {
+            Ok(_) => {
+                println!("Restoring....SUCCESS");
+            }
+            Err(error) => {
+                println!("Restoring....ERROR : {}", error);
+            }
+        }
+    }
+}
+
+/// Sub main, for key pair generation
+fn main_sub_keygen(args: &[String]) {
+    // Check input arguments number
+    let signing = args.len() == 5 && args[4] == "--signing";
+    if args.len() != 4 && !signing {
+        print_usage(Usage::KEYGEN);
+        return;
+    }
+
+    // Signing keys are Ed25519 ones, other keys X25519 ones
+    let keypair = if signing {
+        sealing::random_key().map(|key| (key, signature::get_public_key(&key)))
+    } else {
+        sealing::generate_keypair()
+    };
+
+    let writing = keypair.and_then(|(secret_key, public_key)| {
+        fs::write(&args[2], sealing::to_hex(&secret_key) + "\n")?;
+        fs::write(&args[3], sealing::to_hex(&public_key) + "\n")?;
+        Ok(public_key)
+    });
+
+    match writing {
+        Ok(public_key) => {
+            println!("Keygen.......SUCCESS");
+            println!("Public key : {}", sealing::to_hex(&public_key));
+            if signing {
+                println!("Fingerprint : {}", signature::get_fingerprint(&public_key));
+            }
+            process::exit(0);
+        }
+        Err(error) => {
+            println!("Keygen.......ERROR : {}", error);
+        }
+    }
+}
+
+/// Sub main, for splitting a message into shares hidden in several pictures
+fn main_sub_split(args: &[String]) {
+    // Check input arguments number, each share taking an input and an output picture
+    let mut args = args.to_vec();
+    let counts = (
+        take_option(&mut args, "--threshold").map(|threshold| threshold.parse::<u8>()),
+        take_option(&mut args, "--shares").map(|shares| shares.parse::<u8>()),
+    );
+    let (threshold, shares) = match counts {
+        (Some(Ok(threshold)), Some(Ok(shares))) if args.len() == 4 + 2 * shares as usize => {
+            (threshold, shares)
+        }
+        _ => {
+            print_usage(Usage::SPLIT);
+            return;
+        }
+    };
+
+    // Message is encrypted, then split, each share being encrypted again in its picture
+    let password = &args[3];
+    let splitting = fs::read_to_string(&args[2])
+        .and_then(|message| PasswordSealing::new(password).seal(&message))
+        .and_then(|encrypted_message| shamir::split(&encrypted_message, threshold, shares));
+    let shares = match splitting {
+        Ok(shares) => shares,
+        Err(error) => {
+            println!("Splitting....ERROR : {}", error);
+            return;
+        }
+    };
+    println!("Splitting....SUCCESS");
+
+    for (share, paths) in shares.iter().zip(args[4..].chunks(2)) {
+        let mut input_file = DotMatrix::new(&paths[0]);
+        let encoding = input_file
+            .encode(&share.to_string(), password)
+            .and_then(|_| input_file.write_to_file(&paths[1]));
+
+        match encoding {
+            Ok(_) => println!("Share {}......SUCCESS : {}", share.index, paths[1]),
+            Err(error) => {
+                println!("Share {}......ERROR : {}", share.index, error);
+                return;
+            }
         }
     }
+    process::exit(0);
+}
+
+/// Sub main, for combining shares hidden in several pictures
+fn main_sub_combine(args: &[String]) {
+    // Check input arguments number
+    if args.len() < 5 {
+        print_usage(Usage::COMBINE);
+        return;
+    }
+
+    // Pictures which hold no valid share are reported, then left aside
+    let password = &args[3];
+    let mut shares = Vec::new();
+    for path in &args[4..] {
+        let decoding = DotMatrix::new(path)
+            .decode(password)
+            .and_then(|share| share.parse::<Share>());
+
+        match decoding {
+            Ok(share) => {
+                println!("Share {}......SUCCESS : {}", share.index, path);
+                shares.push(share);
+            }
+            Err(error) => println!("Share........ERROR : {} : {}", path, error),
+        }
+    }
+
+    let combining = shamir::combine(&shares)
+        .and_then(|encrypted_message| PasswordSealing::new(password).open(&encrypted_message))
+        .and_then(|message| fs::write(&args[2], message));
+    match combining {
+        Ok(_) => {
+            println!("Combining....SUCCESS");
+            process::exit(0);
+        }
+        Err(error) => println!("Combining....ERROR : {}", error),
+    }
+}
+
+/// Sub main, for spreading a message over several pictures, along with parity chunks
+fn main_sub_spread(args: &[String]) {
+    // Check input arguments number, each chunk taking an input and an output picture
+    let mut args = args.to_vec();
+    let parity_count = match take_option(&mut args, "--parity").map(|count| count.parse::<u8>()) {
+        Some(Ok(count)) if args.len() >= 6 && args.len().is_multiple_of(2) => count,
+        _ => {
+            print_usage(Usage::SPREAD);
+            return;
+        }
+    };
+    let data_count = ((args.len() - 4) / 2).saturating_sub(parity_count as usize);
+
+    // Message is encrypted, then spread, each chunk being encrypted again in its picture
+    let password = &args[3];
+    let spreading = u8::try_from(data_count)
+        .map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Payload takes 1 to 255 pictures, parity ones included",
+            )
+        })
+        .and_then(|data_count| {
+            fs::read_to_string(&args[2])
+                .and_then(|message| PasswordSealing::new(password).seal(&message))
+                .and_then(|encrypted_message| {
+                    parity::spread(&encrypted_message, data_count, parity_count)
+                })
+        });
+    let chunks = match spreading {
+        Ok(chunks) => chunks,
+        Err(error) => {
+            println!("Spreading....ERROR : {}", error);
+            return;
+        }
+    };
+    println!("Spreading....SUCCESS");
+
+    for (chunk, paths) in chunks.iter().zip(args[4..].chunks(2)) {
+        let mut input_file = DotMatrix::new(&paths[0]);
+        let encoding = input_file
+            .encode(&chunk.to_string(), password)
+            .and_then(|_| input_file.write_to_file(&paths[1]));
+
+        match encoding {
+            Ok(_) => println!("Chunk {}......SUCCESS : {}", chunk.index, paths[1]),
+            Err(error) => {
+                println!("Chunk {}......ERROR : {}", chunk.index, error);
+                return;
+            }
+        }
+    }
+    process::exit(0);
+}
+
+/// Sub main, for gathering a message spread over several pictures
+fn main_sub_gather(args: &[String]) {
+    // Check input arguments number
+    if args.len() < 5 {
+        print_usage(Usage::GATHER);
+        return;
+    }
+
+    // Lost or damaged pictures are reported, then left aside
+    let password = &args[3];
+    let mut chunks = Vec::new();
+    for path in &args[4..] {
+        let decoding = DotMatrix::new(path)
+            .decode(password)
+            .and_then(|chunk| chunk.parse::<Chunk>());
+
+        match decoding {
+            Ok(chunk) => {
+                println!("Chunk {}......SUCCESS : {}", chunk.index, path);
+                chunks.push(chunk);
+            }
+            Err(error) => println!("Chunk........ERROR : {} : {}", path, error),
+        }
+    }
+
+    let gathering = parity::gather(&chunks).and_then(|(encrypted_message, rebuilt)| {
+        let message = PasswordSealing::new(password).open(&encrypted_message)?;
+        fs::write(&args[2], message)?;
+        Ok(rebuilt)
+    });
+    match gathering {
+        Ok(rebuilt) => {
+            for index in rebuilt {
+                println!("Chunk {}......REBUILT", index);
+            }
+            println!("Gathering....SUCCESS");
+            process::exit(0);
+        }
+        Err(error) => println!("Gathering....ERROR : {}", error),
+    }
+}
+
+/// Sub main, for slotted containers : appending, listing, replacing or deleting messages
+fn main_sub_container(args: &[String]) {
+    // Check input arguments number
+    let command = args[1].to_uppercase();
+    let expected = match command.as_str() {
+        "APPEND" => 6,
+        "LIST" => 4,
+        "REPLACE" => 7,
+        _ => 6,
+    };
+    if args.len() != expected {
+        print_usage(Usage::CONTAINER);
+        return;
+    }
+
+    let mut input_file = DotMatrix::new(&args[2]);
+    if command == "LIST" {
+        match input_file.list_messages(&args[3]) {
+            Ok(entries) => {
+                println!("Listing......SUCCESS");
+                for entry in entries {
+                    let state = match (entry.used, entry.owned) {
+                        (true, true) => "yours",
+                        (true, false) => "in use",
+                        _ => "free",
+                    };
+                    println!("Slot {}.......{}", entry.index, state);
+                }
+                process::exit(0);
+            }
+            Err(error) => println!("Listing......ERROR : {}", error),
+        }
+        return;
+    }
+
+    // Other commands write the picture back
+    let password = &args[4];
+    let slot = args.get(5).map(|slot| slot.parse::<usize>());
+    let updating = match (command.as_str(), slot) {
+        ("APPEND", _) => fs::read_to_string(&args[5])
+            .and_then(|message| input_file.append_message(&message, password))
+            .map(|index| println!("Slot {}.......APPENDED", index)),
+        (_, Some(Err(error))) => Err(Error::new(ErrorKind::InvalidInput, error)),
+        ("REPLACE", Some(Ok(index))) => fs::read_to_string(&args[6])
+            .and_then(|message| input_file.replace_message(index, &message, password)),
+        (_, Some(Ok(index))) => input_file.delete_message(index, password),
+        _ => Ok(()),
+    };
+    print_encoding_and_write(updating, || input_file.write_to_file(&args[3]));
+}
+
+/// Sub main, for wiping any payload out of a picture
+fn main_sub_wipe(args: &[String]) {
+    // Check input arguments number
+    let mut args = args.to_vec();
+    let low_bits = match take_option(&mut args, "--low-bits").map(|low_bits| low_bits.parse::<u8>())
+    {
+        None => None,
+        Some(Ok(low_bits)) => Some(low_bits),
+        Some(Err(error)) => {
+            println!("Wiping.......ERROR : {}", error);
+            return;
+        }
+    };
+    if args.len() != 3 && args.len() != 4 {
+        print_usage(Usage::WIPE);
+        return;
+    }
+
+    // Each carrier is wiped where encode hides bits, and written back in its own format
+    let options = WipeOptions { low_bits };
+    let output_filepath = args.get(3).unwrap_or(&args[2]);
+    let wiping = if JpegMatrix::is_jpeg_file(&args[2]) {
+        let mut input_file = JpegMatrix::new(&args[2]);
+        input_file
+            .wipe(&options)
+            .and_then(|_| input_file.write_to_file(output_filepath))
+    } else if AnimationMatrix::is_animation_file(&args[2]) {
+        let mut input_file = AnimationMatrix::new(&args[2]);
+        input_file
+            .wipe(&options)
+            .and_then(|_| input_file.write_to_file(output_filepath))
+    } else if WavCarrier::is_wav_file(&args[2]) {
+        let mut input_file = WavCarrier::new(&args[2]);
+        input_file
+            .wipe(&options)
+            .and_then(|_| input_file.write_to_file(output_filepath))
+    } else {
+        let mut input_file = DotMatrix::new(&args[2]);
+        input_file
+            .wipe(&options)
+            .and_then(|_| input_file.write_to_file(output_filepath))
+    };
+
+    match wiping {
+        Ok(_) => {
+            println!("Wiping.......SUCCESS");
+            process::exit(0);
+        }
+        Err(error) => println!("Wiping.......ERROR : {}", error),
+    }
+}
+
+/// Sub main, for sanitizing incoming pictures
+fn main_sub_sanitize(args: &[String]) {
+    // Check input arguments number
+    if args.len() != 4 {
+        print_usage(Usage::SANITIZE);
+        return;
+    }
+
+    let mut input_file = DotMatrix::new(&args[2]);
+    let sanitizing = input_file.sanitize().and_then(|report| {
+        input_file.write_to_file(&args[3])?;
+        Ok(report)
+    });
+
+    match sanitizing {
+        Ok(report) => {
+            println!("Sanitizing...SUCCESS");
+            println!("PSNR.........{:.2} dB", report.psnr);
+            if !report.stripped_chunks.is_empty() {
+                println!("Stripped.....{}", report.stripped_chunks.join(", "));
+            }
+            if report.appended_bytes > 0 {
+                println!("Stripped.....{} appended bytes", report.appended_bytes);
+            }
+            process::exit(0);
+        }
+        Err(error) => println!("Sanitizing...ERROR : {}", error),
+    }
+}
+
+/// Sub main, for steganalysis of a picture
+fn main_sub_analyze(args: &[String]) {
+    // Check input arguments number
+    let mut args = args.to_vec();
+    let block_rows = take_option(&mut args, "--block-rows").map(|rows| rows.parse::<usize>());
+    let method = args
+        .get(2)
+        .map(|method| method.to_uppercase())
+        .unwrap_or_default();
+    if args.len() != 4 || !["CHI2", "RS", "SPA"].contains(&method.as_str()) {
+        print_usage(Usage::ANALYZE);
+        return;
+    }
+
+    let picture = match Picture::open(&args[3]) {
+        Ok(picture) => picture,
+        Err(error) => {
+            println!("Analyzing....ERROR : {}", error);
+            return;
+        }
+    };
+    // Embedding rate estimators work on 8 bits RGB values
+    if method != "CHI2" {
+        let rates = picture
+            .to_dynamic_image()
+            .map(|image| match method.as_str() {
+                "RS" => steganalysis::rs_analysis(&image),
+                _ => steganalysis::sample_pair_analysis(&image),
+            });
+
+        match rates {
+            Ok(rates) => {
+                println!("Analyzing....SUCCESS");
+                for (name, rate) in steganalysis::RGB_CHANNEL_NAMES.iter().zip(rates) {
+                    println!(
+                        "Channel {:<6}: {:.2} % estimated embedding rate",
+                        name,
+                        rate * 100.0
+                    );
+                }
+                process::exit(0);
+            }
+            Err(error) => println!("Analyzing....ERROR : {}", error),
+        }
+        return;
+    }
+
+    let block_rows = match block_rows {
+        None => (picture.dimensions().1 as usize / 16).max(1),
+        Some(Ok(rows)) if rows > 0 => rows,
+        _ => {
+            print_usage(Usage::ANALYZE);
+            return;
+        }
+    };
+
+    // Probability of embedding per channel, then over the first rows
+    let height = picture.dimensions().1 as usize;
+    println!("Analyzing....SUCCESS");
+    let names = steganalysis::get_channel_names(picture.get_color_type());
+    for (name, result) in names
+        .iter()
+        .zip(steganalysis::chi_square(&picture, block_rows))
+    {
+        println!(
+            "Channel {:<6}: {:.2} % embedding probability",
+            name,
+            result.probability * 100.0
+        );
+        for (block, probability) in result.curve.iter().enumerate() {
+            println!(
+                "  rows 0 to {:<6}: {:.2} %",
+                ((block + 1) * block_rows).min(height) - 1,
+                probability * 100.0
+            );
+        }
+    }
+    process::exit(0);
+}
+
+/// Sub main, for rendering a bit plane, or the difference between a cover and a stego picture
+fn main_sub_visualize(args: &[String]) {
+    // Check input arguments number
+    let mut args = args.to_vec();
+    let plane = take_option(&mut args, "--plane").map(|plane| plane.parse::<u8>());
+    let channel =
+        take_option(&mut args, "--channel").map(|channel| match channel.to_uppercase().as_str() {
+            "R" | "RED" | "GRAY" => Ok(0),
+            "G" | "GREEN" => Ok(1),
+            "B" | "BLUE" => Ok(2),
+            _ => channel.parse::<usize>(),
+        });
+    let cover = take_option(&mut args, "--difference");
+    let amplification =
+        take_option(&mut args, "--amplification").map(|factor| factor.parse::<u32>());
+    if args.len() != 4 {
+        print_usage(Usage::VISUALIZE);
+        return;
+    }
+
+    let input_file = DotMatrix::new(&args[2]);
+    let rendering = match (cover, plane, channel, amplification) {
+        (Some(cover), None, None, None) => input_file.get_difference(&DotMatrix::new(&cover), 64),
+        (Some(cover), None, None, Some(Ok(factor))) => {
+            input_file.get_difference(&DotMatrix::new(&cover), factor)
+        }
+        (None, plane, channel, None) => match (plane.unwrap_or(Ok(0)), channel.unwrap_or(Ok(0))) {
+            (Ok(plane), Ok(channel)) => input_file.get_bit_plane(plane, channel),
+            _ => {
+                print_usage(Usage::VISUALIZE);
+                return;
+            }
+        },
+        _ => {
+            print_usage(Usage::VISUALIZE);
+            return;
+        }
+    };
+
+    match rendering.and_then(|picture| picture.save(&args[3])) {
+        Ok(_) => {
+            println!("Rendering....SUCCESS");
+            process::exit(0);
+        }
+        Err(error) => println!("Rendering....ERROR : {}", error),
+    }
+}
+
+/// Sub main, for measuring distortion between a cover and a stego picture
+fn main_sub_compare(args: &[String]) {
+    // Check input arguments number
+    if args.len() != 4 {
+        print_usage(Usage::COMPARE);
+        return;
+    }
+
+    let comparison = Picture::open(&args[2]).and_then(|cover| {
+        Picture::open(&args[3]).and_then(|stego| quality::compare(&cover, &stego))
+    });
+    match comparison {
+        Ok(comparison) => {
+            println!("Comparing....SUCCESS");
+            println!("PSNR          : {:.2} dB", comparison.psnr);
+            println!("SSIM          : {:.6}", comparison.ssim);
+            println!("MSE           : {:.6}", comparison.mse);
+            println!(
+                "Pixels        : {} / {} changed ({:.2} %)",
+                comparison.changed_pixels.0,
+                comparison.changed_pixels.1,
+                comparison.get_changed_pixels_percentage()
+            );
+            println!(
+                "Values        : {} / {} changed ({:.2} %)",
+                comparison.changed_values.0,
+                comparison.changed_values.1,
+                comparison.get_changed_values_percentage()
+            );
+            println!(
+                "Histogram     : {} values moved between bins",
+                comparison.get_histogram_moves()
+            );
+            process::exit(0);
+        }
+        Err(error) => println!("Comparing....ERROR : {}", error),
+    }
 }
 
 /// MAIN
@@ -108,6 +1150,39 @@ fn main() {
         "DECODE" => {
             main_sub_decode(&args);
         }
+        "KEYGEN" => {
+            main_sub_keygen(&args);
+        }
+        "SPLIT" => {
+            main_sub_split(&args);
+        }
+        "COMBINE" => {
+            main_sub_combine(&args);
+        }
+        "SPREAD" => {
+            main_sub_spread(&args);
+        }
+        "GATHER" => {
+            main_sub_gather(&args);
+        }
+        "APPEND" | "LIST" | "REPLACE" | "DELETE" => {
+            main_sub_container(&args);
+        }
+        "WIPE" => {
+            main_sub_wipe(&args);
+        }
+        "SANITIZE" => {
+            main_sub_sanitize(&args);
+        }
+        "ANALYZE" => {
+            main_sub_analyze(&args);
+        }
+        "VISUALIZE" => {
+            main_sub_visualize(&args);
+        }
+        "COMPARE" => {
+            main_sub_compare(&args);
+        }
         _ => {
             print_usage(Usage::FULL);
         }